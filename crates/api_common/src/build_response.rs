@@ -130,7 +130,7 @@ pub async fn send_local_notifs(
       // Send an email to those local users that have notifications on
       if do_send_email {
         let lang = get_interface_language(&mention_user_view);
-        let content = markdown_to_html(&comment.content);
+        let content = markdown_to_html(&comment.content, &context.settings().html);
         send_email_to_user(
           &mention_user_view,
           &lang.notification_mentioned_by_subject(&person.name),
@@ -179,7 +179,7 @@ pub async fn send_local_notifs(
 
         if do_send_email {
           let lang = get_interface_language(&parent_user_view);
-          let content = markdown_to_html(&comment.content);
+          let content = markdown_to_html(&comment.content, &context.settings().html);
           send_email_to_user(
             &parent_user_view,
             &lang.notification_comment_reply_subject(&person.name),
@@ -221,7 +221,7 @@ pub async fn send_local_notifs(
 
         if do_send_email {
           let lang = get_interface_language(&parent_user_view);
-          let content = markdown_to_html(&comment.content);
+          let content = markdown_to_html(&comment.content, &context.settings().html);
           send_email_to_user(
             &parent_user_view,
             &lang.notification_post_reply_subject(&person.name),
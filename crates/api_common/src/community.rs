@@ -147,6 +147,20 @@ pub struct EditCommunity {
   /// Whether to restrict posting only to moderators.
   pub posting_restricted_to_mods: Option<bool>,
   pub discussion_languages: Option<Vec<LanguageId>>,
+  /// Posts whose title or body contain one of these words are marked as NSFW.
+  pub nsfw_keywords: Option<Vec<String>>,
+  /// Maximum number of posts per remote user within the rate limit interval. Set to 0 to use the
+  /// instance default.
+  pub post_rate_limit: Option<i32>,
+  /// Reject posts from other instances.
+  pub local_only: Option<bool>,
+  /// Reject posts linking to these domains or their subdomains.
+  pub blocked_url_domains: Option<Vec<String>>,
+  /// Only accept link posts to this host or its subdomains. Set to an empty string to remove the
+  /// restriction.
+  pub required_url_host: Option<String>,
+  /// Reject new posts once the community has this many posts. Set to 0 to remove the limit.
+  pub max_posts: Option<i32>,
 }
 
 #[skip_serializing_none]
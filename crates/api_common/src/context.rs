@@ -1,3 +1,8 @@
+use crate::{
+  spam::{AcceptAll, SpamClassifier},
+  thumbnail::{PictrsThumbnails, ThumbnailGenerator},
+  url_blocklist::{DomainBlocklist, UrlBlocklist},
+};
 use lemmy_db_schema::{
   source::secret::Secret,
   utils::{ActualDbPool, DbPool},
@@ -15,6 +20,10 @@ pub struct LemmyContext {
   client: Arc<ClientWithMiddleware>,
   secret: Arc<Secret>,
   rate_limit_cell: RateLimitCell,
+  spam_classifier: Arc<dyn SpamClassifier>,
+  thumbnail_generator: Arc<dyn ThumbnailGenerator>,
+  url_blocklist: Arc<dyn UrlBlocklist>,
+  settings: &'static Settings,
 }
 
 impl LemmyContext {
@@ -29,8 +38,35 @@ impl LemmyContext {
       client: Arc::new(client),
       secret: Arc::new(secret),
       rate_limit_cell,
+      spam_classifier: Arc::new(AcceptAll),
+      thumbnail_generator: Arc::new(PictrsThumbnails),
+      url_blocklist: Arc::new(DomainBlocklist::new(&SETTINGS.federation.malicious_domains)),
+      settings: &SETTINGS,
     }
   }
+  /// Replaces the default spam classifier, which accepts everything.
+  pub fn with_spam_classifier(mut self, spam_classifier: Arc<dyn SpamClassifier>) -> LemmyContext {
+    self.spam_classifier = spam_classifier;
+    self
+  }
+  /// Replaces the default thumbnail generator, which uses pictrs.
+  pub fn with_thumbnail_generator(
+    mut self,
+    thumbnail_generator: Arc<dyn ThumbnailGenerator>,
+  ) -> LemmyContext {
+    self.thumbnail_generator = thumbnail_generator;
+    self
+  }
+  /// Replaces the default url blocklist, which uses the domains from the config.
+  pub fn with_url_blocklist(mut self, url_blocklist: Arc<dyn UrlBlocklist>) -> LemmyContext {
+    self.url_blocklist = url_blocklist;
+    self
+  }
+  /// Replaces the settings, which are read from the config file by default.
+  pub fn with_settings(mut self, settings: &'static Settings) -> LemmyContext {
+    self.settings = settings;
+    self
+  }
   pub fn pool(&self) -> DbPool<'_> {
     DbPool::Pool(&self.pool)
   }
@@ -41,7 +77,7 @@ impl LemmyContext {
     &self.client
   }
   pub fn settings(&self) -> &'static Settings {
-    &SETTINGS
+    self.settings
   }
   pub fn secret(&self) -> &Secret {
     &self.secret
@@ -49,4 +85,13 @@ impl LemmyContext {
   pub fn rate_limit_cell(&self) -> &RateLimitCell {
     &self.rate_limit_cell
   }
+  pub fn spam_classifier(&self) -> &dyn SpamClassifier {
+    self.spam_classifier.as_ref()
+  }
+  pub fn thumbnail_generator(&self) -> &dyn ThumbnailGenerator {
+    self.thumbnail_generator.as_ref()
+  }
+  pub fn url_blocklist(&self) -> &dyn UrlBlocklist {
+    self.url_blocklist.as_ref()
+  }
 }
@@ -17,6 +17,12 @@ pub mod send_activity;
 pub mod sensitive;
 pub mod site;
 #[cfg(feature = "full")]
+pub mod spam;
+#[cfg(feature = "full")]
+pub mod thumbnail;
+#[cfg(feature = "full")]
+pub mod url_blocklist;
+#[cfg(feature = "full")]
 pub mod utils;
 
 pub extern crate lemmy_db_schema;
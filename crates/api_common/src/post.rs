@@ -251,6 +251,9 @@ pub struct SiteMetadata {
   pub description: Option<String>,
   pub(crate) image: Option<DbUrl>,
   pub embed_video_url: Option<DbUrl>,
+  /// The canonical link of the page, from `rel=canonical` or the redirect target
+  #[serde(skip)]
+  pub canonical_url: Option<DbUrl>,
 }
 
 #[skip_serializing_none]
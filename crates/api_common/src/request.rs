@@ -1,4 +1,4 @@
-use crate::{context::LemmyContext, post::SiteMetadata};
+use crate::{context::LemmyContext, post::SiteMetadata, thumbnail::Thumbnail};
 use encoding::{all::encodings, DecoderTrap};
 use lemmy_db_schema::newtypes::DbUrl;
 use lemmy_utils::{
@@ -23,12 +23,17 @@ pub async fn fetch_site_metadata(
 ) -> Result<SiteMetadata, LemmyError> {
   info!("Fetching site metadata for url: {}", url);
   let response = client.get(url.as_str()).send().await?;
+  let final_url = response.url().clone();
 
   // Can't use .text() here, because it only checks the content header, not the actual bytes
   // https://github.com/LemmyNet/lemmy/issues/1964
   let html_bytes = response.bytes().await.map_err(LemmyError::from)?.to_vec();
 
-  let tags = html_to_site_metadata(&html_bytes, url)?;
+  let mut tags = html_to_site_metadata(&html_bytes, url)?;
+  // Shortlinks redirect to the actual article
+  if tags.canonical_url.is_none() && &final_url != url {
+    tags.canonical_url = Some(final_url.into());
+  }
 
   Ok(tags)
 }
@@ -63,6 +68,11 @@ fn html_to_site_metadata(html_bytes: &[u8], url: &Url) -> Result<SiteMetadata, L
     }
   }
 
+  let canonical_url = page
+    .url
+    .as_ref()
+    .and_then(|c| url.join(c).ok())
+    .filter(|c| c != url);
   let page_title = page.title;
   let page_description = page.description;
 
@@ -94,20 +104,27 @@ fn html_to_site_metadata(html_bytes: &[u8], url: &Url) -> Result<SiteMetadata, L
     description: og_description.or(page_description),
     image: og_image.map(Into::into),
     embed_video_url: og_embed_url.map(Into::into),
+    canonical_url: canonical_url.map(Into::into),
   })
 }
 
 #[derive(Deserialize, Debug, Clone)]
 pub(crate) struct PictrsResponse {
-  files: Vec<PictrsFile>,
+  pub(crate) files: Vec<PictrsFile>,
   msg: String,
 }
 
 #[derive(Deserialize, Debug, Clone)]
 pub(crate) struct PictrsFile {
-  file: String,
+  pub(crate) file: String,
   #[allow(dead_code)]
   delete_token: String,
+  pub(crate) details: Option<PictrsFileDetails>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub(crate) struct PictrsFileDetails {
+  pub(crate) blurhash: Option<String>,
 }
 
 #[derive(Deserialize, Debug, Clone)]
@@ -218,61 +235,40 @@ pub async fn delete_image_from_pictrs(
 }
 
 /// Both are options, since the URL might be either an html page, or an image
-/// Returns the SiteMetadata, and an image URL, if there is a picture associated
+/// Returns the SiteMetadata, and a thumbnail, if there is a picture associated
 #[tracing::instrument(skip_all)]
 pub async fn fetch_site_data(
-  client: &ClientWithMiddleware,
-  settings: &Settings,
+  context: &LemmyContext,
   url: Option<&Url>,
   include_image: bool,
-) -> (Option<SiteMetadata>, Option<DbUrl>) {
+) -> (Option<SiteMetadata>, Option<Thumbnail>) {
   match &url {
     Some(url) => {
       // Fetch metadata
       // Ignore errors, since it may be an image, or not have the data.
       // Warning, this may ignore SSL errors
-      let metadata_option = fetch_site_metadata(client, url).await.ok();
+      let metadata_option = fetch_site_metadata(context.client(), url).await.ok();
       if !include_image {
         (metadata_option, None)
       } else {
-        let thumbnail_url =
-          fetch_pictrs_url_from_site_metadata(client, &metadata_option, settings, url)
-            .await
-            .ok();
-        (metadata_option, thumbnail_url)
+        // Try to generate a small thumbnail if there's a full sized one from post-links,
+        // otherwise try to fetch the URL as an image
+        let image_url = metadata_option
+          .as_ref()
+          .and_then(|m| m.image.as_ref())
+          .map(DbUrl::inner)
+          .unwrap_or(url);
+        let thumbnail = context
+          .thumbnail_generator()
+          .generate(image_url, context)
+          .await;
+        (metadata_option, thumbnail)
       }
     }
     None => (None, None),
   }
 }
 
-async fn fetch_pictrs_url_from_site_metadata(
-  client: &ClientWithMiddleware,
-  metadata_option: &Option<SiteMetadata>,
-  settings: &Settings,
-  url: &Url,
-) -> Result<DbUrl, LemmyError> {
-  let pictrs_res = match metadata_option {
-    Some(metadata_res) => match &metadata_res.image {
-      // Metadata, with image
-      // Try to generate a small thumbnail if there's a full sized one from post-links
-      Some(metadata_image) => fetch_pictrs(client, settings, metadata_image).await,
-      // Metadata, but no image
-      None => fetch_pictrs(client, settings, url).await,
-    },
-    // No metadata, try to fetch the URL as an image
-    None => fetch_pictrs(client, settings, url).await,
-  }?;
-
-  Url::parse(&format!(
-    "{}/pictrs/image/{}",
-    settings.get_protocol_and_hostname(),
-    pictrs_res.files.first().expect("missing pictrs file").file
-  ))
-  .map(Into::into)
-  .map_err(Into::into)
-}
-
 #[tracing::instrument(skip_all)]
 async fn is_image_content_type(client: &ClientWithMiddleware, url: &Url) -> Result<(), LemmyError> {
   let response = client.get(url.as_str()).send().await?;
@@ -290,16 +286,24 @@ async fn is_image_content_type(client: &ClientWithMiddleware, url: &Url) -> Resu
 }
 
 pub fn client_builder(settings: &Settings) -> ClientBuilder {
-  let user_agent = format!(
+  Client::builder()
+    .user_agent(user_agent(settings))
+    .timeout(REQWEST_TIMEOUT)
+    .connect_timeout(REQWEST_TIMEOUT)
+}
+
+/// Identifies the instance to remote servers, some of which block requests without a descriptive
+/// User-Agent.
+fn user_agent(settings: &Settings) -> String {
+  let mut user_agent = format!(
     "Lemmy/{}; +{}",
     VERSION,
     settings.get_protocol_and_hostname()
   );
-
-  Client::builder()
-    .user_agent(user_agent)
-    .timeout(REQWEST_TIMEOUT)
-    .connect_timeout(REQWEST_TIMEOUT)
+  if let Some(contact_url) = &settings.federation.contact_url {
+    user_agent.push_str(&format!("; contact: {contact_url}"));
+  }
+  user_agent
 }
 
 #[cfg(test)]
@@ -307,11 +311,55 @@ mod tests {
   #![allow(clippy::unwrap_used)]
   #![allow(clippy::indexing_slicing)]
 
-  use crate::request::{client_builder, fetch_site_metadata, html_to_site_metadata, SiteMetadata};
+  use crate::request::{
+    client_builder,
+    fetch_site_metadata,
+    html_to_site_metadata,
+    SiteMetadata,
+    VERSION,
+  };
   use lemmy_utils::settings::SETTINGS;
   use pretty_assertions::assert_eq;
+  use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpListener,
+  };
   use url::Url;
 
+  #[tokio::test]
+  async fn test_user_agent_header() {
+    let mut settings = SETTINGS.clone();
+    settings.federation.contact_url = Some("mailto:admin@example.com".to_string());
+    let client = client_builder(&settings).no_proxy().build().unwrap();
+
+    // Minimal http server which returns the received request
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = tokio::spawn(async move {
+      let (mut socket, _) = listener.accept().await.unwrap();
+      let mut buf = vec![0; 4096];
+      let len = socket.read(&mut buf).await.unwrap();
+      socket
+        .write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 0\r\n\r\n")
+        .await
+        .unwrap();
+      String::from_utf8_lossy(&buf[..len]).to_lowercase()
+    });
+
+    client
+      .get(format!("http://{addr}/u/picard"))
+      .send()
+      .await
+      .unwrap();
+    let request = server.await.unwrap();
+    let expected = format!(
+      "user-agent: lemmy/{}; +{}; contact: mailto:admin@example.com",
+      VERSION,
+      settings.get_protocol_and_hostname()
+    );
+    assert!(request.contains(&expected), "{request}");
+  }
+
   // These helped with testing
   #[tokio::test]
   async fn test_site_metadata() {
@@ -331,6 +379,7 @@ mod tests {
             .into()
         ),
         embed_video_url: None,
+        canonical_url: None,
       },
       sample_res
     );
@@ -344,6 +393,22 @@ mod tests {
   //   assert!(res_other.is_err());
   // }
 
+  #[test]
+  fn test_canonical_url() {
+    let url = Url::parse("https://example.com/amp/article").unwrap();
+    let html_bytes = b"<!DOCTYPE html><html><head><link rel='canonical' href='/article'></head><body></body></html>";
+    let metadata = html_to_site_metadata(html_bytes, &url).expect("Unable to parse metadata");
+    assert_eq!(
+      metadata.canonical_url,
+      Some(Url::parse("https://example.com/article").unwrap().into())
+    );
+
+    // pages which declare themselves as canonical don't need a separate link
+    let url = Url::parse("https://example.com/article").unwrap();
+    let metadata = html_to_site_metadata(html_bytes, &url).expect("Unable to parse metadata");
+    assert_eq!(metadata.canonical_url, None);
+  }
+
   #[test]
   fn test_resolve_image_url() {
     // url that lists the opengraph fields
@@ -0,0 +1,25 @@
+use url::Url;
+
+/// Content of a received post which is checked for spam.
+pub struct SpamCheckPost<'a> {
+  pub ap_id: &'a Url,
+  pub name: Option<&'a str>,
+  pub body: Option<&'a str>,
+  pub url: Option<&'a Url>,
+}
+
+/// Extension point to run received posts through a spam classifier, which is set in
+/// [`LemmyContext`](crate::context::LemmyContext).
+pub trait SpamClassifier: Send + Sync {
+  /// Returns the reason if the post should be rejected as spam.
+  fn check_post(&self, post: &SpamCheckPost) -> Option<String>;
+}
+
+/// Default classifier which accepts all posts.
+pub struct AcceptAll;
+
+impl SpamClassifier for AcceptAll {
+  fn check_post(&self, _post: &SpamCheckPost) -> Option<String> {
+    None
+  }
+}
@@ -0,0 +1,51 @@
+use crate::{context::LemmyContext, request::fetch_pictrs};
+use lemmy_db_schema::newtypes::DbUrl;
+use url::Url;
+
+/// Extension point to store thumbnails of post links in a media backend, which is set in
+/// [`LemmyContext`](crate::context::LemmyContext).
+#[async_trait::async_trait]
+pub trait ThumbnailGenerator: Send + Sync {
+  /// Stores a thumbnail for the given image and returns it, or `None` if it failed.
+  async fn generate(&self, image_url: &Url, context: &LemmyContext) -> Option<Thumbnail>;
+}
+
+/// A stored thumbnail.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Thumbnail {
+  pub url: DbUrl,
+  /// Blurred placeholder of the image, if the media backend computes one.
+  pub blurhash: Option<String>,
+}
+
+impl From<DbUrl> for Thumbnail {
+  fn from(url: DbUrl) -> Self {
+    Thumbnail {
+      url,
+      blurhash: None,
+    }
+  }
+}
+
+/// Default generator which stores thumbnails in pictrs.
+pub struct PictrsThumbnails;
+
+#[async_trait::async_trait]
+impl ThumbnailGenerator for PictrsThumbnails {
+  async fn generate(&self, image_url: &Url, context: &LemmyContext) -> Option<Thumbnail> {
+    let pictrs_res = fetch_pictrs(context.client(), context.settings(), image_url)
+      .await
+      .ok()?;
+    let file = pictrs_res.files.first()?;
+    let url = Url::parse(&format!(
+      "{}/pictrs/image/{}",
+      context.settings().get_protocol_and_hostname(),
+      file.file
+    ))
+    .ok()?;
+    Some(Thumbnail {
+      url: url.into(),
+      blurhash: file.details.as_ref().and_then(|d| d.blurhash.clone()),
+    })
+  }
+}
@@ -0,0 +1,48 @@
+use url::Url;
+
+/// Extension point to reject received posts which link to known malicious sites, for example
+/// using an external phishing list. It is set in [`LemmyContext`](crate::context::LemmyContext).
+pub trait UrlBlocklist: Send + Sync {
+  /// Returns true if the link must not be accepted.
+  fn is_blocked(&self, url: &Url) -> bool;
+}
+
+/// Default blocklist with the domains from `federation.malicious_domains` in the config.
+/// Subdomains of listed domains are blocked as well.
+pub struct DomainBlocklist(Vec<String>);
+
+impl DomainBlocklist {
+  pub fn new(domains: &[String]) -> Self {
+    DomainBlocklist(domains.iter().map(|d| d.trim().to_lowercase()).collect())
+  }
+}
+
+impl UrlBlocklist for DomainBlocklist {
+  fn is_blocked(&self, url: &Url) -> bool {
+    let Some(host) = url.domain().map(str::to_lowercase) else {
+      return false;
+    };
+    self
+      .0
+      .iter()
+      .any(|d| host == *d || host.ends_with(&format!(".{d}")))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  #![allow(clippy::unwrap_used)]
+
+  use super::*;
+
+  #[test]
+  fn test_domain_blocklist() {
+    let blocklist = DomainBlocklist::new(&["Phishing.example".to_string()]);
+    let blocked = |url: &str| blocklist.is_blocked(&Url::parse(url).unwrap());
+
+    assert!(blocked("https://phishing.example/login"));
+    assert!(blocked("https://www.phishing.example/"));
+    assert!(!blocked("https://notphishing.example/"));
+    assert!(!blocked("https://example.com/phishing.example"));
+  }
+}
@@ -67,6 +67,15 @@ pub async fn update_community(
     banner,
     nsfw: data.nsfw,
     posting_restricted_to_mods: data.posting_restricted_to_mods,
+    nsfw_keywords: data.nsfw_keywords.clone(),
+    post_rate_limit: data.post_rate_limit.map(|l| Some(l).filter(|l| *l > 0)),
+    local_only: data.local_only,
+    blocked_url_domains: data.blocked_url_domains.clone(),
+    required_url_host: data
+      .required_url_host
+      .as_ref()
+      .map(|h| Some(h.trim().to_lowercase()).filter(|h| !h.is_empty())),
+    max_posts: data.max_posts.map(|m| Some(m).filter(|m| *m > 0)),
     updated: Some(Some(naive_now())),
     ..Default::default()
   };
@@ -82,8 +82,7 @@ pub async fn create_post(
   }
 
   // Fetch post links and pictrs cached image
-  let (metadata_res, thumbnail_url) =
-    fetch_site_data(context.client(), context.settings(), data_url, true).await;
+  let (metadata_res, thumbnail) = fetch_site_data(&context, data_url, true).await;
   let (embed_title, embed_description, embed_video_url) = metadata_res
     .map(|u| (u.title, u.description, u.embed_video_url))
     .unwrap_or_default();
@@ -121,7 +120,9 @@ pub async fn create_post(
     .embed_description(embed_description)
     .embed_video_url(embed_video_url)
     .language_id(language_id)
-    .thumbnail_url(thumbnail_url)
+    .thumbnail_url(thumbnail.as_ref().map(|t| t.url.clone()))
+    .thumbnail_blurhash(thumbnail.and_then(|t| t.blurhash))
+    .from_bot(Some(local_user_view.person.bot_account))
     .build();
 
   let inserted_post = Post::create(&mut context.pool(), &post_form)
@@ -69,8 +69,7 @@ pub async fn update_post(
 
   // Fetch post links and Pictrs cached image
   let data_url = data.url.as_ref();
-  let (metadata_res, thumbnail_url) =
-    fetch_site_data(context.client(), context.settings(), data_url, true).await;
+  let (metadata_res, thumbnail) = fetch_site_data(&context, data_url, true).await;
   let (embed_title, embed_description, embed_video_url) = metadata_res
     .map(|u| (Some(u.title), Some(u.description), Some(u.embed_video_url)))
     .unwrap_or_default();
@@ -92,7 +91,8 @@ pub async fn update_post(
     embed_description,
     embed_video_url,
     language_id: data.language_id,
-    thumbnail_url: Some(thumbnail_url),
+    thumbnail_url: Some(thumbnail.as_ref().map(|t| t.url.clone())),
+    thumbnail_blurhash: Some(thumbnail.and_then(|t| t.blurhash)),
     updated: Some(Some(naive_now())),
     ..Default::default()
   };
@@ -81,7 +81,7 @@ pub async fn create_private_message(
     let lang = get_interface_language(&local_recipient);
     let inbox_link = format!("{}/inbox", context.settings().get_protocol_and_hostname());
     let sender_name = &local_user_view.person.name;
-    let content = markdown_to_html(&content);
+    let content = markdown_to_html(&content, &context.settings().html);
     send_email_to_user(
       &local_recipient,
       &lang.notification_private_message_subject(sender_name),
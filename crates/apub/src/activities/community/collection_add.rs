@@ -159,14 +159,15 @@ impl ActivityHandler for CollectionAdd {
         // TODO: send websocket notification about added mod
       }
       CollectionType::Featured => {
-        let post = ObjectId::<ApubPost>::from(self.object)
-          .dereference(context)
-          .await?;
-        let form = PostUpdateForm {
-          featured_community: Some(true),
-          ..Default::default()
-        };
-        Post::update(&mut context.pool(), post.id, &form).await?;
+        let post = ObjectId::<ApubPost>::from(self.object);
+        // nothing to change if the post was deleted in the meantime
+        if let Some(post) = ApubPost::resolve(&post, context).await? {
+          let form = PostUpdateForm {
+            featured_community: Some(true),
+            ..Default::default()
+          };
+          Post::update(&mut context.pool(), post.id, &form).await?;
+        }
       }
     }
     Ok(())
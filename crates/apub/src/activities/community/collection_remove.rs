@@ -147,14 +147,15 @@ impl ActivityHandler for CollectionRemove {
         // TODO: send websocket notification about removed mod
       }
       CollectionType::Featured => {
-        let post = ObjectId::<ApubPost>::from(self.object)
-          .dereference(context)
-          .await?;
-        let form = PostUpdateForm {
-          featured_community: Some(false),
-          ..Default::default()
-        };
-        Post::update(&mut context.pool(), post.id, &form).await?;
+        let post = ObjectId::<ApubPost>::from(self.object);
+        // nothing to change if the post was deleted in the meantime
+        if let Some(post) = ApubPost::resolve(&post, context).await? {
+          let form = PostUpdateForm {
+            featured_community: Some(false),
+            ..Default::default()
+          };
+          Post::update(&mut context.pool(), post.id, &form).await?;
+        }
       }
     }
     Ok(())
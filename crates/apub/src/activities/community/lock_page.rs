@@ -9,7 +9,7 @@ use crate::{
   },
   activity_lists::AnnouncableActivities,
   insert_received_activity,
-  objects::community::ApubCommunity,
+  objects::{community::ApubCommunity, post::ApubPost},
   protocol::{
     activities::community::lock_page::{LockPage, LockType, UndoLockPage},
     InCommunity,
@@ -61,8 +61,10 @@ impl ActivityHandler for LockPage {
       locked: Some(true),
       ..Default::default()
     };
-    let post = self.object.dereference(context).await?;
-    Post::update(&mut context.pool(), post.id, &form).await?;
+    // nothing to lock if the post was deleted in the meantime
+    if let Some(post) = ApubPost::resolve(&self.object, context).await? {
+      Post::update(&mut context.pool(), post.id, &form).await?;
+    }
     Ok(())
   }
 }
@@ -95,8 +97,10 @@ impl ActivityHandler for UndoLockPage {
       locked: Some(false),
       ..Default::default()
     };
-    let post = self.object.object.dereference(context).await?;
-    Post::update(&mut context.pool(), post.id, &form).await?;
+    // nothing to lock if the post was deleted in the meantime
+    if let Some(post) = ApubPost::resolve(&self.object.object, context).await? {
+      Post::update(&mut context.pool(), post.id, &form).await?;
+    }
     Ok(())
   }
 }
@@ -9,16 +9,23 @@ use crate::{
   },
   activity_lists::AnnouncableActivities,
   insert_received_activity,
-  objects::{community::ApubCommunity, person::ApubPerson, post::ApubPost},
+  objects::{
+    community::ApubCommunity,
+    person::ApubPerson,
+    post::{is_relay, verify_page, ApubPost, ACTIVITY_PAGE_TYPES},
+    VerifyOrigin,
+  },
   protocol::{
     activities::{create_or_update::page::CreateOrUpdatePage, CreateOrUpdateType},
+    objects::page::Page,
     InCommunity,
   },
 };
 use activitypub_federation::{
   config::Data,
+  fetch::object_id::ObjectId,
   kinds::public,
-  protocol::verification::{verify_domains_match, verify_urls_match},
+  protocol::verification::verify_urls_match,
   traits::{ActivityHandler, Actor, Object},
 };
 use lemmy_api_common::context::LemmyContext;
@@ -43,15 +50,27 @@ impl CreateOrUpdatePage {
     community: &ApubCommunity,
     kind: CreateOrUpdateType,
     context: &Data<LemmyContext>,
+  ) -> Result<CreateOrUpdatePage, LemmyError> {
+    let page = post.into_json(context).await?;
+    CreateOrUpdatePage::from_page(page, actor.id().into(), community, kind, context)
+  }
+
+  /// Wraps an already serialized post, eg from [ApubPost::into_json_batch].
+  pub(crate) fn from_page(
+    page: Page,
+    actor: ObjectId<ApubPerson>,
+    community: &ApubCommunity,
+    kind: CreateOrUpdateType,
+    context: &Data<LemmyContext>,
   ) -> Result<CreateOrUpdatePage, LemmyError> {
     let id = generate_activity_id(
       kind.clone(),
       &context.settings().get_protocol_and_hostname(),
     )?;
     Ok(CreateOrUpdatePage {
-      actor: actor.id().into(),
+      actor,
       to: vec![public()],
-      object: post.into_json(context).await?,
+      object: page,
       cc: vec![community.id()],
       kind,
       id: id.clone(),
@@ -88,23 +107,14 @@ impl CreateOrUpdatePage {
     .await?;
     Ok(())
   }
-}
-
-#[async_trait::async_trait]
-impl ActivityHandler for CreateOrUpdatePage {
-  type DataType = LemmyContext;
-  type Error = LemmyError;
-
-  fn id(&self) -> &Url {
-    &self.id
-  }
-
-  fn actor(&self) -> &Url {
-    self.actor.inner()
-  }
 
-  #[tracing::instrument(skip_all)]
-  async fn verify(&self, context: &Data<LemmyContext>) -> Result<(), LemmyError> {
+  /// Verifies the activity. If it was delivered by a relay, the relay is the actor, so the post
+  /// and its author are on other domains.
+  pub(crate) async fn verify_with_relay(
+    &self,
+    relayed: bool,
+    context: &Data<LemmyContext>,
+  ) -> Result<(), LemmyError> {
     verify_is_public(&self.to, &self.cc)?;
     let community = self.community(context).await?;
     verify_person_in_community(&self.actor, &community, context).await?;
@@ -112,8 +122,10 @@ impl ActivityHandler for CreateOrUpdatePage {
 
     match self.kind {
       CreateOrUpdateType::Create => {
-        verify_domains_match(self.actor.inner(), self.object.id.inner())?;
-        verify_urls_match(self.actor.inner(), self.object.creator()?.inner())?;
+        if !relayed {
+          self.object.verify_origin(self.actor.inner())?;
+          verify_urls_match(self.actor.inner(), self.object.creator()?.inner())?;
+        }
         // Check that the post isnt locked, as that isnt possible for newly created posts.
         // However, when fetching a remote post we generate a new create activity with the current
         // locked value, so this check may fail. So only check if its a local community,
@@ -127,15 +139,45 @@ impl ActivityHandler for CreateOrUpdatePage {
         let is_mod_action = self.object.is_mod_action(context).await?;
         if is_mod_action {
           verify_mod_action(&self.actor, &community, context).await?;
-        } else {
-          verify_domains_match(self.actor.inner(), self.object.id.inner())?;
+        } else if !relayed {
+          self.object.verify_origin(self.actor.inner())?;
           verify_urls_match(self.actor.inner(), self.object.creator()?.inner())?;
         }
       }
     }
-    ApubPost::verify(&self.object, self.actor.inner(), context).await?;
+    verify_page(
+      &self.object,
+      self.actor.inner(),
+      &ACTIVITY_PAGE_TYPES,
+      relayed,
+      context,
+    )
+    .await?;
     Ok(())
   }
+}
+
+#[async_trait::async_trait]
+impl ActivityHandler for CreateOrUpdatePage {
+  type DataType = LemmyContext;
+  type Error = LemmyError;
+
+  fn id(&self) -> &Url {
+    &self.id
+  }
+
+  fn actor(&self) -> &Url {
+    self.actor.inner()
+  }
+
+  #[tracing::instrument(skip_all)]
+  async fn verify(&self, context: &Data<LemmyContext>) -> Result<(), LemmyError> {
+    let relayed = is_relay(
+      self.actor.inner(),
+      &context.settings().federation.relay_domains,
+    );
+    self.verify_with_relay(relayed, context).await
+  }
 
   #[tracing::instrument(skip_all)]
   async fn receive(self, context: &Data<LemmyContext>) -> Result<(), LemmyError> {
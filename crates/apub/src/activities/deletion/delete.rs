@@ -1,13 +1,13 @@
 use crate::{
   activities::{
-    deletion::{receive_delete_action, verify_delete_activity, DeletableObjects},
+    deletion::{receive_delete_action, verify_delete_activity, DeletableObjects, DeletionRole},
     generate_activity_id,
   },
   insert_received_activity,
   objects::person::ApubPerson,
   protocol::{activities::deletion::delete::Delete, IdOrNestedObject},
 };
-use activitypub_federation::{config::Data, kinds::activity::DeleteType, traits::ActivityHandler};
+use activitypub_federation::{config::Data, traits::ActivityHandler};
 use lemmy_api_common::context::LemmyContext;
 use lemmy_db_schema::{
   source::{
@@ -43,21 +43,17 @@ impl ActivityHandler for Delete {
 
   #[tracing::instrument(skip_all)]
   async fn verify(&self, context: &Data<Self::DataType>) -> Result<(), LemmyError> {
-    verify_delete_activity(self, self.summary.is_some(), context).await?;
+    verify_delete_activity(self, self.is_mod_action(), context).await?;
     Ok(())
   }
 
   #[tracing::instrument(skip_all)]
   async fn receive(self, context: &Data<LemmyContext>) -> Result<(), LemmyError> {
     insert_received_activity(&self.id, context).await?;
-    if let Some(reason) = self.summary {
-      // We set reason to empty string if it doesn't exist, to distinguish between delete and
-      // remove. Here we change it back to option, so we don't write it to db.
-      let reason = if reason.is_empty() {
-        None
-      } else {
-        Some(reason)
-      };
+    if self.is_mod_action() {
+      // Older Lemmy versions set reason to empty string if it doesn't exist, to distinguish
+      // between delete and remove. Here we change it back to option, so we don't write it to db.
+      let reason = self.summary.filter(|r| !r.is_empty());
       receive_remove_action(
         &self.actor.dereference(context).await?,
         self.object.id(),
@@ -84,21 +80,19 @@ impl Delete {
     object: DeletableObjects,
     to: Url,
     community: Option<&Community>,
-    summary: Option<String>,
+    role: DeletionRole,
     context: &Data<LemmyContext>,
   ) -> Result<Delete, LemmyError> {
-    let id = generate_activity_id(
-      DeleteType::Delete,
-      &context.settings().get_protocol_and_hostname(),
-    )?;
+    let kind = role.kind();
+    let id = generate_activity_id(kind, &context.settings().get_protocol_and_hostname())?;
     let cc: Option<Url> = community.map(|c| c.actor_id.clone().into());
     Ok(Delete {
       actor: actor.actor_id.clone().into(),
       to: vec![to],
       object: IdOrNestedObject::Id(object.id()),
       cc: cc.into_iter().collect(),
-      kind: DeleteType::Delete,
-      summary,
+      kind,
+      summary: role.summary(),
       id,
       audience: community.map(|c| c.actor_id.clone().into()),
       remove_data: None,
@@ -176,3 +170,118 @@ pub(in crate::activities) async fn receive_remove_action(
   }
   Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{
+    objects::{
+      community::tests::parse_lemmy_community,
+      person::tests::parse_lemmy_person,
+      post::ApubPost,
+      tests::init_context,
+    },
+    protocol::{
+      activities::deletion::delete::DeletionType,
+      objects::tombstone::Tombstone,
+      tests::file_to_json_object,
+    },
+  };
+  use activitypub_federation::{kinds::public, traits::Object};
+  use lemmy_db_schema::{
+    newtypes::DbUrl,
+    source::{person::Person, site::Site},
+  };
+  use lemmy_utils::error::LemmyResult;
+  use serial_test::serial;
+
+  #[tokio::test]
+  #[serial]
+  async fn test_delete_by_role() -> LemmyResult<()> {
+    let context = init_context().await?;
+    let (person, site) = parse_lemmy_person(&context).await?;
+    let community = parse_lemmy_community(&context).await?;
+    let json = file_to_json_object("assets/lemmy/objects/page.json")?;
+    let post = ApubPost::from_json(json, &context).await?;
+
+    let object = || DeletableObjects::Post(post.clone());
+    let delete = Delete::new(
+      &person,
+      object(),
+      public(),
+      Some(&community),
+      DeletionRole::Author,
+      &context,
+    )?;
+    assert_eq!(DeletionType::Delete, delete.kind);
+    assert_eq!(None, delete.summary);
+    assert!(!delete.is_mod_action());
+
+    let role = DeletionRole::Moderator { reason: None };
+    let remove = Delete::new(
+      &person,
+      object(),
+      public(),
+      Some(&community),
+      role,
+      &context,
+    )?;
+    assert_eq!(DeletionType::Remove, remove.kind);
+    assert_eq!(None, remove.summary);
+    assert!(remove.is_mod_action());
+
+    let role = DeletionRole::Moderator {
+      reason: Some("spam".to_string()),
+    };
+    let remove = Delete::new(
+      &person,
+      object(),
+      public(),
+      Some(&community),
+      role,
+      &context,
+    )?;
+    assert_eq!(DeletionType::Remove, remove.kind);
+    assert_eq!(Some("spam".to_string()), remove.summary);
+    assert_eq!(post.ap_id.inner(), remove.object.id());
+    assert!(remove.id.path().starts_with("/activities/remove/"));
+
+    Post::delete(&mut context.pool(), post.id).await?;
+    Person::delete(&mut context.pool(), person.id).await?;
+    Community::delete(&mut context.pool(), community.id).await?;
+    Site::delete(&mut context.pool(), site.id).await?;
+    Ok(())
+  }
+
+  #[tokio::test]
+  #[serial]
+  async fn test_remove_tombstone_domain() -> LemmyResult<()> {
+    let context = init_context().await?;
+    let (person, site) = parse_lemmy_person(&context).await?;
+    let community = parse_lemmy_community(&context).await?;
+    let json = file_to_json_object("assets/lemmy/objects/page.json")?;
+    let post = ApubPost::from_json(json, &context).await?;
+
+    let role = DeletionRole::Moderator { reason: None };
+    let object = DeletableObjects::Post(post.clone());
+    let mut remove = Delete::new(&person, object, public(), Some(&community), role, &context)?;
+    remove.object = IdOrNestedObject::NestedObject(Tombstone::new(post.ap_id.clone().into()));
+    remove.verify(&context).await?;
+
+    // A mod can remove a post from another instance than the community
+    let ap_id: DbUrl = Url::parse("https://lemmy.ml/post/1")?.into();
+    let form = PostUpdateForm {
+      ap_id: Some(ap_id.clone()),
+      ..Default::default()
+    };
+    Post::update(&mut context.pool(), post.id, &form).await?;
+    remove.object = IdOrNestedObject::NestedObject(Tombstone::new(ap_id.into()));
+    remove.verify(&context).await?;
+
+    Post::delete(&mut context.pool(), post.id).await?;
+    Person::delete(&mut context.pool(), person.id).await?;
+    Community::delete(&mut context.pool(), community.id).await?;
+    Site::delete(&mut context.pool(), site.id).await?;
+    Ok(())
+  }
+}
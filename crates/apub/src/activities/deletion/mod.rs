@@ -16,7 +16,11 @@ use crate::{
     private_message::ApubPrivateMessage,
   },
   protocol::{
-    activities::deletion::{delete::Delete, undo_delete::UndoDelete},
+    activities::deletion::{
+      delete::{Delete, DeletionType},
+      undo_delete::UndoDelete,
+    },
+    IdOrNestedObject,
     InCommunity,
   },
 };
@@ -35,7 +39,6 @@ use lemmy_db_schema::{
     comment::{Comment, CommentUpdateForm},
     community::{Community, CommunityUpdateForm},
     person::Person,
-    post::{Post, PostUpdateForm},
     private_message::{PrivateMessage, PrivateMessageUpdateForm},
   },
   traits::Crud,
@@ -47,24 +50,52 @@ use url::Url;
 pub mod delete;
 pub mod undo_delete;
 
-/// Parameter `reason` being set indicates that this is a removal by a mod. If its unset, this
-/// action was done by a normal user.
+/// Who deletes an object. The author's deletion is federated as `Delete`, a removal by a mod as
+/// `Remove` with the reason in `summary`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum DeletionRole {
+  /// The author deletes their own content
+  Author,
+  /// A mod or admin removes the content
+  Moderator { reason: Option<String> },
+}
+
+impl DeletionRole {
+  pub(crate) fn is_mod_action(&self) -> bool {
+    matches!(self, DeletionRole::Moderator { .. })
+  }
+
+  fn kind(&self) -> DeletionType {
+    match self {
+      DeletionRole::Author => DeletionType::Delete,
+      DeletionRole::Moderator { .. } => DeletionType::Remove,
+    }
+  }
+
+  fn summary(self) -> Option<String> {
+    match self {
+      DeletionRole::Author => None,
+      DeletionRole::Moderator { reason } => reason,
+    }
+  }
+}
+
 #[tracing::instrument(skip_all)]
 pub(crate) async fn send_apub_delete_in_community(
   actor: Person,
   community: Community,
   object: DeletableObjects,
-  reason: Option<String>,
+  role: DeletionRole,
   deleted: bool,
   context: &Data<LemmyContext>,
 ) -> Result<(), LemmyError> {
   let actor = ApubPerson::from(actor);
-  let is_mod_action = reason.is_some();
+  let is_mod_action = role.is_mod_action();
   let activity = if deleted {
-    let delete = Delete::new(&actor, object, public(), Some(&community), reason, context)?;
+    let delete = Delete::new(&actor, object, public(), Some(&community), role, context)?;
     AnnouncableActivities::Delete(delete)
   } else {
-    let undo = UndoDelete::new(&actor, object, public(), Some(&community), reason, context)?;
+    let undo = UndoDelete::new(&actor, object, public(), Some(&community), role, context)?;
     AnnouncableActivities::UndoDelete(undo)
   };
   send_activity_in_community(
@@ -78,25 +109,23 @@ pub(crate) async fn send_apub_delete_in_community(
   .await
 }
 
-/// Parameter `reason` being set indicates that this is a removal by a mod. If its unset, this
-/// action was done by a normal user.
 #[tracing::instrument(skip_all)]
 pub(crate) async fn send_apub_delete_in_community_new(
   actor: Person,
   community_id: CommunityId,
   object: DeletableObjects,
-  reason: Option<String>,
+  role: DeletionRole,
   deleted: bool,
   context: Data<LemmyContext>,
 ) -> Result<(), LemmyError> {
   let community = Community::read(&mut context.pool(), community_id).await?;
   let actor = ApubPerson::from(actor);
-  let is_mod_action = reason.is_some();
+  let is_mod_action = role.is_mod_action();
   let activity = if deleted {
-    let delete = Delete::new(&actor, object, public(), Some(&community), reason, &context)?;
+    let delete = Delete::new(&actor, object, public(), Some(&community), role, &context)?;
     AnnouncableActivities::Delete(delete)
   } else {
-    let undo = UndoDelete::new(&actor, object, public(), Some(&community), reason, &context)?;
+    let undo = UndoDelete::new(&actor, object, public(), Some(&community), role, &context)?;
     AnnouncableActivities::UndoDelete(undo)
   };
   send_activity_in_community(
@@ -125,10 +154,12 @@ pub(crate) async fn send_apub_delete_private_message(
   let deletable = DeletableObjects::PrivateMessage(pm.into());
   let inbox = ActivitySendTargets::to_inbox(recipient.shared_inbox_or_inbox());
   if deleted {
-    let delete: Delete = Delete::new(actor, deletable, recipient.id(), None, None, &context)?;
+    let role = DeletionRole::Author;
+    let delete: Delete = Delete::new(actor, deletable, recipient.id(), None, role, &context)?;
     send_lemmy_activity(&context, delete, actor, inbox, true).await?;
   } else {
-    let undo = UndoDelete::new(actor, deletable, recipient.id(), None, None, &context)?;
+    let role = DeletionRole::Author;
+    let undo = UndoDelete::new(actor, deletable, recipient.id(), None, role, &context)?;
     send_lemmy_activity(&context, undo, actor, inbox, true).await?;
   };
   Ok(())
@@ -142,7 +173,14 @@ pub async fn send_apub_delete_user(
   let person: ApubPerson = person.into();
 
   let deletable = DeletableObjects::Person(person.clone());
-  let mut delete: Delete = Delete::new(&person, deletable, public(), None, None, &context)?;
+  let mut delete: Delete = Delete::new(
+    &person,
+    deletable,
+    public(),
+    None,
+    DeletionRole::Author,
+    &context,
+  )?;
   delete.remove_data = Some(remove_data);
 
   let inboxes = ActivitySendTargets::to_all_instances();
@@ -219,10 +257,20 @@ pub(in crate::activities) async fn verify_delete_activity(
     }
     DeletableObjects::Post(p) => {
       verify_is_public(&activity.to, &[])?;
+      let community = activity.community(context).await?;
+      if let IdOrNestedObject::NestedObject(tombstone) = &activity.object {
+        if is_mod_action {
+          // Mods may be on a different instance than the post, their permission is checked in
+          // verify_delete_post_or_comment
+          verify_urls_match(&tombstone.id, p.ap_id.inner())?;
+        } else {
+          tombstone.verify(p.ap_id.inner(), activity.actor.inner())?;
+        }
+      }
       verify_delete_post_or_comment(
         &activity.actor,
         &p.ap_id.clone().into(),
-        &activity.community(context).await?,
+        &community,
         is_mod_action,
         context,
       )
@@ -280,7 +328,7 @@ async fn receive_delete_action(
         let mod_: Person = actor.dereference(context).await?.deref().clone();
         let object = DeletableObjects::Community(community.clone());
         let c: Community = community.deref().clone();
-        send_apub_delete_in_community(mod_, c, object, None, true, context).await?;
+        send_apub_delete_in_community(mod_, c, object, DeletionRole::Author, true, context).await?;
       }
 
       Community::update(
@@ -301,16 +349,10 @@ async fn receive_delete_action(
       }
     }
     DeletableObjects::Post(post) => {
-      if deleted != post.deleted {
-        Post::update(
-          &mut context.pool(),
-          post.id,
-          &PostUpdateForm {
-            deleted: Some(deleted),
-            ..Default::default()
-          },
-        )
-        .await?;
+      if deleted {
+        post.delete(context).await?;
+      } else {
+        post.restore(context).await?;
       }
     }
     DeletableObjects::Comment(comment) => {
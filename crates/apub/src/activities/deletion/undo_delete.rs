@@ -1,6 +1,6 @@
 use crate::{
   activities::{
-    deletion::{receive_delete_action, verify_delete_activity, DeletableObjects},
+    deletion::{receive_delete_action, verify_delete_activity, DeletableObjects, DeletionRole},
     generate_activity_id,
   },
   insert_received_activity,
@@ -43,14 +43,14 @@ impl ActivityHandler for UndoDelete {
 
   async fn verify(&self, data: &Data<Self::DataType>) -> Result<(), Self::Error> {
     self.object.verify(data).await?;
-    verify_delete_activity(&self.object, self.object.summary.is_some(), data).await?;
+    verify_delete_activity(&self.object, self.object.is_mod_action(), data).await?;
     Ok(())
   }
 
   #[tracing::instrument(skip_all)]
   async fn receive(self, context: &Data<LemmyContext>) -> Result<(), LemmyError> {
     insert_received_activity(&self.id, context).await?;
-    if self.object.summary.is_some() {
+    if self.object.is_mod_action() {
       UndoDelete::receive_undo_remove_action(
         &self.actor.dereference(context).await?,
         self.object.object.id(),
@@ -70,10 +70,10 @@ impl UndoDelete {
     object: DeletableObjects,
     to: Url,
     community: Option<&Community>,
-    summary: Option<String>,
+    role: DeletionRole,
     context: &Data<LemmyContext>,
   ) -> Result<UndoDelete, LemmyError> {
-    let object = Delete::new(actor, object, to.clone(), community, summary, context)?;
+    let object = Delete::new(actor, object, to.clone(), community, role, context)?;
 
     let id = generate_activity_id(
       UndoType::Undo,
@@ -161,3 +161,109 @@ impl UndoDelete {
     Ok(())
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{
+    objects::{
+      community::{tests::parse_lemmy_community, ApubCommunity},
+      person::tests::parse_lemmy_person,
+      post::ApubPost,
+      tests::init_context,
+    },
+    protocol::{
+      objects::person::Person as PersonJson,
+      tests::file_to_json_object,
+      IdOrNestedObject,
+    },
+  };
+  use activitypub_federation::{
+    kinds::activity::DeleteType,
+    traits::{ActivityHandler, Object},
+  };
+  use lemmy_db_schema::source::{person::Person, site::Site};
+  use lemmy_utils::error::LemmyResult;
+  use serial_test::serial;
+
+  /// Undo of the author's deletion of the post, sent by `actor`.
+  fn undo_delete(
+    actor: &ApubPerson,
+    post: &ApubPost,
+    community: &ApubCommunity,
+  ) -> LemmyResult<UndoDelete> {
+    let mut undo: UndoDelete =
+      file_to_json_object("assets/lemmy/activities/deletion/undo_delete_page.json")?;
+    let domain = format!("https://{}", actor.actor_id.domain().unwrap_or_default());
+    undo.actor = actor.actor_id.clone().into();
+    undo.id = generate_activity_id(UndoType::Undo, &domain)?;
+    undo.cc = vec![community.actor_id.clone().into()];
+    undo.audience = Some(community.actor_id.clone().into());
+    undo.object.actor = actor.actor_id.clone().into();
+    undo.object.object = IdOrNestedObject::Id(post.ap_id.clone().into());
+    undo.object.id = generate_activity_id(DeleteType::Delete, &domain)?;
+    undo.object.cc = undo.cc.clone();
+    undo.object.audience = undo.audience.clone();
+    Ok(undo)
+  }
+
+  #[tokio::test]
+  #[serial]
+  async fn test_undo_delete_post() -> LemmyResult<()> {
+    let context = init_context().await?;
+    let (person, site) = parse_lemmy_person(&context).await?;
+    let community = parse_lemmy_community(&context).await?;
+    let json = file_to_json_object("assets/lemmy/objects/page.json")?;
+    let post = ApubPost::from_json(json, &context).await?;
+
+    let undo = undo_delete(&person, &post, &community)?;
+    undo.object.verify(&context).await?;
+    undo.object.clone().receive(&context).await?;
+    let deleted = Post::read(&mut context.pool(), post.id).await?;
+    assert!(deleted.deleted);
+
+    undo.verify(&context).await?;
+    undo.receive(&context).await?;
+    let restored = Post::read(&mut context.pool(), post.id).await?;
+    assert!(!restored.deleted);
+
+    Post::delete(&mut context.pool(), post.id).await?;
+    Person::delete(&mut context.pool(), person.id).await?;
+    Community::delete(&mut context.pool(), community.id).await?;
+    Site::delete(&mut context.pool(), site.id).await?;
+    Ok(())
+  }
+
+  #[tokio::test]
+  #[serial]
+  async fn test_undo_delete_post_by_other_user() -> LemmyResult<()> {
+    let context = init_context().await?;
+    let (person, site) = parse_lemmy_person(&context).await?;
+    let community = parse_lemmy_community(&context).await?;
+    let json = file_to_json_object("assets/lemmy/objects/page.json")?;
+    let post = ApubPost::from_json(json, &context).await?;
+    let form = PostUpdateForm {
+      deleted: Some(true),
+      ..Default::default()
+    };
+    Post::update(&mut context.pool(), post.id, &form).await?;
+
+    let mut json: PersonJson = file_to_json_object("assets/lemmy/objects/person.json")?;
+    json.id = Url::parse("https://ds9.lemmy.ml/u/lemmy_alpha")?.into();
+    json.preferred_username = "lemmy_alpha".to_string();
+    let other = ApubPerson::from_json(json, &context).await?;
+
+    // only the author can restore the post
+    let undo = undo_delete(&other, &post, &community)?;
+    assert!(undo.verify(&context).await.is_err());
+    let post = Post::read(&mut context.pool(), post.id).await?;
+    assert!(post.deleted);
+
+    Post::delete(&mut context.pool(), post.id).await?;
+    Person::delete(&mut context.pool(), person.id).await?;
+    Person::delete(&mut context.pool(), other.id).await?;
+    Community::delete(&mut context.pool(), community.id).await?;
+    Site::delete(&mut context.pool(), site.id).await?;
+    Ok(())
+  }
+}
@@ -0,0 +1,166 @@
+use crate::{
+  insert_received_activity,
+  objects::person::ApubPerson,
+  protocol::{activities::migration::move_user::MoveUser, objects::person::Person},
+};
+use activitypub_federation::{
+  config::Data,
+  fetch::fetch_object_http,
+  protocol::verification::{verify_domains_match, verify_urls_match},
+  traits::ActivityHandler,
+};
+use lemmy_api_common::context::LemmyContext;
+use lemmy_db_schema::source::post::Post;
+use lemmy_utils::error::{LemmyError, LemmyErrorType};
+use url::Url;
+
+/// Handle account migrations, so that posts of the old account are attributed to the new one.
+#[async_trait::async_trait]
+impl ActivityHandler for MoveUser {
+  type DataType = LemmyContext;
+  type Error = LemmyError;
+
+  fn id(&self) -> &Url {
+    &self.id
+  }
+
+  fn actor(&self) -> &Url {
+    self.actor.inner()
+  }
+
+  #[tracing::instrument(skip_all)]
+  async fn verify(&self, context: &Data<LemmyContext>) -> Result<(), LemmyError> {
+    verify_urls_match(self.actor.inner(), self.object.inner())?;
+    verify_domains_match(self.actor.inner(), &self.id)?;
+    if self.target.inner() == self.object.inner() {
+      Err(LemmyErrorType::InvalidMoveTarget)?
+    }
+    // The new account needs to confirm the migration by listing the old one as alias. Fetch it
+    // directly, as the alias list isn't stored in the database.
+    let target: Person = fetch_object_http(self.target.inner(), context)
+      .await?
+      .object;
+    if !target.also_known_as.contains(self.object.inner()) {
+      Err(LemmyErrorType::InvalidMoveTarget)?
+    }
+    Ok(())
+  }
+
+  #[tracing::instrument(skip_all)]
+  async fn receive(self, context: &Data<LemmyContext>) -> Result<(), LemmyError> {
+    insert_received_activity(&self.id, context).await?;
+    let origin: ApubPerson = self.object.dereference(context).await?;
+    let target: ApubPerson = self.target.dereference(context).await?;
+    Post::update_creator(&mut context.pool(), origin.id, target.id).await?;
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{
+    activities::generate_activity_id,
+    objects::{
+      community::tests::parse_lemmy_community,
+      person::tests::parse_lemmy_person,
+      post::ApubPost,
+      tests::init_context_with_responses,
+    },
+    protocol::tests::file_to_json_object,
+  };
+  use activitypub_federation::{kinds::activity::MoveType, traits::Object};
+  use lemmy_db_schema::{
+    source::{community::Community, person::Person as DbPerson, site::Site},
+    traits::Crud,
+  };
+  use lemmy_utils::error::LemmyResult;
+  use pretty_assertions::assert_eq;
+  use serial_test::serial;
+  use std::collections::HashMap;
+
+  const TARGET: &str = "https://enterprise.lemmy.ml/u/riker";
+
+  /// The account which is moved to, with the origin account as alias if given.
+  fn target_json(alias: Option<&Url>) -> LemmyResult<Person> {
+    let mut json: Person = file_to_json_object("assets/lemmy/objects/person.json")?;
+    json.id = Url::parse(TARGET)?.into();
+    json.preferred_username = "riker".to_string();
+    json.also_known_as = alias.into_iter().cloned().collect();
+    Ok(json)
+  }
+
+  /// Test context where fetching the target account returns the given json.
+  async fn init_context_with_target(target: &Person) -> LemmyResult<Data<LemmyContext>> {
+    let responses = HashMap::from([(TARGET.to_string(), serde_json::to_string(target)?)]);
+    init_context_with_responses(responses).await
+  }
+
+  fn move_activity(origin: &ApubPerson) -> LemmyResult<MoveUser> {
+    Ok(MoveUser {
+      actor: origin.actor_id.clone().into(),
+      object: origin.actor_id.clone().into(),
+      target: Url::parse(TARGET)?.into(),
+      kind: MoveType::Move,
+      id: generate_activity_id(MoveType::Move, "https://enterprise.lemmy.ml")?,
+    })
+  }
+
+  #[tokio::test]
+  #[serial]
+  async fn test_move_user() -> LemmyResult<()> {
+    let origin_id = Url::parse("https://enterprise.lemmy.ml/u/picard")?;
+    let json = target_json(Some(&origin_id))?;
+    let context = init_context_with_target(&json).await?;
+    let (origin, site) = parse_lemmy_person(&context).await?;
+    assert_eq!(origin_id, *origin.actor_id.inner());
+    let community = parse_lemmy_community(&context).await?;
+    let page = file_to_json_object("assets/lemmy/objects/page.json")?;
+    let post = ApubPost::from_json(page, &context).await?;
+    assert_eq!(origin.id, post.creator_id);
+    let target = ApubPerson::from_json(json, &context).await?;
+
+    // the target is fetched once to check its aliases
+    let activity = move_activity(&origin)?;
+    activity.verify(&context).await?;
+    assert_eq!(1, context.request_count());
+    activity.receive(&context).await?;
+
+    let post = Post::read(&mut context.pool(), post.id).await?;
+    assert_eq!(target.id, post.creator_id);
+    assert_eq!(1, context.request_count());
+
+    Post::delete(&mut context.pool(), post.id).await?;
+    DbPerson::delete(&mut context.pool(), origin.id).await?;
+    DbPerson::delete(&mut context.pool(), target.id).await?;
+    Community::delete(&mut context.pool(), community.id).await?;
+    Site::delete(&mut context.pool(), site.id).await?;
+    Ok(())
+  }
+
+  #[tokio::test]
+  #[serial]
+  async fn test_move_user_without_alias() -> LemmyResult<()> {
+    let other = Url::parse("https://enterprise.lemmy.ml/u/data")?;
+    let context = init_context_with_target(&target_json(Some(&other))?).await?;
+    let (origin, site) = parse_lemmy_person(&context).await?;
+
+    // the target doesn't list the origin account as alias, so the migration isn't confirmed
+    let res = move_activity(&origin)?.verify(&context).await;
+    assert_eq!(
+      res.err().map(|e| e.error_type),
+      Some(LemmyErrorType::InvalidMoveTarget)
+    );
+
+    let context = init_context_with_target(&target_json(None)?).await?;
+    let res = move_activity(&origin)?.verify(&context).await;
+    assert_eq!(
+      res.err().map(|e| e.error_type),
+      Some(LemmyErrorType::InvalidMoveTarget)
+    );
+
+    DbPerson::delete(&mut context.pool(), origin.id).await?;
+    Site::delete(&mut context.pool(), site.id).await?;
+    Ok(())
+  }
+}
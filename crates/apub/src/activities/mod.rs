@@ -14,6 +14,7 @@ use crate::{
       send_apub_delete_private_message,
       send_apub_delete_user,
       DeletableObjects,
+      DeletionRole,
     },
     voting::send_like_activity,
   },
@@ -51,6 +52,7 @@ pub mod community;
 pub mod create_or_update;
 pub mod deletion;
 pub mod following;
+pub mod migration;
 pub mod voting;
 
 /// Checks that the specified Url actually identifies a Person (by fetching it), and that the person
@@ -78,6 +80,15 @@ pub(crate) async fn verify_person_in_community(
   context: &Data<LemmyContext>,
 ) -> Result<(), LemmyError> {
   let person = person_id.dereference(context).await?;
+  verify_dereferenced_person_in_community(&person, community, context).await
+}
+
+/// Like [verify_person_in_community], for a person which was already fetched.
+pub(crate) async fn verify_dereferenced_person_in_community(
+  person: &ApubPerson,
+  community: &ApubCommunity,
+  context: &Data<LemmyContext>,
+) -> Result<(), LemmyError> {
   if person.banned {
     Err(LemmyErrorType::PersonIsBannedFromSite(
       person.actor_id.to_string(),
@@ -250,7 +261,7 @@ pub async fn match_outgoing_activities(
           person,
           post.community_id,
           DeletableObjects::Post(post.into()),
-          None,
+          DeletionRole::Author,
           data.deleted,
           context,
         )
@@ -261,7 +272,9 @@ pub async fn match_outgoing_activities(
           person,
           post.community_id,
           DeletableObjects::Post(post.into()),
-          data.reason.or_else(|| Some(String::new())),
+          DeletionRole::Moderator {
+            reason: data.reason,
+          },
           data.removed,
           context,
         )
@@ -280,13 +293,14 @@ pub async fn match_outgoing_activities(
       DeleteComment(comment, actor, community) => {
         let is_deleted = comment.deleted;
         let deletable = DeletableObjects::Comment(comment.into());
-        send_apub_delete_in_community(actor, community, deletable, None, is_deleted, &context).await
+        let role = DeletionRole::Author;
+        send_apub_delete_in_community(actor, community, deletable, role, is_deleted, &context).await
       }
       RemoveComment(comment, actor, community, reason) => {
         let is_removed = comment.removed;
         let deletable = DeletableObjects::Comment(comment.into());
-        send_apub_delete_in_community(actor, community, deletable, reason, is_removed, &context)
-          .await
+        let role = DeletionRole::Moderator { reason };
+        send_apub_delete_in_community(actor, community, deletable, role, is_removed, &context).await
       }
       LikePostOrComment(object_id, person, community, score) => {
         send_like_activity(object_id, person, community, score, context).await
@@ -297,7 +311,8 @@ pub async fn match_outgoing_activities(
       UpdateCommunity(actor, community) => send_update_community(community, actor, context).await,
       DeleteCommunity(actor, community, removed) => {
         let deletable = DeletableObjects::Community(community.clone().into());
-        send_apub_delete_in_community(actor, community, deletable, None, removed, &context).await
+        let role = DeletionRole::Author;
+        send_apub_delete_in_community(actor, community, deletable, role, removed, &context).await
       }
       RemoveCommunity(actor, community, reason, removed) => {
         let deletable = DeletableObjects::Community(community.clone().into());
@@ -305,7 +320,9 @@ pub async fn match_outgoing_activities(
           actor,
           community,
           deletable,
-          reason.clone().or_else(|| Some(String::new())),
+          DeletionRole::Moderator {
+            reason: reason.clone(),
+          },
           removed,
           &context,
         )
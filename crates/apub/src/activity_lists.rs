@@ -1,5 +1,6 @@
 use crate::{
   objects::community::ApubCommunity,
+  page_context,
   protocol::{
     activities::{
       block::{block_user::BlockUser, undo_block_user::UndoBlockUser},
@@ -18,16 +19,20 @@ use crate::{
       },
       deletion::{delete::Delete, undo_delete::UndoDelete},
       following::{accept::AcceptFollow, follow::Follow, undo_follow::UndoFollow},
+      migration::move_user::MoveUser,
       voting::{undo_vote::UndoVote, vote::Vote},
     },
     objects::page::Page,
+    IdOrNestedObject,
     InCommunity,
   },
+  FEDERATION_CONTEXT,
 };
 use activitypub_federation::{config::Data, traits::ActivityHandler};
 use lemmy_api_common::context::LemmyContext;
-use lemmy_utils::error::LemmyError;
+use lemmy_utils::{error::LemmyError, settings::structs::Settings};
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use url::Url;
 
 /// List of activities which the shared inbox can handle.
@@ -44,11 +49,32 @@ pub enum SharedInboxActivities {
   UndoFollow(UndoFollow),
   CreateOrUpdatePrivateMessage(CreateOrUpdateChatMessage),
   Report(Report),
+  MoveUser(MoveUser),
   AnnounceActivity(AnnounceActivity),
   /// This is a catch-all and needs to be last
   RawAnnouncableActivities(RawAnnouncableActivities),
 }
 
+impl SharedInboxActivities {
+  /// The context to send the activity with. Posts use [page_context], also when they are
+  /// announced, so that the `mastodon_compat` setting applies to them.
+  pub fn send_context(&self, settings: &Settings) -> Value {
+    let inner = match self {
+      SharedInboxActivities::AnnounceActivity(a) => match &a.object {
+        IdOrNestedObject::NestedObject(o) => Some(o),
+        IdOrNestedObject::Id(_) => None,
+      },
+      SharedInboxActivities::RawAnnouncableActivities(a) => Some(a),
+      _ => None,
+    };
+    if inner.is_some_and(RawAnnouncableActivities::is_create_or_update_page) {
+      page_context(settings)
+    } else {
+      FEDERATION_CONTEXT.clone()
+    }
+  }
+}
+
 /// List of activities which the group inbox can handle.
 #[derive(Debug, Deserialize, Serialize)]
 #[serde(untagged)]
@@ -70,10 +96,10 @@ pub enum PersonInboxActivities {
   AcceptFollow(AcceptFollow),
   UndoFollow(UndoFollow),
   CreateOrUpdatePrivateMessage(CreateOrUpdateChatMessage),
-  Delete(Delete),
-  UndoDelete(UndoDelete),
+  MoveUser(MoveUser),
   AnnounceActivity(AnnounceActivity),
-  /// User can also receive some "announcable" activities, eg a comment mention.
+  /// User can also receive some "announcable" activities, eg a comment mention. This also
+  /// includes deletion of private messages.
   AnnouncableActivities(AnnouncableActivities),
 }
 
@@ -85,13 +111,14 @@ pub enum AnnouncableActivities {
   CreateOrUpdatePost(CreateOrUpdatePage),
   Vote(Vote),
   UndoVote(UndoVote),
+  /// Needs to come before `Delete`, which would also accept `Remove` without `target`
+  CollectionRemove(CollectionRemove),
   Delete(Delete),
   UndoDelete(UndoDelete),
   UpdateCommunity(UpdateCommunity),
   BlockUser(BlockUser),
   UndoBlockUser(UndoBlockUser),
   CollectionAdd(CollectionAdd),
-  CollectionRemove(CollectionRemove),
   LockPost(LockPage),
   UndoLockPost(UndoLockPage),
   // For compatibility with Pleroma/Mastodon (send only)
@@ -127,10 +154,18 @@ mod tests {
   #![allow(clippy::indexing_slicing)]
 
   use crate::{
-    activity_lists::{GroupInboxActivities, PersonInboxActivities, SharedInboxActivities},
-    protocol::tests::{test_json, test_parse_lemmy_item},
+    activity_lists::{
+      AnnouncableActivities,
+      GroupInboxActivities,
+      PersonInboxActivities,
+      SharedInboxActivities,
+    },
+    protocol::tests::{file_to_json_object, test_json, test_parse_lemmy_item},
+    FEDERATION_CONTEXT,
+    MASTODON_COMPAT_CONTEXT,
   };
-  use lemmy_utils::error::LemmyResult;
+  use lemmy_utils::{error::LemmyResult, settings::SETTINGS};
+  use pretty_assertions::assert_eq;
 
   #[test]
   fn test_group_inbox() -> LemmyResult<()> {
@@ -152,15 +187,64 @@ mod tests {
     test_parse_lemmy_item::<PersonInboxActivities>(
       "assets/lemmy/activities/create_or_update/create_private_message.json",
     )?;
+    let activity = test_parse_lemmy_item::<PersonInboxActivities>(
+      "assets/lemmy/activities/deletion/delete_private_message.json",
+    )?;
+    assert!(matches!(
+      activity,
+      PersonInboxActivities::AnnouncableActivities(AnnouncableActivities::Delete(_))
+    ));
     test_json::<PersonInboxActivities>("assets/mastodon/activities/follow.json")?;
     Ok(())
   }
 
+  #[test]
+  fn test_announcable_remove() -> LemmyResult<()> {
+    let activity = test_parse_lemmy_item::<AnnouncableActivities>(
+      "assets/lemmy/activities/community/remove_featured_post.json",
+    )?;
+    assert!(matches!(
+      activity,
+      AnnouncableActivities::CollectionRemove(_)
+    ));
+    let activity = test_parse_lemmy_item::<AnnouncableActivities>(
+      "assets/lemmy/activities/deletion/remove_note.json",
+    )?;
+    assert!(matches!(activity, AnnouncableActivities::Delete(_)));
+    Ok(())
+  }
+
+  #[test]
+  fn test_send_context() -> LemmyResult<()> {
+    let create: SharedInboxActivities =
+      file_to_json_object("assets/lemmy/activities/create_or_update/create_page.json")?;
+    let announce: SharedInboxActivities =
+      file_to_json_object("assets/lemmy/activities/community/announce_create_page.json")?;
+    let follow: SharedInboxActivities =
+      file_to_json_object("assets/lemmy/activities/following/follow.json")?;
+
+    let mut settings = SETTINGS.clone();
+    for activity in [&create, &announce, &follow] {
+      assert_eq!(*FEDERATION_CONTEXT, activity.send_context(&settings));
+    }
+
+    settings.federation.mastodon_compat = true;
+    assert_eq!(*MASTODON_COMPAT_CONTEXT, create.send_context(&settings));
+    assert_eq!(*MASTODON_COMPAT_CONTEXT, announce.send_context(&settings));
+    assert_eq!(*FEDERATION_CONTEXT, follow.send_context(&settings));
+    Ok(())
+  }
+
   #[test]
   fn test_shared_inbox() -> LemmyResult<()> {
     test_parse_lemmy_item::<SharedInboxActivities>(
       "assets/lemmy/activities/deletion/delete_user.json",
     )?;
+    let activity = test_json::<SharedInboxActivities>("assets/mastodon/activities/move.json")?;
+    assert!(matches!(
+      activity.inner(),
+      SharedInboxActivities::MoveUser(_)
+    ));
     Ok(())
   }
 }
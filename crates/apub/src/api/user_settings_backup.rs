@@ -194,11 +194,14 @@ pub async fn import_settings(
         .into_iter()
         .map(|s| (s, context.reset_request_count()))
         .map(|(saved, context)| async move {
-          let post = saved.dereference(&context).await?;
-          let form = PostSavedForm {
-            person_id,
-            post_id: post.id,
+          // only the id is needed, so posts which are already known are read without their body
+          let summary =
+            ApubPost::read_summary_from_apub_id(saved.inner().clone(), &context).await?;
+          let post_id = match summary {
+            Some(post) => post.id,
+            None => saved.dereference(&context).await?.id,
           };
+          let form = PostSavedForm { person_id, post_id };
           PostSaved::save(&mut context.pool(), &form).await?;
           LemmyResult::Ok(())
         }),
@@ -305,13 +308,15 @@ mod tests {
   use activitypub_federation::config::Data;
   use lemmy_api_common::context::LemmyContext;
   use lemmy_db_schema::{
+    newtypes::DbUrl,
     source::{
       community::{Community, CommunityFollower, CommunityFollowerForm, CommunityInsertForm},
       instance::Instance,
       local_user::{LocalUser, LocalUserInsertForm},
       person::{Person, PersonInsertForm},
+      post::{Post, PostInsertForm, PostSaved, PostSavedForm},
     },
-    traits::{Crud, Followable},
+    traits::{Crud, Followable, Saveable},
   };
   use lemmy_db_views::structs::LocalUserView;
   use lemmy_db_views_actor::structs::CommunityFollowerView;
@@ -365,6 +370,17 @@ mod tests {
       pending: false,
     };
     CommunityFollower::follow(&mut context.pool(), &follower_form).await?;
+    let post_form = PostInsertForm::builder()
+      .name("testpost".to_string())
+      .creator_id(export_user.person.id)
+      .community_id(community.id)
+      .build();
+    let post = Post::create(&mut context.pool(), &post_form).await?;
+    let saved_form = PostSavedForm {
+      person_id: export_user.person.id,
+      post_id: post.id,
+    };
+    PostSaved::save(&mut context.pool(), &saved_form).await?;
 
     let backup = export_settings(export_user.clone(), context.reset_request_count()).await?;
 
@@ -389,6 +405,15 @@ mod tests {
     assert_eq!(follows.len(), 1);
     assert_eq!(follows[0].community.actor_id, community.actor_id);
 
+    let import_backup = export_settings(import_user.clone(), context.reset_request_count()).await?;
+    let saved_posts: Vec<DbUrl> = import_backup
+      .saved_posts
+      .iter()
+      .map(|p| p.clone().into())
+      .collect();
+    assert_eq!(vec![post.ap_id.clone()], saved_posts);
+
+    Post::delete(&mut context.pool(), post.id).await?;
     LocalUser::delete(&mut context.pool(), export_user.local_user.id).await?;
     LocalUser::delete(&mut context.pool(), import_user.local_user.id).await?;
     Ok(())
@@ -6,9 +6,9 @@ use activitypub_federation::{
   config::Data,
   kinds::collection::OrderedCollectionType,
   protocol::verification::verify_domains_match,
-  traits::{ActivityHandler, Collection, Object},
+  traits::{ActivityHandler, Collection},
 };
-use futures::future::{join_all, try_join_all};
+use futures::future::join_all;
 use lemmy_api_common::{context::LemmyContext, utils::generate_featured_url};
 use lemmy_db_schema::{source::post::Post, utils::FETCH_LIMIT_MAX};
 use lemmy_utils::error::LemmyError;
@@ -28,14 +28,12 @@ impl Collection for ApubCommunityFeatured {
     owner: &Self::Owner,
     data: &Data<Self::DataType>,
   ) -> Result<Self::Kind, Self::Error> {
-    let ordered_items = try_join_all(
-      Post::list_featured_for_community(&mut data.pool(), owner.id)
-        .await?
-        .into_iter()
-        .map(ApubPost::from)
-        .map(|p| p.into_json(data)),
-    )
-    .await?;
+    let posts = Post::list_featured_for_community(&mut data.pool(), owner.id)
+      .await?
+      .into_iter()
+      .map(ApubPost::from)
+      .collect();
+    let ordered_items = ApubPost::into_json_batch(posts, data).await?;
     Ok(GroupFeatured {
       r#type: OrderedCollectionType::OrderedCollection,
       id: generate_featured_url(&owner.actor_id)?.into(),
@@ -1,6 +1,8 @@
+use crate::objects::person::ApubPerson;
 use activitypub_federation::{
   config::Data,
-  fetch::webfinger::webfinger_resolve_actor,
+  error::Error as ActivityPubError,
+  fetch::{object_id::ObjectId, webfinger::webfinger_resolve_actor},
   traits::{Actor, Object},
 };
 use diesel::NotFound;
@@ -8,7 +10,8 @@ use itertools::Itertools;
 use lemmy_api_common::context::LemmyContext;
 use lemmy_db_schema::traits::ApubActor;
 use lemmy_db_views::structs::LocalUserView;
-use lemmy_utils::error::LemmyError;
+use lemmy_utils::error::{LemmyError, LemmyResult};
+use url::Url;
 
 pub mod post_or_comment;
 pub mod search;
@@ -63,3 +66,75 @@ where
     )
   }
 }
+
+/// Fetches a person from its id. Some software references actors by webfinger address like
+/// `acct:alice@example.com` instead of their id, those can't be dereferenced directly and are
+/// resolved with webfinger instead.
+pub(crate) async fn dereference_person(
+  id: &ObjectId<ApubPerson>,
+  context: &Data<LemmyContext>,
+) -> LemmyResult<ApubPerson> {
+  let Some(identifier) = webfinger_identifier(id.inner()) else {
+    return id.dereference(context).await;
+  };
+  let person: ApubPerson = webfinger_resolve_actor(&identifier, context).await?;
+  // The webfinger response of one instance must not attribute objects to actors of another
+  if actor_domain(person.actor_id.inner()) != actor_domain(id.inner()) {
+    Err(ActivityPubError::UrlVerificationError(
+      "Domains do not match",
+    ))?
+  }
+  Ok(person)
+}
+
+/// The `alice@example.com` identifier of an `acct:` uri, or `None` for actor ids.
+fn webfinger_identifier(id: &Url) -> Option<String> {
+  if id.scheme() != "acct" {
+    return None;
+  }
+  let identifier = id.path().trim_start_matches('@').to_lowercase();
+  let valid = identifier
+    .split_once('@')
+    .is_some_and(|(name, domain)| !name.is_empty() && !domain.is_empty());
+  valid.then_some(identifier)
+}
+
+/// The domain of an actor as used in mentions, including the port if any. Works for ids as well as
+/// `acct:` uris.
+pub(crate) fn actor_domain(id: &Url) -> Option<String> {
+  if id.scheme() == "acct" {
+    return id
+      .path()
+      .rsplit_once('@')
+      .map(|(_, domain)| domain.to_lowercase())
+      .filter(|d| !d.is_empty());
+  }
+  let host = id.host_str()?;
+  Some(match id.port() {
+    Some(port) => format!("{host}:{port}"),
+    None => host.to_string(),
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use pretty_assertions::assert_eq;
+
+  #[test]
+  fn test_webfinger_identifier() -> LemmyResult<()> {
+    let acct = Url::parse("acct:Alice@Example.com")?;
+    assert_eq!(
+      Some("alice@example.com".to_string()),
+      webfinger_identifier(&acct)
+    );
+    assert_eq!(Some("example.com".to_string()), actor_domain(&acct));
+    let id = Url::parse("https://example.com/u/alice")?;
+    assert_eq!(None, webfinger_identifier(&id));
+    assert_eq!(Some("example.com".to_string()), actor_domain(&id));
+    let id = Url::parse("http://example.com:8536/u/alice")?;
+    assert_eq!(Some("example.com:8536".to_string()), actor_domain(&id));
+    assert_eq!(None, webfinger_identifier(&Url::parse("acct:alice")?));
+    Ok(())
+  }
+}
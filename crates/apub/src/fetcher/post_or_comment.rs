@@ -1,6 +1,7 @@
 use crate::{
   objects::{comment::ApubComment, community::ApubCommunity, post::ApubPost},
   protocol::{
+    json_ld::compact_expanded,
     objects::{note::Note, page::Page},
     InCommunity,
   },
@@ -12,23 +13,50 @@ use lemmy_db_schema::{
   source::{community::Community, post::Post},
   traits::Crud,
 };
-use lemmy_utils::error::LemmyError;
+use lemmy_utils::error::{LemmyError, LemmyErrorType, LemmyResult};
 use serde::Deserialize;
+use serde_json::Value;
 use url::Url;
 
 #[derive(Clone, Debug)]
 pub enum PostOrComment {
-  Post(ApubPost),
+  Post(Box<ApubPost>),
   Comment(ApubComment),
 }
 
 #[derive(Deserialize)]
-#[serde(untagged)]
+#[serde(try_from = "Value")]
 pub enum PageOrNote {
   Page(Box<Page>),
   Note(Note),
 }
 
+impl TryFrom<Value> for PageOrNote {
+  type Error = LemmyError;
+
+  /// Selects the parser for an incoming object based on its `type`. A `Note` which has all the
+  /// fields of a post (eg top-level Mastodon statuses) is parsed as [Page], otherwise as comment.
+  /// The type is read from the compact JSON-LD form, [Page] compacts expanded objects by itself.
+  fn try_from(value: Value) -> LemmyResult<Self> {
+    let compact = compact_expanded(value.clone());
+    let kind = compact
+      .get("type")
+      .and_then(Value::as_str)
+      .unwrap_or_default()
+      .to_string();
+    Ok(match kind.as_str() {
+      "Page" | "Article" | "Video" | "Event" | "Audio" => {
+        PageOrNote::Page(Box::new(serde_json::from_value(value)?))
+      }
+      "Note" => match serde_json::from_value::<Page>(value) {
+        Ok(p) => PageOrNote::Page(Box::new(p)),
+        Err(_) => PageOrNote::Note(serde_json::from_value(compact)?),
+      },
+      _ => Err(LemmyErrorType::UnsupportedObjectType(kind))?,
+    })
+  }
+}
+
 #[async_trait::async_trait]
 impl Object for PostOrComment {
   type DataType = LemmyContext;
@@ -46,7 +74,7 @@ impl Object for PostOrComment {
   ) -> Result<Option<Self>, LemmyError> {
     let post = ApubPost::read_from_id(object_id.clone(), data).await?;
     Ok(match post {
-      Some(o) => Some(PostOrComment::Post(o)),
+      Some(o) => Some(PostOrComment::Post(Box::new(o))),
       None => ApubComment::read_from_id(object_id, data)
         .await?
         .map(PostOrComment::Comment),
@@ -80,7 +108,7 @@ impl Object for PostOrComment {
   #[tracing::instrument(skip_all)]
   async fn from_json(apub: PageOrNote, context: &Data<LemmyContext>) -> Result<Self, LemmyError> {
     Ok(match apub {
-      PageOrNote::Page(p) => PostOrComment::Post(ApubPost::from_json(*p, context).await?),
+      PageOrNote::Page(p) => PostOrComment::Post(Box::new(ApubPost::from_json(*p, context).await?)),
       PageOrNote::Note(n) => PostOrComment::Comment(ApubComment::from_json(n, context).await?),
     })
   }
@@ -100,3 +128,54 @@ impl InCommunity for PostOrComment {
     Ok(Community::read(&mut context.pool(), cid).await?.into())
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::protocol::tests::file_to_json_object;
+
+  fn dispatch(path: &str) -> LemmyResult<PageOrNote> {
+    PageOrNote::try_from(file_to_json_object::<Value>(path)?)
+  }
+
+  #[test]
+  fn test_dispatch_by_type() -> LemmyResult<()> {
+    assert!(matches!(
+      dispatch("assets/lemmy/objects/page.json")?,
+      PageOrNote::Page(_)
+    ));
+    assert!(matches!(
+      dispatch("assets/peertube/objects/video.json")?,
+      PageOrNote::Page(_)
+    ));
+    assert!(matches!(
+      dispatch("assets/mobilizon/objects/event.json")?,
+      PageOrNote::Page(_)
+    ));
+    assert!(matches!(
+      dispatch("assets/mastodon/objects/page.json")?,
+      PageOrNote::Page(_)
+    ));
+    assert!(matches!(
+      dispatch("assets/lemmy/objects/note.json")?,
+      PageOrNote::Note(_)
+    ));
+    Ok(())
+  }
+
+  #[test]
+  fn test_dispatch_unknown_type() -> LemmyResult<()> {
+    let mut json: Value = file_to_json_object("assets/lemmy/objects/page.json")?;
+    if let Some(o) = json.as_object_mut() {
+      o.insert("type".to_string(), "Question".into());
+    }
+    let err = PageOrNote::try_from(json).err().map(|e| e.error_type);
+    assert_eq!(
+      Some(LemmyErrorType::UnsupportedObjectType(
+        "Question".to_string()
+      )),
+      err
+    );
+    Ok(())
+  }
+}
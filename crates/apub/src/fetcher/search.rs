@@ -70,7 +70,7 @@ pub(crate) enum SearchableObjects {
 pub(crate) enum SearchableKinds {
   Group(Group),
   Person(Person),
-  Page(Page),
+  Page(Box<Page>),
   Note(Note),
 }
 
@@ -153,7 +153,7 @@ impl Object for SearchableObjects {
     Ok(match apub {
       SAT::Group(g) => SO::Community(ApubCommunity::from_json(g, context).await?),
       SAT::Person(p) => SO::Person(ApubPerson::from_json(p, context).await?),
-      SAT::Page(p) => SO::Post(ApubPost::from_json(p, context).await?),
+      SAT::Page(p) => SO::Post(ApubPost::from_json(*p, context).await?),
       SAT::Note(n) => SO::Comment(ApubComment::from_json(n, context).await?),
     })
   }
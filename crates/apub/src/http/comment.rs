@@ -1,6 +1,6 @@
 use crate::{
   http::{create_apub_response, create_apub_tombstone_response, redirect_remote_object},
-  objects::comment::ApubComment,
+  objects::{comment::ApubComment, TombstoneIfDeleted},
 };
 use activitypub_federation::{config::Data, traits::Object};
 use actix_web::{web::Path, HttpResponse};
@@ -24,9 +24,9 @@ pub(crate) async fn get_apub_comment(
   let comment: ApubComment = Comment::read(&mut context.pool(), id).await?.into();
   if !comment.local {
     Ok(redirect_remote_object(&comment.ap_id))
-  } else if !comment.deleted && !comment.removed {
-    create_apub_response(&comment.into_json(&context).await?)
+  } else if let Some(tombstone) = comment.tombstone_if_deleted() {
+    create_apub_tombstone_response(tombstone)
   } else {
-    create_apub_tombstone_response(comment.ap_id.clone())
+    create_apub_response(&comment.into_json(&context).await?)
   }
 }
@@ -7,7 +7,7 @@ use crate::{
     community_outbox::ApubCommunityOutbox,
   },
   http::{create_apub_response, create_apub_tombstone_response},
-  objects::{community::ApubCommunity, person::ApubPerson},
+  objects::{community::ApubCommunity, person::ApubPerson, TombstoneIfDeleted},
 };
 use activitypub_federation::{
   actix_web::inbox::receive_activity,
@@ -37,12 +37,12 @@ pub(crate) async fn get_apub_community_http(
       .await?
       .into();
 
-  if !community.deleted && !community.removed {
+  if let Some(tombstone) = community.tombstone_if_deleted() {
+    create_apub_tombstone_response(tombstone)
+  } else {
     let apub = community.into_json(&context).await?;
 
     create_apub_response(&apub)
-  } else {
-    create_apub_tombstone_response(community.actor_id.clone())
   }
 }
 
@@ -53,10 +53,15 @@ pub async fn community_inbox(
   body: Bytes,
   data: Data<LemmyContext>,
 ) -> Result<HttpResponse, LemmyError> {
-  receive_activity::<WithContext<GroupInboxActivities>, ApubPerson, LemmyContext>(
-    request, body, &data,
-  )
-  .await
+  // Activities without `@context` are only accepted with lenient_context enabled
+  if data.settings().federation.lenient_context {
+    receive_activity::<GroupInboxActivities, ApubPerson, LemmyContext>(request, body, &data).await
+  } else {
+    receive_activity::<WithContext<GroupInboxActivities>, ApubPerson, LemmyContext>(
+      request, body, &data,
+    )
+    .await
+  }
 }
 
 /// Returns an empty followers collection, only populating the size (for privacy).
@@ -16,6 +16,7 @@ use lemmy_api_common::context::LemmyContext;
 use lemmy_db_schema::{newtypes::DbUrl, source::activity::SentActivity};
 use lemmy_utils::error::{LemmyError, LemmyResult};
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use std::ops::Deref;
 use url::Url;
 
@@ -43,7 +44,15 @@ fn create_apub_response<T>(data: &T) -> LemmyResult<HttpResponse>
 where
   T: Serialize,
 {
-  let json = serde_json::to_string_pretty(&WithContext::new(data, FEDERATION_CONTEXT.clone()))?;
+  create_apub_response_with_context(data, FEDERATION_CONTEXT.clone())
+}
+
+/// Like [create_apub_response], but with a custom `@context`.
+fn create_apub_response_with_context<T>(data: &T, context: Value) -> LemmyResult<HttpResponse>
+where
+  T: Serialize,
+{
+  let json = serde_json::to_string_pretty(&WithContext::new(data, context))?;
 
   Ok(
     HttpResponse::Ok()
@@ -52,8 +61,7 @@ where
   )
 }
 
-fn create_apub_tombstone_response<T: Into<Url>>(id: T) -> LemmyResult<HttpResponse> {
-  let tombstone = Tombstone::new(id.into());
+fn create_apub_tombstone_response(tombstone: Tombstone) -> LemmyResult<HttpResponse> {
   let json = serde_json::to_string_pretty(&WithContext::new(
     tombstone,
     FEDERATION_CONTEXT.deref().clone(),
@@ -2,7 +2,7 @@ use crate::{
   activity_lists::PersonInboxActivities,
   fetcher::user_or_community::UserOrCommunity,
   http::{create_apub_response, create_apub_tombstone_response},
-  objects::person::ApubPerson,
+  objects::{person::ApubPerson, TombstoneIfDeleted},
   protocol::collections::empty_outbox::EmptyOutbox,
 };
 use activitypub_federation::{
@@ -34,12 +34,12 @@ pub(crate) async fn get_apub_person_http(
     .await?
     .into();
 
-  if !person.deleted {
+  if let Some(tombstone) = person.tombstone_if_deleted() {
+    create_apub_tombstone_response(tombstone)
+  } else {
     let apub = person.into_json(&context).await?;
 
     create_apub_response(&apub)
-  } else {
-    create_apub_tombstone_response(person.actor_id.clone())
   }
 }
 
@@ -49,10 +49,16 @@ pub async fn person_inbox(
   body: Bytes,
   data: Data<LemmyContext>,
 ) -> Result<HttpResponse, LemmyError> {
-  receive_activity::<WithContext<PersonInboxActivities>, UserOrCommunity, LemmyContext>(
-    request, body, &data,
-  )
-  .await
+  // Activities without `@context` are only accepted with lenient_context enabled
+  if data.settings().federation.lenient_context {
+    receive_activity::<PersonInboxActivities, UserOrCommunity, LemmyContext>(request, body, &data)
+      .await
+  } else {
+    receive_activity::<WithContext<PersonInboxActivities>, UserOrCommunity, LemmyContext>(
+      request, body, &data,
+    )
+    .await
+  }
 }
 
 #[tracing::instrument(skip_all)]
@@ -1,6 +1,11 @@
 use crate::{
-  http::{create_apub_response, create_apub_tombstone_response, redirect_remote_object},
-  objects::post::ApubPost,
+  http::{
+    create_apub_response_with_context,
+    create_apub_tombstone_response,
+    redirect_remote_object,
+  },
+  objects::{post::ApubPost, TombstoneIfDeleted},
+  page_context,
 };
 use activitypub_federation::{config::Data, traits::Object};
 use actix_web::{web, HttpResponse};
@@ -24,9 +29,10 @@ pub(crate) async fn get_apub_post(
   let post: ApubPost = Post::read(&mut context.pool(), id).await?.into();
   if !post.local {
     Ok(redirect_remote_object(&post.ap_id))
-  } else if !post.deleted && !post.removed {
-    create_apub_response(&post.into_json(&context).await?)
+  } else if let Some(tombstone) = post.tombstone_if_deleted() {
+    create_apub_tombstone_response(tombstone)
   } else {
-    create_apub_tombstone_response(post.ap_id.clone())
+    let page_context = page_context(context.settings());
+    create_apub_response_with_context(&post.into_json(&context).await?, page_context)
   }
 }
@@ -19,6 +19,7 @@ use actix_web::{
   http::{header, Method},
   web,
 };
+use lemmy_utils::settings::SETTINGS;
 
 pub fn config(cfg: &mut web::ServiceConfig) {
   cfg
@@ -56,6 +57,9 @@ pub fn config(cfg: &mut web::ServiceConfig) {
   cfg.service(
     web::scope("")
       .guard(InboxRequestGuard)
+      .app_data(web::PayloadConfig::new(
+        SETTINGS.federation.max_inbox_body_size,
+      ))
       .route("/c/{community_name}/inbox", web::post().to(community_inbox))
       .route("/u/{user_name}/inbox", web::post().to(person_inbox))
       .route("/inbox", web::post().to(shared_inbox)),
@@ -78,3 +82,29 @@ impl Guard for InboxRequestGuard {
     false
   }
 }
+
+#[cfg(test)]
+mod tests {
+  #![allow(clippy::unwrap_used)]
+
+  use super::*;
+  use activitypub_federation::FEDERATION_CONTENT_TYPE;
+  use actix_web::{
+    http::StatusCode,
+    test::{call_service, init_service, TestRequest},
+    App,
+  };
+
+  #[actix_web::test]
+  async fn test_inbox_rejects_large_body() {
+    let app = init_service(App::new().configure(config)).await;
+    let body = vec![b' '; SETTINGS.federation.max_inbox_body_size + 1];
+    let req = TestRequest::post()
+      .uri("/inbox")
+      .insert_header((header::CONTENT_TYPE, FEDERATION_CONTENT_TYPE))
+      .set_payload(body)
+      .to_request();
+    let res = call_service(&app, req).await;
+    assert_eq!(StatusCode::PAYLOAD_TOO_LARGE, res.status());
+  }
+}
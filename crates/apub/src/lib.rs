@@ -9,10 +9,13 @@ use lemmy_db_schema::{
   source::{activity::ReceivedActivity, instance::Instance, local_site::LocalSite},
   utils::{ActualDbPool, DbPool},
 };
-use lemmy_utils::error::{LemmyError, LemmyErrorType, LemmyResult};
+use lemmy_utils::{
+  error::{LemmyError, LemmyErrorType, LemmyResult},
+  settings::{structs::Settings, SETTINGS},
+};
 use moka::future::Cache;
 use once_cell::sync::Lazy;
-use serde_json::Value;
+use serde_json::{json, Value};
 use std::{sync::Arc, time::Duration};
 use url::Url;
 
@@ -43,6 +46,28 @@ pub static FEDERATION_CONTEXT: Lazy<Value> = Lazy::new(|| {
   ])
 });
 
+/// Minimal context for posts which Mastodon is known to handle, used with the `mastodon_compat`
+/// setting. Only declares the terms which Mastodon reads beyond ActivityStreams.
+pub static MASTODON_COMPAT_CONTEXT: Lazy<Value> = Lazy::new(|| {
+  json!([
+    "https://www.w3.org/ns/activitystreams",
+    {
+      "lemmy": "https://join-lemmy.org/ns#",
+      "sensitive": "as:sensitive",
+      "stickied": "lemmy:stickied"
+    }
+  ])
+});
+
+/// Context for outgoing posts, depending on the `mastodon_compat` setting.
+pub(crate) fn page_context(settings: &Settings) -> Value {
+  if settings.federation.mastodon_compat {
+    MASTODON_COMPAT_CONTEXT.clone()
+  } else {
+    FEDERATION_CONTEXT.clone()
+  }
+}
+
 #[derive(Clone)]
 pub struct VerifyUrlData(pub ActualDbPool);
 
@@ -194,12 +219,86 @@ pub(crate) async fn check_apub_id_valid_with_strictness(
 /// Store received activities in the database.
 ///
 /// This ensures that the same activity doesnt get received and processed more than once, which
-/// would be a waste of resources.
+/// would be a waste of resources. Recently received ids are also kept in memory, so that delivery
+/// retries are rejected without a database query.
 #[tracing::instrument(skip(data))]
 async fn insert_received_activity(
   ap_id: &Url,
   data: &Data<LemmyContext>,
 ) -> Result<(), LemmyError> {
+  static RECEIVED: Lazy<Cache<Url, ()>> = Lazy::new(|| {
+    Cache::builder()
+      .max_capacity(SETTINGS.federation.received_activity_cache_size)
+      .build()
+  });
+  if RECEIVED.contains_key(ap_id) {
+    Err(LemmyErrorType::DuplicateActivity)?
+  }
   ReceivedActivity::create(&mut data.pool(), &ap_id.clone().into()).await?;
+  RECEIVED.insert(ap_id.clone(), ()).await;
   Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+  #![allow(clippy::unwrap_used)]
+  #![allow(clippy::indexing_slicing)]
+
+  use super::*;
+  use crate::objects::tests::init_context;
+  use pretty_assertions::assert_eq;
+  use serial_test::serial;
+  use uuid::Uuid;
+
+  #[test]
+  fn test_page_context() {
+    let mut settings = SETTINGS.clone();
+    assert_eq!(*FEDERATION_CONTEXT, page_context(&settings));
+
+    settings.federation.mastodon_compat = true;
+    let context = page_context(&settings);
+    assert_eq!(*MASTODON_COMPAT_CONTEXT, context);
+    assert_eq!(
+      Some("https://www.w3.org/ns/activitystreams"),
+      context.get(0).and_then(Value::as_str)
+    );
+    let terms = context.get(1);
+    assert_eq!(
+      Some("as:sensitive"),
+      terms
+        .and_then(|t| t.get("sensitive"))
+        .and_then(Value::as_str)
+    );
+    assert_eq!(
+      Some("lemmy:stickied"),
+      terms
+        .and_then(|t| t.get("stickied"))
+        .and_then(Value::as_str)
+    );
+  }
+
+  #[tokio::test]
+  #[serial]
+  async fn test_insert_received_activity_skips_retries() -> LemmyResult<()> {
+    let context = init_context().await?;
+    let ap_id = Url::parse(&format!(
+      "https://enterprise.lemmy.ml/activities/create/{}",
+      Uuid::new_v4()
+    ))?;
+
+    insert_received_activity(&ap_id, &context).await?;
+    // the retry is rejected from memory, the database would accept it after the row is deleted
+    let deleted = ReceivedActivity::delete(&mut context.pool(), &ap_id.clone().into()).await?;
+    assert_eq!(1, deleted);
+    let res = insert_received_activity(&ap_id, &context).await;
+    assert_eq!(
+      Some(LemmyErrorType::DuplicateActivity),
+      res.err().map(|e| e.error_type)
+    );
+    assert_eq!(
+      0,
+      ReceivedActivity::delete(&mut context.pool(), &ap_id.into()).await?
+    );
+    Ok(())
+  }
+}
@@ -2,9 +2,14 @@ use crate::{
   activities::{verify_is_public, verify_person_in_community},
   check_apub_id_valid_with_strictness,
   mentions::collect_non_local_mentions,
-  objects::{read_from_string_or_source, verify_is_remote_object},
+  objects::{
+    markdown_to_html_outgoing,
+    read_from_string_or_source,
+    verify_is_remote_object,
+    TombstoneIfDeleted,
+  },
   protocol::{
-    objects::{note::Note, LanguageTag},
+    objects::{note::Note, tombstone::Tombstone, LanguageTag},
     InCommunity,
     Source,
   },
@@ -18,6 +23,7 @@ use activitypub_federation::{
 use chrono::{DateTime, Utc};
 use lemmy_api_common::{context::LemmyContext, utils::local_site_opt_to_slur_regex};
 use lemmy_db_schema::{
+  aggregates::structs::PostAggregates,
   source::{
     comment::{Comment, CommentInsertForm, CommentUpdateForm},
     community::Community,
@@ -29,7 +35,7 @@ use lemmy_db_schema::{
 };
 use lemmy_utils::{
   error::{LemmyError, LemmyErrorType},
-  utils::{markdown::markdown_to_html, slurs::remove_slurs},
+  utils::slurs::remove_slurs,
 };
 use std::ops::Deref;
 use url::Url;
@@ -109,7 +115,7 @@ impl Object for ApubComment {
       attributed_to: creator.actor_id.into(),
       to: vec![public()],
       cc: maa.ccs,
-      content: markdown_to_html(&self.content),
+      content: markdown_to_html_outgoing(&self.content, context.settings()),
       media_type: Some(MediaTypeMarkdownOrHtml::Html),
       source: Some(Source::new(self.content.clone())),
       in_reply_to,
@@ -153,6 +159,8 @@ impl Object for ApubComment {
   async fn from_json(note: Note, context: &Data<LemmyContext>) -> Result<ApubComment, LemmyError> {
     let creator = note.attributed_to.dereference(context).await?;
     let (post, parent_comment) = note.get_parents(context).await?;
+    // read existing comment if any, to know if the comment count was already incremented for it
+    let is_new = note.id.dereference_local(context).await.is_err();
 
     let content = read_from_string_or_source(&note.content, &note.media_type, &note.source);
 
@@ -177,10 +185,21 @@ impl Object for ApubComment {
     };
     let parent_comment_path = parent_comment.map(|t| t.0.path);
     let comment = Comment::create(&mut context.pool(), &form, parent_comment_path.as_ref()).await?;
+    if is_new {
+      PostAggregates::correct_seeded_comment_count(&mut context.pool(), post.id).await?;
+    }
     Ok(comment.into())
   }
 }
 
+impl TombstoneIfDeleted for ApubComment {
+  type TombstoneType = Tombstone;
+
+  fn tombstone_if_deleted(&self) -> Option<Tombstone> {
+    (self.deleted || self.removed).then(|| Tombstone::new(self.ap_id.clone().into()))
+  }
+}
+
 #[cfg(test)]
 pub(crate) mod tests {
   use super::*;
@@ -2,9 +2,13 @@ use crate::{
   activities::GetActorType,
   check_apub_id_valid,
   local_site_data_cached,
-  objects::instance::fetch_instance_actor_for_object,
+  objects::{
+    instance::fetch_instance_actor_for_object,
+    markdown_to_html_outgoing,
+    TombstoneIfDeleted,
+  },
   protocol::{
-    objects::{group::Group, Endpoints, LanguageTag},
+    objects::{group::Group, tombstone::Tombstone, Endpoints, LanguageTag},
     ImageObject,
     Source,
   },
@@ -28,7 +32,7 @@ use lemmy_db_schema::{
   traits::{ApubActor, Crud},
 };
 use lemmy_db_views_actor::structs::CommunityFollowerView;
-use lemmy_utils::{error::LemmyError, spawn_try_task, utils::markdown::markdown_to_html};
+use lemmy_utils::{error::LemmyError, spawn_try_task};
 use std::ops::Deref;
 use url::Url;
 
@@ -91,7 +95,10 @@ impl Object for ApubCommunity {
       id: self.id().into(),
       preferred_username: self.name.clone(),
       name: Some(self.title.clone()),
-      summary: self.description.as_ref().map(|b| markdown_to_html(b)),
+      summary: self
+        .description
+        .as_ref()
+        .map(|b| markdown_to_html_outgoing(b, data.settings())),
       source: self.description.clone().map(Source::new),
       icon: self.icon.clone().map(ImageObject::new),
       image: self.banner.clone().map(ImageObject::new),
@@ -108,6 +115,8 @@ impl Object for ApubCommunity {
       published: Some(self.published),
       updated: self.updated,
       posting_restricted_to_mods: Some(self.posting_restricted_to_mods),
+      blocked_url_domains: Some(self.blocked_url_domains.clone()),
+      required_url_host: self.required_url_host.clone(),
       attributed_to: Some(generate_moderators_url(&self.actor_id)?.into()),
     };
     Ok(group)
@@ -156,6 +165,14 @@ impl Object for ApubCommunity {
   }
 }
 
+impl TombstoneIfDeleted for ApubCommunity {
+  type TombstoneType = Tombstone;
+
+  fn tombstone_if_deleted(&self) -> Option<Tombstone> {
+    (self.deleted || self.removed).then(|| Tombstone::new(self.actor_id.clone().into()))
+  }
+}
+
 impl Actor for ApubCommunity {
   fn id(&self) -> Url {
     self.actor_id.inner().clone()
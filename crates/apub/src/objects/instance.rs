@@ -2,9 +2,9 @@ use crate::{
   activities::GetActorType,
   check_apub_id_valid_with_strictness,
   local_site_data_cached,
-  objects::read_from_string_or_source_opt,
+  objects::{markdown_to_html_outgoing, read_from_string_or_source_opt, TombstoneIfDeleted},
   protocol::{
-    objects::{instance::Instance, LanguageTag},
+    objects::{instance::Instance, tombstone::Tombstone, LanguageTag},
     ImageObject,
     Source,
   },
@@ -31,10 +31,7 @@ use lemmy_db_schema::{
 };
 use lemmy_utils::{
   error::LemmyError,
-  utils::{
-    markdown::markdown_to_html,
-    slurs::{check_slurs, check_slurs_opt},
-  },
+  utils::slurs::{check_slurs, check_slurs_opt},
 };
 use std::ops::Deref;
 use tracing::debug;
@@ -92,7 +89,10 @@ impl Object for ApubSite {
       kind: ApplicationType::Application,
       id: self.id().into(),
       name: self.name.clone(),
-      content: self.sidebar.as_ref().map(|d| markdown_to_html(d)),
+      content: self
+        .sidebar
+        .as_ref()
+        .map(|d| markdown_to_html_outgoing(d, data.settings())),
       source: self.sidebar.clone().map(Source::new),
       summary: self.description.clone(),
       media_type: self.sidebar.as_ref().map(|_| MediaTypeHtml::Html),
@@ -154,6 +154,10 @@ impl Object for ApubSite {
   }
 }
 
+impl TombstoneIfDeleted for ApubSite {
+  type TombstoneType = Tombstone;
+}
+
 impl Actor for ApubSite {
   fn id(&self) -> Url {
     self.actor_id.inner().clone()
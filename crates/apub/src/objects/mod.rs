@@ -1,8 +1,15 @@
 use crate::protocol::Source;
-use activitypub_federation::protocol::values::MediaTypeMarkdownOrHtml;
+use activitypub_federation::{
+  protocol::{values::MediaTypeMarkdownOrHtml, verification::verify_domains_match},
+  traits::Object,
+};
 use anyhow::anyhow;
 use html2md::parse_html;
-use lemmy_utils::{error::LemmyError, settings::structs::Settings};
+use lemmy_utils::{
+  error::{LemmyError, LemmyResult},
+  settings::structs::Settings,
+  utils::markdown::{markdown_to_html_with_options, MarkdownOptions},
+};
 use url::Url;
 
 pub mod comment;
@@ -12,14 +19,45 @@ pub mod person;
 pub mod post;
 pub mod private_message;
 
+/// Serving an object over HTTP or sending it out needs to check if it was deleted in the meantime.
+pub(crate) trait TombstoneIfDeleted: Object {
+  type TombstoneType;
+
+  /// Returns the tombstone which replaces the object if it was deleted or removed, and None if it
+  /// can be served normally.
+  fn tombstone_if_deleted(&self) -> Option<Self::TombstoneType> {
+    None
+  }
+}
+
+/// Received objects must be on the same instance as the actor who signed them, otherwise any
+/// instance could forge objects of another.
+pub(crate) trait VerifyOrigin {
+  /// Id of the object, whose host is compared with the signer.
+  fn ap_id(&self) -> &Url;
+
+  /// Checks that the object is on the same host as `signing_actor`.
+  ///
+  /// Mod actions are the exception, as a moderator may be on a different instance than the
+  /// object. These need to be verified with `verify_mod_action` instead of calling this.
+  fn verify_origin(&self, signing_actor: &Url) -> LemmyResult<()> {
+    verify_domains_match(self.ap_id(), signing_actor)?;
+    Ok(())
+  }
+}
+
 pub(crate) fn read_from_string_or_source(
   content: &str,
   media_type: &Option<MediaTypeMarkdownOrHtml>,
   source: &Option<Source>,
 ) -> String {
   if let Some(s) = source {
-    // markdown sent by lemmy in source field
-    s.content.clone()
+    match &s.media_type {
+      // markdown sent by lemmy in source field
+      MediaTypeMarkdownOrHtml::Markdown => s.content.clone(),
+      // html source, needs to be converted like content
+      MediaTypeMarkdownOrHtml::Html => parse_html(&s.content),
+    }
   } else if media_type == &Some(MediaTypeMarkdownOrHtml::Markdown) {
     // markdown sent by peertube in content field
     content.to_string()
@@ -39,6 +77,17 @@ pub(crate) fn read_from_string_or_source_opt(
     .map(|content| read_from_string_or_source(content, media_type, source))
 }
 
+/// Renders markdown for outgoing objects, loading images through the image proxy and embedding
+/// media links if configured.
+pub(crate) fn markdown_to_html_outgoing(text: &str, settings: &Settings) -> String {
+  let options = MarkdownOptions {
+    image_proxy_template: settings.federation.image_proxy_template.as_deref(),
+    media_embed_hosts: &settings.federation.media_embed_hosts,
+    html: Some(&settings.html),
+  };
+  markdown_to_html_with_options(text, &options)
+}
+
 /// When for example a Post is made in a remote community, the community will send it back,
 /// wrapped in Announce. If we simply receive this like any other federated object, overwrite the
 /// existing, local Post. In particular, it will set the field local = false, so that the object
@@ -59,8 +108,9 @@ pub(crate) mod tests {
   use lemmy_api_common::{context::LemmyContext, request::client_builder};
   use lemmy_db_schema::{source::secret::Secret, utils::build_db_pool_for_tests};
   use lemmy_utils::{error::LemmyResult, rate_limit::RateLimitCell, settings::SETTINGS};
-  use reqwest::{Request, Response};
-  use reqwest_middleware::{ClientBuilder, Middleware, Next};
+  use reqwest::{Request, Response, StatusCode};
+  use reqwest_middleware::{ClientBuilder, ClientWithMiddleware, Middleware, Next};
+  use std::collections::HashMap;
   use task_local_extensions::Extensions;
 
   struct BlockedMiddleware;
@@ -78,8 +128,70 @@ pub(crate) mod tests {
     }
   }
 
+  /// A reqwest middleware which answers requests to the given urls with a fixed status and body,
+  /// and blocks all other requests
+  struct StubMiddleware(HashMap<String, (StatusCode, String)>);
+
+  #[async_trait::async_trait]
+  impl Middleware for StubMiddleware {
+    async fn handle(
+      &self,
+      req: Request,
+      _extensions: &mut Extensions,
+      _next: Next<'_>,
+    ) -> reqwest_middleware::Result<Response> {
+      let (status, body) = self
+        .0
+        .get(req.url().as_str())
+        .ok_or_else(|| anyhow!("Network requests not allowed"))?;
+      let res = http::Response::builder()
+        .status(*status)
+        .body(body.clone())
+        .map_err(anyhow::Error::from)?;
+      Ok(res.into())
+    }
+  }
+
   // TODO: would be nice if we didnt have to use a full context for tests.
   pub(crate) async fn init_context() -> LemmyResult<Data<LemmyContext>> {
+    init_context_with(|context| context).await
+  }
+
+  /// Creates a test context, with changes such as a different spam classifier.
+  pub(crate) async fn init_context_with(
+    modify: impl FnOnce(LemmyContext) -> LemmyContext,
+  ) -> LemmyResult<Data<LemmyContext>> {
+    build_context(modify, None).await
+  }
+
+  /// Creates a test context where federation requests to the given urls get the given response
+  /// bodies.
+  pub(crate) async fn init_context_with_responses(
+    responses: HashMap<String, String>,
+  ) -> LemmyResult<Data<LemmyContext>> {
+    let responses = responses
+      .into_iter()
+      .map(|(url, body)| (url, (StatusCode::OK, body)))
+      .collect();
+    init_context_with_status_responses(responses).await
+  }
+
+  /// Like [init_context_with_responses], but also with the http status of each response, eg to
+  /// simulate deleted objects.
+  pub(crate) async fn init_context_with_status_responses(
+    responses: HashMap<String, (StatusCode, String)>,
+  ) -> LemmyResult<Data<LemmyContext>> {
+    let client = reqwest::Client::builder().build()?;
+    let client = ClientBuilder::new(client)
+      .with(StubMiddleware(responses))
+      .build();
+    build_context(|context| context, Some(client)).await
+  }
+
+  async fn build_context(
+    modify: impl FnOnce(LemmyContext) -> LemmyContext,
+    federation_client: Option<ClientWithMiddleware>,
+  ) -> LemmyResult<Data<LemmyContext>> {
     // call this to run migrations
     let pool = build_db_pool_for_tests().await;
 
@@ -93,12 +205,18 @@ pub(crate) mod tests {
 
     let rate_limit_cell = RateLimitCell::with_test_config();
 
-    let context = LemmyContext::create(pool, client, secret, rate_limit_cell.clone());
-    let config = FederationConfig::builder()
-      .domain("example.com")
-      .app_data(context)
-      .build()
-      .await?;
+    let context = modify(LemmyContext::create(
+      pool,
+      client,
+      secret,
+      rate_limit_cell.clone(),
+    ));
+    let mut config = FederationConfig::builder();
+    config.domain("example.com").app_data(context);
+    if let Some(client) = federation_client {
+      config.client(client);
+    }
+    let config = config.build().await?;
     Ok(config.to_request_data())
   }
 }
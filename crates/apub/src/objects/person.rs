@@ -2,10 +2,15 @@ use crate::{
   activities::GetActorType,
   check_apub_id_valid_with_strictness,
   local_site_data_cached,
-  objects::{instance::fetch_instance_actor_for_object, read_from_string_or_source_opt},
+  objects::{
+    instance::fetch_instance_actor_for_object,
+    read_from_string_or_source_opt,
+    TombstoneIfDeleted,
+  },
   protocol::{
     objects::{
       person::{Person, UserTypes},
+      tombstone::Tombstone,
       Endpoints,
     },
     ImageObject,
@@ -89,7 +94,7 @@ impl Object for ApubPerson {
   }
 
   #[tracing::instrument(skip_all)]
-  async fn into_json(self, _context: &Data<Self::DataType>) -> Result<Person, LemmyError> {
+  async fn into_json(self, context: &Data<Self::DataType>) -> Result<Person, LemmyError> {
     let kind = if self.bot_account {
       UserTypes::Service
     } else {
@@ -101,7 +106,10 @@ impl Object for ApubPerson {
       id: self.actor_id.clone().into(),
       preferred_username: self.name.clone(),
       name: self.display_name.clone(),
-      summary: self.bio.as_ref().map(|b| markdown_to_html(b)),
+      summary: self
+        .bio
+        .as_ref()
+        .map(|b| markdown_to_html(b, &context.settings().html)),
       source: self.bio.clone().map(Source::new),
       icon: self.avatar.clone().map(ImageObject::new),
       image: self.banner.clone().map(ImageObject::new),
@@ -114,6 +122,7 @@ impl Object for ApubPerson {
       public_key: self.public_key(),
       updated: self.updated,
       inbox: self.inbox_url.clone().into(),
+      also_known_as: vec![],
     };
     Ok(person)
   }
@@ -159,6 +168,7 @@ impl Object for ApubPerson {
       avatar: person.icon.map(|i| i.url.into()),
       banner: person.image.map(|i| i.url.into()),
       published: person.published.map(Into::into),
+      published_unknown: Some(person.published.is_none()),
       updated: person.updated.map(Into::into),
       actor_id: Some(person.id.into()),
       bio,
@@ -178,6 +188,16 @@ impl Object for ApubPerson {
   }
 }
 
+impl TombstoneIfDeleted for ApubPerson {
+  type TombstoneType = Tombstone;
+
+  fn tombstone_if_deleted(&self) -> Option<Tombstone> {
+    self
+      .deleted
+      .then(|| Tombstone::new(self.actor_id.clone().into()))
+  }
+}
+
 impl Actor for ApubPerson {
   fn id(&self) -> Url {
     self.actor_id.inner().clone()
@@ -243,6 +263,35 @@ pub(crate) mod tests {
     assert_eq!(person.display_name, Some("Jean-Luc Picard".to_string()));
     assert!(!person.local);
     assert_eq!(person.bio.as_ref().map(std::string::String::len), Some(39));
+    assert!(!person.published_unknown);
+
+    cleanup((person, site), &context).await?;
+    Ok(())
+  }
+
+  #[tokio::test]
+  #[serial]
+  async fn test_parse_person_without_published() -> LemmyResult<()> {
+    let context = init_context().await?;
+    let site = parse_lemmy_instance(&context).await?;
+    let json: Person = file_to_json_object("assets/lemmy/objects/person.json")?;
+    let published = json.published;
+    assert!(published.is_some());
+
+    let person = ApubPerson::from_json(
+      Person {
+        published: None,
+        ..json.clone()
+      },
+      &context,
+    )
+    .await?;
+    assert!(person.published_unknown);
+
+    // the flag is cleared once the actor includes its creation time
+    let person = ApubPerson::from_json(json, &context).await?;
+    assert!(!person.published_unknown);
+    assert_eq!(published, Some(person.published));
 
     cleanup((person, site), &context).await?;
     Ok(())
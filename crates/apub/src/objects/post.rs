@@ -1,11 +1,19 @@
 use crate::{
-  activities::{verify_is_public, verify_person_in_community},
+  activities::{verify_dereferenced_person_in_community, verify_is_public},
   check_apub_id_valid_with_strictness,
+  fetcher::{actor_domain, dereference_person},
   local_site_data_cached,
-  objects::{read_from_string_or_source_opt, verify_is_remote_object},
+  objects::{
+    markdown_to_html_outgoing,
+    person::ApubPerson,
+    verify_is_remote_object,
+    TombstoneIfDeleted,
+    VerifyOrigin,
+  },
   protocol::{
     objects::{
-      page::{Attachment, AttributedTo, Page, PageType},
+      page::{Attachment, AttributedTo, Generator, Page, PageType, Place, MEDIA_TYPE_HTML},
+      tombstone::Tombstone,
       LanguageTag,
     },
     ImageObject,
@@ -15,42 +23,78 @@ use crate::{
 };
 use activitypub_federation::{
   config::Data,
+  error::Error as ActivityPubError,
+  fetch::object_id::ObjectId,
   kinds::public,
-  protocol::{values::MediaTypeMarkdownOrHtml, verification::verify_domains_match},
   traits::Object,
 };
 use anyhow::anyhow;
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Duration, Utc};
 use html2text::{from_read_with_decorator, render::text_renderer::TrivialDecorator};
+use itertools::Itertools;
 use lemmy_api_common::{
   context::LemmyContext,
+  post::SiteMetadata,
   request::fetch_site_data,
+  spam::SpamCheckPost,
+  thumbnail::Thumbnail,
   utils::{is_mod_or_admin, local_site_opt_to_slur_regex},
 };
 use lemmy_db_schema::{
   self,
+  aggregates::structs::{CommunityAggregates, PostAggregates},
+  newtypes::{CommunityId, DbUrl, LanguageId, PersonId, PostId},
   source::{
-    community::Community,
+    community::{Community, CommunityFollower},
+    language::Language,
     local_site::LocalSite,
     moderator::{ModLockPost, ModLockPostForm},
     person::Person,
-    post::{Post, PostInsertForm, PostUpdateForm},
+    post::{
+      Post,
+      PostEditHistory,
+      PostEditHistoryForm,
+      PostInsertForm,
+      PostLocation,
+      PostLocationForm,
+      PostRawApub,
+      PostRawApubForm,
+      PostSummary,
+      PostTranslation,
+      PostUpdateForm,
+    },
   },
-  traits::Crud,
+  traits::{ApubActor, Crud},
+  utils::DbPool,
 };
 use lemmy_utils::{
-  error::LemmyError,
+  error::{LemmyError, LemmyErrorType, LemmyResult},
+  settings::{structs::Settings, SETTINGS},
   utils::{
-    markdown::markdown_to_html,
-    slurs::{check_slurs_opt, remove_slurs},
+    markdown::{markdown_link_count, markdown_word_count},
+    mention::scrape_text_for_mention_links,
+    slurs::{build_slur_regex, check_slurs_opt, remove_slurs},
     validation::check_url_scheme,
   },
 };
-use std::ops::Deref;
+use moka::sync::Cache;
+use once_cell::sync::Lazy;
+use regex::{Regex, RegexBuilder};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::{
+  collections::{BTreeMap, HashMap},
+  future::Future,
+  ops::Deref,
+  sync::Arc,
+  time::Duration as StdDuration,
+};
 use stringreader::StringReader;
-use url::Url;
+use tracing::{debug, info, warn};
+use url::{form_urlencoded, Url};
 
 const MAX_TITLE_LENGTH: usize = 200;
+const MAX_IMAGE_URL_LENGTH: usize = 2000;
 
 #[derive(Clone, Debug)]
 pub struct ApubPost(pub(crate) Post);
@@ -68,6 +112,248 @@ impl From<Post> for ApubPost {
   }
 }
 
+/// A post without its body, for feeds and lists which only show title, link and thumbnail.
+#[derive(Clone, Debug)]
+pub struct ApubPostSummary(pub(crate) PostSummary);
+
+impl Deref for ApubPostSummary {
+  type Target = PostSummary;
+  fn deref(&self) -> &Self::Target {
+    &self.0
+  }
+}
+
+/// Everything besides the posts themselves which is needed to serialize them as pages.
+struct PageParts {
+  creators: HashMap<PersonId, Person>,
+  communities: HashMap<CommunityId, Community>,
+  languages: HashMap<LanguageId, Language>,
+  locations: HashMap<PostId, PostLocation>,
+  /// Only read if translations are federated
+  translations: HashMap<PostId, Vec<PostTranslation>>,
+}
+
+impl PageParts {
+  /// Reads the parts of all given posts, with one query per table regardless of the number of
+  /// posts.
+  async fn read(
+    posts: &[ApubPost],
+    settings: &Settings,
+    pool: &mut DbPool<'_>,
+  ) -> LemmyResult<Self> {
+    let post_ids: Vec<_> = posts.iter().map(|p| p.id).collect();
+    let creator_ids: Vec<_> = posts.iter().map(|p| p.creator_id).unique().collect();
+    let community_ids: Vec<_> = posts.iter().map(|p| p.community_id).unique().collect();
+    let creators = Person::read_many(pool, &creator_ids)
+      .await?
+      .into_iter()
+      .map(|p| (p.id, p))
+      .collect();
+    let communities = Community::read_many(pool, &community_ids)
+      .await?
+      .into_iter()
+      .map(|c| (c.id, c))
+      .collect();
+    let locations = PostLocation::read_many(pool, &post_ids)
+      .await?
+      .into_iter()
+      .map(|l| (l.post_id, l))
+      .collect();
+    let translations = if settings.federation.federate_translations {
+      PostTranslation::list_many(pool, &post_ids)
+        .await?
+        .into_iter()
+        .into_group_map_by(|t| t.post_id)
+    } else {
+      HashMap::new()
+    };
+    let language_ids: Vec<_> = posts
+      .iter()
+      .map(|p| p.language_id)
+      .chain(translations.values().flatten().map(|t| t.language_id))
+      .unique()
+      .collect();
+    let languages = Language::read_many(pool, &language_ids)
+      .await?
+      .into_iter()
+      .map(|l| (l.id, l))
+      .collect();
+    Ok(PageParts {
+      creators,
+      communities,
+      languages,
+      locations,
+      translations,
+    })
+  }
+
+  fn language_tag(&self, language_id: LanguageId) -> Option<LanguageTag> {
+    self
+      .languages
+      .get(&language_id)
+      .cloned()
+      .and_then(LanguageTag::from_language)
+  }
+}
+
+impl ApubPost {
+  /// Reads a post from the database without fetching it. `Ok(None)` means that the post isn't
+  /// known locally, errors are only returned if the database query failed.
+  pub async fn read_local(
+    object_id: &ObjectId<ApubPost>,
+    context: &Data<LemmyContext>,
+  ) -> LemmyResult<Option<ApubPost>> {
+    Self::read_from_id(object_id.inner().clone(), context).await
+  }
+
+  /// Like [ObjectId::dereference], but returns `Ok(None)` if the post doesn't exist or was deleted
+  /// on the remote instance, so that only real failures like network errors are returned as `Err`.
+  pub async fn resolve(
+    object_id: &ObjectId<ApubPost>,
+    context: &Data<LemmyContext>,
+  ) -> LemmyResult<Option<ApubPost>> {
+    match object_id.dereference(context).await {
+      Ok(post) => Ok(Some(post)),
+      Err(e) if is_not_found(&e) => Ok(None),
+      Err(e) => Err(e),
+    }
+  }
+
+  /// Reads a post by its activitypub id like [Object::read_from_id], but skips loading the body.
+  pub async fn read_summary_from_apub_id(
+    object_id: Url,
+    context: &Data<LemmyContext>,
+  ) -> LemmyResult<Option<ApubPostSummary>> {
+    Ok(
+      Post::read_summary_from_apub_id(&mut context.pool(), object_id)
+        .await?
+        .map(ApubPostSummary),
+    )
+  }
+
+  /// Serializes the post like [Object::into_json], with everything besides the post already read.
+  fn into_json_with(self, parts: &PageParts, settings: &Settings) -> LemmyResult<Page> {
+    let creator = parts
+      .creators
+      .get(&self.creator_id)
+      .ok_or(LemmyErrorType::CouldntFindPerson)?;
+    let community = parts
+      .communities
+      .get(&self.community_id)
+      .ok_or(LemmyErrorType::CouldntFindCommunity)?;
+    let mut page = Page::from_post_parts(&self, creator, community, settings);
+    page.language = parts.language_tag(self.language_id);
+    page.location = parts.locations.get(&self.id).map(|l| Place {
+      kind: Default::default(),
+      name: l.name.clone(),
+      latitude: l.latitude,
+      longitude: l.longitude,
+    });
+    if settings.federation.federate_translations {
+      page.content_map = self.content_map(parts, settings);
+    }
+    Ok(page)
+  }
+
+  /// The rendered body in the post language and in all stored translations, keyed by language
+  /// code. None if the post has no translations.
+  fn content_map(
+    &self,
+    parts: &PageParts,
+    settings: &Settings,
+  ) -> Option<BTreeMap<String, String>> {
+    let translations = parts.translations.get(&self.id)?;
+    let mut content_map = BTreeMap::new();
+    let original = self.body.as_ref().map(|b| (self.language_id, b));
+    let translations = translations.iter().map(|t| (t.language_id, &t.body));
+    for (language_id, body) in original.into_iter().chain(translations) {
+      // Undetermined language can't be used as key
+      if let Some(language) = parts.language_tag(language_id) {
+        let content = render_markdown_cached(body, settings);
+        content_map.insert(language.identifier, content.to_string());
+      }
+    }
+    Some(content_map)
+  }
+
+  /// Serializes multiple posts like [Object::into_json]. Everything besides the posts is read with
+  /// one query per table for all posts, instead of several queries per post.
+  pub(crate) async fn into_json_batch(
+    posts: Vec<ApubPost>,
+    context: &Data<LemmyContext>,
+  ) -> LemmyResult<Vec<Page>> {
+    let settings = context.settings();
+    let parts = PageParts::read(&posts, settings, &mut context.pool()).await?;
+    posts
+      .into_iter()
+      .map(|p| p.into_json_with(&parts, settings))
+      .collect()
+  }
+
+  /// Reverts [Object::delete], eg when receiving `Undo/Delete`.
+  pub async fn restore(self, context: &Data<LemmyContext>) -> LemmyResult<()> {
+    if self.deleted {
+      let form = PostUpdateForm {
+        deleted: Some(false),
+        ..Default::default()
+      };
+      Post::update(&mut context.pool(), self.id, &form).await?;
+    }
+    Ok(())
+  }
+
+  /// Returns the activitypub id of the post with the given database id, for addressing
+  /// activities which only need to refer to the post.
+  pub async fn read_ap_id(post_id: PostId, context: &Data<LemmyContext>) -> LemmyResult<Url> {
+    Ok(Post::read_ap_id(&mut context.pool(), post_id).await?.into())
+  }
+
+  /// Link to the post on this instance. For federated posts this is different from the
+  /// activitypub id, as that points to the original instance.
+  pub fn local_url(&self, settings: &Settings) -> LemmyResult<Url> {
+    let url = format!("{}/post/{}", settings.get_protocol_and_hostname(), self.id);
+    Ok(Url::parse(&url)?)
+  }
+
+  /// Fetches the metadata of the post link again, and updates the link preview with it. Other
+  /// fields like title or body are left unchanged. This can be used to refresh stale previews.
+  #[tracing::instrument(skip_all)]
+  pub async fn thumbnail_refresh(self, context: &Data<LemmyContext>) -> LemmyResult<ApubPost> {
+    let Some(url) = &self.url else {
+      return Ok(self);
+    };
+    let (metadata, thumbnail) = fetch_site_data(context, Some(url.inner()), true).await;
+    self.update_embed(metadata, thumbnail, context).await
+  }
+
+  async fn update_embed(
+    self,
+    metadata: Option<SiteMetadata>,
+    thumbnail: Option<Thumbnail>,
+    context: &Data<LemmyContext>,
+  ) -> LemmyResult<ApubPost> {
+    let (embed_title, embed_description, embed_video_url) = metadata
+      .map(|u| (u.title, u.description, u.embed_video_url))
+      .unwrap_or_default();
+    let form = PostUpdateForm {
+      embed_title: Some(embed_title),
+      embed_description: Some(embed_description),
+      embed_video_url: Some(embed_video_url),
+      // keep the previous thumbnail if no new one could be fetched, the alt text doesn't describe
+      // a new one
+      alt_text: thumbnail.is_some().then_some(None),
+      thumbnail_blurhash: thumbnail.as_ref().map(|t| t.blurhash.clone()),
+      thumbnail_url: Some(thumbnail.map(|t| t.url).or(self.thumbnail_url.clone())),
+      ..Default::default()
+    };
+    Ok(
+      Post::update(&mut context.pool(), self.id, &form)
+        .await?
+        .into(),
+    )
+  }
+}
+
 #[async_trait::async_trait]
 impl Object for ApubPost {
   type DataType = LemmyContext;
@@ -105,33 +391,9 @@ impl Object for ApubPost {
   // Turn a Lemmy post into an ActivityPub page that can be sent out over the network.
   #[tracing::instrument(skip_all)]
   async fn into_json(self, context: &Data<Self::DataType>) -> Result<Page, LemmyError> {
-    let creator_id = self.creator_id;
-    let creator = Person::read(&mut context.pool(), creator_id).await?;
-    let community_id = self.community_id;
-    let community = Community::read(&mut context.pool(), community_id).await?;
-    let language = LanguageTag::new_single(self.language_id, &mut context.pool()).await?;
-
-    let page = Page {
-      kind: PageType::Page,
-      id: self.ap_id.clone().into(),
-      attributed_to: AttributedTo::Lemmy(creator.actor_id.into()),
-      to: vec![community.actor_id.clone().into(), public()],
-      cc: vec![],
-      name: Some(self.name.clone()),
-      content: self.body.as_ref().map(|b| markdown_to_html(b)),
-      media_type: Some(MediaTypeMarkdownOrHtml::Html),
-      source: self.body.clone().map(Source::new),
-      attachment: self.url.clone().map(Attachment::new).into_iter().collect(),
-      image: self.thumbnail_url.clone().map(ImageObject::new),
-      comments_enabled: Some(!self.locked),
-      sensitive: Some(self.nsfw),
-      language,
-      published: Some(self.published),
-      updated: self.updated,
-      audience: Some(community.actor_id.into()),
-      in_reply_to: None,
-    };
-    Ok(page)
+    let settings = context.settings();
+    let parts = PageParts::read(std::slice::from_ref(&self), settings, &mut context.pool()).await?;
+    self.into_json_with(&parts, settings)
   }
 
   #[tracing::instrument(skip_all)]
@@ -140,30 +402,45 @@ impl Object for ApubPost {
     expected_domain: &Url,
     context: &Data<Self::DataType>,
   ) -> Result<(), LemmyError> {
-    // We can't verify the domain in case of mod action, because the mod may be on a different
-    // instance from the post author.
-    if !page.is_mod_action(context).await? {
-      verify_domains_match(page.id.inner(), expected_domain)?;
-      verify_is_remote_object(page.id.inner(), context.settings())?;
-    };
-
-    let community = page.community(context).await?;
-    check_apub_id_valid_with_strictness(page.id.inner(), community.local, context).await?;
-    verify_person_in_community(&page.creator()?, &community, context).await?;
-
-    let local_site_data = local_site_data_cached(&mut context.pool()).await?;
-    let slur_regex = &local_site_opt_to_slur_regex(&local_site_data.local_site);
-    check_slurs_opt(&page.name, slur_regex)?;
-
-    verify_domains_match(page.creator()?.inner(), page.id.inner())?;
-    verify_is_public(&page.to, &page.cc)?;
-    Ok(())
+    let relayed = is_relay(
+      expected_domain,
+      &context.settings().federation.relay_domains,
+    );
+    verify_page(page, expected_domain, &FETCHED_PAGE_TYPES, relayed, context).await
   }
 
   #[tracing::instrument(skip_all)]
   async fn from_json(page: Page, context: &Data<Self::DataType>) -> Result<ApubPost, LemmyError> {
-    let creator = page.creator()?.dereference(context).await?;
+    let timeout = StdDuration::from_secs(context.settings().federation.post_import_timeout);
+    with_timeout(ApubPost::import(page, context), timeout).await
+  }
+}
+
+impl ApubPost {
+  /// Stores a received post, see [Object::from_json].
+  async fn import(mut page: Page, context: &Data<LemmyContext>) -> LemmyResult<ApubPost> {
+    let raw = raw_apub(&page, context.settings().federation.store_raw_apub);
+    let federation = &context.settings().federation;
+    limit_attachments(
+      &mut page,
+      federation.max_attachments_per_post,
+      federation.reject_excess_attachments,
+    )?;
+    let mut requests = ImportRequests::new(context);
+    let creator = dereference_person(&page.creator()?, context).await?;
+    requests.author = requests.since_last(context);
     let community = page.community(context).await?;
+    requests.community = requests.since_last(context);
+    check_local_followers(
+      context
+        .settings()
+        .federation
+        .skip_posts_without_local_followers,
+      &page,
+      &community,
+      context,
+    )
+    .await?;
     if community.posting_restricted_to_mods {
       is_mod_or_admin(&mut context.pool(), &creator, community.id).await?;
     }
@@ -193,50 +470,145 @@ impl Object for ApubPost {
     }
 
     // read existing, local post if any (for generating mod log)
-    let old_post = page.id.dereference_local(context).await;
+    let old_post = ApubPost::read_local(&page.id, context).await?;
 
-    let form = if !page.is_mod_action(context).await? {
-      let first_attachment = page.attachment.into_iter().map(Attachment::url).next();
+    let is_mod_action = page.is_mod_action(context).await?;
+    let location = page.location.clone();
+    let language = page.language();
+    let replies = page.replies.as_ref().map(|r| r.total_items);
+    let conversation_id = page.conversation_id();
+    let form = if !is_mod_action {
+      let audio_url = page.audio_url();
+      let body = page.body(&context.settings().federation.accepted_media_types);
+      let duration = page.duration();
+      let first_attachment = page
+        .attachment_url()
+        .filter(|u| !is_link_to_self(u, page.id.inner()));
       let url = if first_attachment.is_some() {
         first_attachment
       } else if page.kind == PageType::Video {
         // we cant display videos directly, so insert a link to external video page
         Some(page.id.inner().clone())
+      } else if page.kind == PageType::Audio {
+        // link to the audio file if available, otherwise to the external page
+        audio_url.or_else(|| Some(page.id.inner().clone()))
       } else {
         None
       };
       check_url_scheme(&url)?;
+      let url = url.map(|u| {
+        localize_self_url(
+          u,
+          &context.settings().hostname,
+          context.settings().get_protocol_string(),
+        )
+      });
 
       let local_site = LocalSite::read(&mut context.pool()).await.ok();
       let include_image = false;
 
       // Only fetch metadata if the post has a url and was not seen previously. We dont want to
-      // waste resources by fetching metadata for the same post multiple times.
+      // waste resources by fetching metadata for the same post multiple times. It is also not
+      // needed if the post includes a preview of the link.
       // Additionally, only fetch image if content is not sensitive or is allowed on local site.
-      let (metadata_res, thumbnail) = match &url {
-        Some(url) if old_post.is_err() => {
-          fetch_site_data(
-            context.client(),
-            context.settings(),
-            Some(url),
-            include_image,
-          )
-          .await
+      let preview = page.preview;
+      let (metadata_res, thumbnail) = match (&url, &preview) {
+        (_, Some(preview)) => (
+          None,
+          preview
+            .image()
+            .filter(|u| u.as_str().len() <= MAX_IMAGE_URL_LENGTH)
+            .map(Thumbnail::from),
+        ),
+        (Some(url), None) if old_post.is_none() => {
+          fetch_site_data(context, Some(url), include_image).await
         }
         _ => (None, None),
       };
-      // If no image was included with metadata, use post image instead when available.
-      let thumbnail_url = thumbnail.or_else(|| page.image.map(|i| i.url.into()));
+      // If no image was included with metadata, use post image instead when available, along
+      // with its alt text and blurhash. Ignore absurdly long image urls instead of storing them.
+      let (thumbnail_url, alt_text, thumbnail_blurhash) = match thumbnail {
+        Some(thumbnail) => (Some(thumbnail.url), None, thumbnail.blurhash),
+        None => page
+          .image
+          .or(page.icon)
+          .filter(|i| i.url.as_str().len() <= MAX_IMAGE_URL_LENGTH)
+          .map(|i| (Some(i.url.into()), i.name, i.blurhash))
+          .unwrap_or_default(),
+      };
+      let thumbnail_url = with_fallback_thumbnail(
+        thumbnail_url,
+        &url,
+        context
+          .settings()
+          .federation
+          .fallback_thumbnail_url
+          .as_ref(),
+      );
 
-      let (embed_title, embed_description, embed_video_url) = metadata_res
-        .map(|u| (u.title, u.description, u.embed_video_url))
-        .unwrap_or_default();
+      // Keep the canonical link from when the post was first received, metadata is only fetched
+      // for new posts
+      let canonical_url = metadata_res
+        .as_ref()
+        .and_then(|m| m.canonical_url.clone())
+        .or_else(|| old_post.as_ref().and_then(|p| p.canonical_url.clone()));
+      let (embed_title, embed_description, embed_video_url) = match preview {
+        Some(preview) => preview.embed(),
+        None => metadata_res
+          .map(|u| (u.title, u.description, u.embed_video_url))
+          .unwrap_or_default(),
+      };
       let slur_regex = &local_site_opt_to_slur_regex(&local_site);
+      let name = replace_generic_name(
+        name,
+        embed_title.as_deref(),
+        GENERIC_POST_NAME_REGEX.as_ref(),
+        slur_regex,
+      );
+
+      let body = body.map(|s| remove_slurs(&s, slur_regex)).map(|s| {
+        if context.settings().federation.collapse_blank_lines {
+          collapse_blank_lines(&s)
+        } else {
+          s
+        }
+      });
+      let body = match body {
+        Some(body) => {
+          let body = localize_self_links(&body, &context.settings().hostname);
+          Some(resolve_mention_links(body, context).await?)
+        }
+        None => None,
+      };
+      let language_id = LanguageTag::to_language_id_single(language, &mut context.pool()).await?;
+
+      let content_hash = content_hash(
+        canonical_url.as_ref().map(DbUrl::inner).or(url.as_ref()),
+        body.as_deref(),
+      );
+      let nsfw = if matches_nsfw_keyword(&community.nsfw_keywords, &name, body.as_deref()) {
+        Some(true)
+      } else {
+        page.sensitive
+      };
+      let (published, updated) = ordered_timestamps(
+        page.published,
+        page.updated,
+        context.settings().federation.swap_invalid_post_timestamps,
+      )?;
 
-      let body = read_from_string_or_source_opt(&page.content, &page.media_type, &page.source)
-        .map(|s| remove_slurs(&s, slur_regex));
-      let language_id =
-        LanguageTag::to_language_id_single(page.language, &mut context.pool()).await?;
+      // Keep the original link separately, so that proxied posts can still be deduplicated.
+      let (url, original_url) = match (url, &context.settings().federation.url_proxy_template) {
+        (Some(url), Some(template)) => (Some(apply_url_proxy(&url, template)?), Some(url)),
+        (url, _) => (url, None),
+      };
+      let (word_count, reading_time) = match &body {
+        Some(body) if context.settings().federation.compute_reading_time => {
+          let (words, minutes) = reading_time(body);
+          (Some(words), Some(minutes))
+        }
+        _ => (None, None),
+      };
 
       PostInsertForm {
         name,
@@ -246,19 +618,35 @@ impl Object for ApubPost {
         community_id: community.id,
         removed: None,
         locked: page.comments_enabled.map(|e| !e),
-        published: page.published.map(Into::into),
-        updated: page.updated.map(Into::into),
+        published,
+        updated,
         deleted: Some(false),
-        nsfw: page.sensitive,
+        nsfw,
         embed_title,
         embed_description,
         embed_video_url,
         thumbnail_url,
+        thumbnail_blurhash,
         ap_id: Some(page.id.clone().into()),
         local: Some(false),
         language_id,
         featured_community: None,
         featured_local: None,
+        original_url: original_url.map(Into::into),
+        generator: page.generator.and_then(|g| g.software()),
+        content_hash,
+        canonical_url,
+        alt_text,
+        word_count,
+        reading_time,
+        duration,
+        from_bot: Some(creator.bot_account),
+        conversation_id: conversation_id.map(Into::into),
+        // keep the time of first arrival when the post is updated
+        received_at: old_post
+          .as_ref()
+          .and_then(|p| p.received_at)
+          .or(Some(Utc::now())),
       }
     } else {
       // if is mod action, only update locked/stickied fields, nothing else
@@ -272,8 +660,38 @@ impl Object for ApubPost {
         .build()
     };
 
+    if let Some(old_post) = old_post.as_ref().filter(|_| !is_mod_action) {
+      store_edit_history(old_post, &form, context).await?;
+    }
+
     let post = Post::create(&mut context.pool(), &form).await?;
 
+    if let Some(data) = raw {
+      let form = PostRawApubForm {
+        post_id: post.id,
+        data,
+      };
+      PostRawApub::upsert(&mut context.pool(), &form).await?;
+    }
+
+    if !is_mod_action {
+      if let Some(location) = location {
+        let form = PostLocationForm {
+          post_id: post.id,
+          name: location.name,
+          latitude: location.latitude,
+          longitude: location.longitude,
+        };
+        PostLocation::upsert(&mut context.pool(), &form).await?;
+      } else {
+        PostLocation::delete(&mut context.pool(), post.id).await?;
+      }
+      // Comments are fetched later, until then show the number reported by the remote instance
+      if let Some(replies) = replies.filter(|r| r > &0) {
+        PostAggregates::seed_comment_count(&mut context.pool(), post.id, replies).await?;
+      }
+    }
+
     // write mod log entry for lock
     if Page::is_locked_changed(&old_post, &page.comments_enabled) {
       let form = ModLockPostForm {
@@ -284,63 +702,3289 @@ impl Object for ApubPost {
       ModLockPost::create(&mut context.pool(), &form).await?;
     }
 
+    requests.nested = requests.since_last(context);
+    requests.log(&post.ap_id);
     Ok(post.into())
   }
 }
 
-#[cfg(test)]
-mod tests {
-  use super::*;
-  use crate::{
-    objects::{
-      community::{tests::parse_lemmy_community, ApubCommunity},
-      instance::ApubSite,
-      person::{tests::parse_lemmy_person, ApubPerson},
-      post::ApubPost,
-      tests::init_context,
-    },
-    protocol::tests::file_to_json_object,
+impl Page {
+  /// Builds the page of a post from already loaded parts, without database access. Language,
+  /// location and translations are stored separately, they are left empty here and filled in by
+  /// [Object::into_json].
+  pub(crate) fn from_post_parts(
+    post: &Post,
+    creator: &Person,
+    community: &Community,
+    settings: &Settings,
+  ) -> Page {
+    Page {
+      kind: PageType::Page,
+      id: post.ap_id.clone().into(),
+      attributed_to: AttributedTo::Lemmy(creator.actor_id.clone().into()),
+      // Addressing is always public in `to`, followed by the community in `cc`. Keep this order
+      // stable, some receivers compare it with the create activity.
+      to: vec![public()],
+      cc: vec![community.actor_id.clone().into()],
+      bto: vec![],
+      bcc: vec![],
+      name: Some(post.name.clone()),
+      content: match &post.body {
+        Some(b) => {
+          let b = absolute_self_links(b, &settings.get_protocol_and_hostname());
+          Some(render_markdown_cached(&b, settings).to_string())
+        }
+        None => None,
+      },
+      content_map: None,
+      media_type: Some(MEDIA_TYPE_HTML.to_string()),
+      // Markdown of remote posts may have been converted from html, so only the rendered content
+      // is sent for them
+      source: post.body.clone().filter(|_| post.local).map(Source::new),
+      attachment: post.url.clone().map(Attachment::new).into_iter().collect(),
+      image: post.thumbnail_url.clone().map(|i| {
+        ImageObject::new(i)
+          .with_name(post.alt_text.clone())
+          .with_blurhash(post.thumbnail_blurhash.clone())
+      }),
+      icon: None,
+      comments_enabled: Some(!post.locked),
+      sensitive: Some(post.nsfw),
+      language: None,
+      published: Some(post.published),
+      // Some platforms show any updated timestamp as edit, so leave it out if nothing changed
+      updated: post.updated.filter(|u| u != &post.published),
+      audience: Some(community.actor_id.clone().into()),
+      in_reply_to: None,
+      generator: None,
+      location: None,
+      replies: None,
+      preview: None,
+      in_language: None,
+      url: vec![],
+      duration: post.duration.clone(),
+      context: post.conversation_id.clone().map(Into::into),
+      conversation: None,
+      raw: None,
+    }
+  }
+}
+
+/// Stores the previous version of a received post before it is overwritten, if the title, body or
+/// link was actually changed.
+async fn store_edit_history(
+  old_post: &Post,
+  form: &PostInsertForm,
+  context: &Data<LemmyContext>,
+) -> LemmyResult<()> {
+  let keep = context.settings().federation.post_edit_history_size;
+  let edited = old_post.name != form.name || old_post.body != form.body || old_post.url != form.url;
+  if keep <= 0 || !edited {
+    return Ok(());
+  }
+  let form = PostEditHistoryForm {
+    post_id: old_post.id,
+    name: old_post.name.clone(),
+    body: old_post.body.clone(),
+    url: old_post.url.clone(),
+    published: old_post.updated.unwrap_or(old_post.published),
   };
-  use lemmy_db_schema::source::site::Site;
-  use lemmy_utils::error::LemmyResult;
-  use pretty_assertions::assert_eq;
-  use serial_test::serial;
+  PostEditHistory::create(&mut context.pool(), &form, keep).await?;
+  Ok(())
+}
 
-  #[tokio::test]
-  #[serial]
-  async fn test_parse_lemmy_post() -> LemmyResult<()> {
-    let context = init_context().await?;
-    let (person, site) = parse_lemmy_person(&context).await?;
-    let community = parse_lemmy_community(&context).await?;
+/// Number of remote fetches made while importing a post, split by what they were needed for. Logged
+/// to find out how many outgoing requests incoming posts cause.
+#[derive(Debug, Default, PartialEq)]
+struct ImportRequests {
+  author: u32,
+  community: u32,
+  nested: u32,
+  last_count: u32,
+}
 
-    let json = file_to_json_object("assets/lemmy/objects/page.json")?;
-    let url = Url::parse("https://enterprise.lemmy.ml/post/55143")?;
-    ApubPost::verify(&json, &url, &context).await?;
-    let post = ApubPost::from_json(json, &context).await?;
+impl ImportRequests {
+  fn new(context: &Data<LemmyContext>) -> Self {
+    ImportRequests {
+      last_count: context.request_count(),
+      ..Default::default()
+    }
+  }
 
-    assert_eq!(post.ap_id, url.into());
-    assert_eq!(post.name, "Post title");
-    assert!(post.body.is_some());
-    assert_eq!(post.body.as_ref().map(std::string::String::len), Some(45));
-    assert!(!post.locked);
-    assert!(!post.featured_community);
-    assert_eq!(context.request_count(), 0);
+  /// Returns the number of fetches since the previous call.
+  fn since_last(&mut self, context: &Data<LemmyContext>) -> u32 {
+    let count = context.request_count();
+    let since_last = count.saturating_sub(self.last_count);
+    self.last_count = count;
+    since_last
+  }
 
-    cleanup(&context, person, site, community, post).await?;
-    Ok(())
+  fn total(&self) -> u32 {
+    self.author + self.community + self.nested
   }
 
-  #[tokio::test]
-  #[serial]
-  async fn test_convert_mastodon_post_title() -> LemmyResult<()> {
-    let context = init_context().await?;
-    let (person, site) = parse_lemmy_person(&context).await?;
-    let community = parse_lemmy_community(&context).await?;
+  fn log(&self, ap_id: &DbUrl) {
+    debug!(
+      total = self.total(),
+      author = self.author,
+      community = self.community,
+      nested = self.nested,
+      "Remote fetches for importing post {ap_id}"
+    );
+  }
+}
 
-    let json = file_to_json_object("assets/mastodon/objects/page.json")?;
-    let post = ApubPost::from_json(json, &context).await?;
+/// Persons dereferenced while verifying a single post, so that the same id is fetched at most once
+/// even if several checks need it. Failed lookups are remembered too, and not retried.
+#[derive(Default)]
+struct ImportCache {
+  persons: HashMap<Url, Option<ApubPerson>>,
+}
 
-    assert_eq!(post.name, "Variable never resetting at refresh");
+impl ImportCache {
+  async fn person(
+    &mut self,
+    id: &ObjectId<ApubPerson>,
+    context: &Data<LemmyContext>,
+  ) -> LemmyResult<ApubPerson> {
+    if let Some(person) = self.persons.get(id.inner()) {
+      return Ok(person.clone().ok_or(LemmyErrorType::CouldntFindPerson)?);
+    }
+    let res = dereference_person(id, context).await;
+    self
+      .persons
+      .insert(id.inner().clone(), res.as_ref().ok().cloned());
+    res
+  }
+}
+
+impl VerifyOrigin for ApubPost {
+  fn ap_id(&self) -> &Url {
+    &self.ap_id
+  }
+}
+
+impl VerifyOrigin for Page {
+  fn ap_id(&self) -> &Url {
+    self.id.inner()
+  }
+}
+
+impl TombstoneIfDeleted for ApubPost {
+  type TombstoneType = Tombstone;
+
+  fn tombstone_if_deleted(&self) -> Option<Tombstone> {
+    (self.deleted || self.removed).then(|| Tombstone::new(self.ap_id.clone().into()))
+  }
+}
+
+/// Compiled from the `generic_post_name_pattern` setting. An invalid pattern is logged and
+/// ignored.
+static GENERIC_POST_NAME_REGEX: Lazy<Option<Regex>> = Lazy::new(|| {
+  let pattern = SETTINGS.federation.generic_post_name_pattern.as_ref()?;
+  RegexBuilder::new(&format!("^(?:{pattern})$"))
+    .case_insensitive(true)
+    .build()
+    .map_err(|e| warn!("Invalid generic_post_name_pattern: {e}"))
+    .ok()
+});
+
+/// Replaces a post name which matches the generic name pattern with the title of the linked page,
+/// if there is one. Slurs in the title are removed like in the body.
+fn replace_generic_name(
+  name: String,
+  embed_title: Option<&str>,
+  generic_name: Option<&Regex>,
+  slur_regex: &Option<Regex>,
+) -> String {
+  match (generic_name, embed_title.map(str::trim)) {
+    (Some(generic), Some(title)) if !title.is_empty() && generic.is_match(name.trim()) => {
+      remove_slurs(title, slur_regex)
+        .chars()
+        .take(MAX_TITLE_LENGTH)
+        .collect()
+    }
+    _ => name,
+  }
+}
+
+/// Average reading speed which is used to estimate the reading time of posts
+const WORDS_PER_MINUTE: usize = 200;
+
+/// Returns the word count of a markdown post body, and the estimated minutes to read it.
+fn reading_time(body: &str) -> (i32, i32) {
+  let words = markdown_word_count(body);
+  let minutes = words.div_ceil(WORDS_PER_MINUTE);
+  (
+    i32::try_from(words).unwrap_or(i32::MAX),
+    i32::try_from(minutes).unwrap_or(i32::MAX),
+  )
+}
+
+/// Number of posts which [purge_deleted_remote_posts] deletes per query
+const PURGE_BATCH_SIZE: i64 = 1000;
+
+/// Permanently deletes remote posts which were deleted more than `retention_days` ago, and
+/// returns how many were purged. This runs in batches to avoid long locks, and can be repeated
+/// any time as it only affects posts which are past the retention period. The retention period
+/// needs to be at least one day.
+pub async fn purge_deleted_remote_posts(
+  pool: &mut DbPool<'_>,
+  retention_days: i64,
+) -> LemmyResult<usize> {
+  let before = Some(retention_days)
+    .filter(|d| *d > 0)
+    .and_then(Duration::try_days)
+    .and_then(|d| Utc::now().checked_sub_signed(d))
+    .ok_or(LemmyErrorType::InvalidRetentionPeriod)?;
+  let mut purged = 0;
+  loop {
+    let count = Post::purge_deleted_remote(pool, before, PURGE_BATCH_SIZE).await?;
+    purged += count;
+    if count < PURGE_BATCH_SIZE as usize {
+      return Ok(purged);
+    }
+  }
+}
+
+/// Html content of posts contains mentions as links to the remote actor. If the actor is known
+/// locally, the link is replaced with a plain mention so that it resolves to the local copy.
+/// Other mention links are kept unchanged.
+async fn resolve_mention_links(body: String, context: &Data<LemmyContext>) -> LemmyResult<String> {
+  let mut body = body;
+  for link in scrape_text_for_mention_links(&body) {
+    let Ok(url) = Url::parse(&link.url) else {
+      continue;
+    };
+    let Some(domain) = actor_domain(&url) else {
+      continue;
+    };
+    let url = url.into();
+    let mention = if link.is_community() {
+      Community::read_from_apub_id(&mut context.pool(), &url)
+        .await?
+        .map(|c| format!("!{}@{domain}", c.name))
+    } else {
+      Person::read_from_apub_id(&mut context.pool(), &url)
+        .await?
+        .map(|p| format!("@{}@{domain}", p.name))
+    };
+    if let Some(mention) = mention {
+      body = body.replace(&link.link, &mention);
+    }
+  }
+  Ok(body)
+}
+
+/// Rewrites markdown links to content on this instance into relative links, so that they open the
+/// local route instead of leaving the site or passing through a redirect. They are made absolute
+/// again with [absolute_self_links] before the post is federated.
+fn localize_self_links(body: &str, hostname: &str) -> String {
+  rewrite_link_targets(body, |target| {
+    let url = Url::parse(target)
+      .ok()
+      .filter(|u| is_self_link(u, hostname))?;
+    let mut local = url.path().to_string();
+    if let Some(query) = url.query() {
+      local.push('?');
+      local.push_str(query);
+    }
+    if let Some(fragment) = url.fragment() {
+      local.push('#');
+      local.push_str(fragment);
+    }
+    Some(local)
+  })
+}
+
+fn is_self_link(url: &Url, hostname: &str) -> bool {
+  matches!(url.scheme(), "http" | "https") && actor_domain(url).as_deref() == Some(hostname)
+}
+
+/// Turns relative markdown links into absolute links to this instance, so that they also work on
+/// other instances.
+fn absolute_self_links(body: &str, protocol_and_hostname: &str) -> String {
+  rewrite_link_targets(body, |target| {
+    (target.starts_with('/') && !target.starts_with("//"))
+      .then(|| format!("{protocol_and_hostname}{target}"))
+  })
+}
+
+/// Replaces the target of each markdown link or image for which `rewrite` returns a value.
+fn rewrite_link_targets(body: &str, rewrite: impl Fn(&str) -> Option<String>) -> String {
+  let mut rewritten = String::with_capacity(body.len());
+  let mut rest = body;
+  while let Some(start) = rest.find("](") {
+    let (before, after) = rest.split_at(start + 2);
+    rewritten.push_str(before);
+    let end = after
+      .find(|c: char| c == ')' || c.is_whitespace())
+      .unwrap_or(after.len());
+    let (target, tail) = after.split_at(end);
+    rewritten.push_str(&rewrite(target).unwrap_or_else(|| target.to_string()));
+    rest = tail;
+  }
+  rewritten.push_str(rest);
+  rewritten
+}
+
+/// Links to content on this instance are stored with the scheme of this instance, so that links
+/// with `http` to an instance served over `https` don't go through a redirect.
+fn localize_self_url(mut url: Url, hostname: &str, protocol: &str) -> Url {
+  if is_self_link(&url, hostname) {
+    // only fails when changing between special and non-special schemes, which are excluded above
+    let _ = url.set_scheme(protocol);
+  }
+  url
+}
+
+/// Collapses runs of three or more blank lines to two and removes trailing whitespace, without
+/// changing code blocks. Whitespace at the end of other lines is kept, as it can be a line break.
+fn collapse_blank_lines(text: &str) -> String {
+  let mut lines = vec![];
+  let mut in_code_block = false;
+  let mut blank_lines = 0;
+  for line in text.lines() {
+    if !in_code_block && line.trim().is_empty() {
+      blank_lines += 1;
+      if blank_lines <= 2 {
+        lines.push("");
+      }
+      continue;
+    }
+    blank_lines = 0;
+    let trimmed = line.trim_start();
+    if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+      in_code_block = !in_code_block;
+    }
+    lines.push(line);
+  }
+  lines.join("\n").trim_end().to_string()
+}
+
+/// Some software sets the link of a post to its own id, with an added fragment. Such a link
+/// doesn't point to external content, so it is ignored.
+fn is_link_to_self(url: &Url, id: &Url) -> bool {
+  let mut url = url.clone();
+  let mut id = id.clone();
+  url.set_fragment(None);
+  id.set_fragment(None);
+  url == id
+}
+
+/// Renders the markdown of a post body to html, with a cache keyed by hash of the markdown and the
+/// settings which affect rendering. Edited bodies have a different hash, so they are rendered
+/// again.
+fn render_markdown_cached(markdown: &str, settings: &Settings) -> Arc<String> {
+  static CACHE: Lazy<Cache<[u8; 32], Arc<String>>> = Lazy::new(|| {
+    Cache::builder()
+      .max_capacity(SETTINGS.federation.markdown_cache_size)
+      .build()
+  });
+  let federation = &settings.federation;
+  let html = &settings.html;
+  let template = Vec::from_iter(federation.image_proxy_template.clone());
+  let mut hasher = Sha256::new();
+  // Each part is prefixed with its length, so that different inputs can't result in the same key
+  let mut update = |part: &[u8]| {
+    hasher.update((part.len() as u64).to_le_bytes());
+    hasher.update(part);
+  };
+  update(markdown.as_bytes());
+  for list in [
+    &template,
+    &federation.media_embed_hosts,
+    &html.allowed_tags,
+    &html.allowed_attributes,
+    &html.forbidden_markdown_tags,
+  ] {
+    update(&(list.len() as u64).to_le_bytes());
+    list.iter().for_each(|item| update(item.as_bytes()));
+  }
+  let key = hasher.finalize().into();
+  CACHE.get_with(key, || {
+    Arc::new(markdown_to_html_outgoing(markdown, settings))
+  })
+}
+
+/// Published and updated timestamps of a post
+type Timestamps = (Option<DateTime<Utc>>, Option<DateTime<Utc>>);
+
+/// Checks that a post wasn't updated before it was published. If it was, the timestamps are
+/// swapped when `swap` is enabled, otherwise an error is returned.
+fn ordered_timestamps(
+  published: Option<DateTime<Utc>>,
+  updated: Option<DateTime<Utc>>,
+  swap: bool,
+) -> LemmyResult<Timestamps> {
+  match (published, updated) {
+    (Some(p), Some(u)) if u < p => {
+      if swap {
+        Ok((Some(u), Some(p)))
+      } else {
+        Err(LemmyErrorType::PostUpdatedBeforePublished)?
+      }
+    }
+    _ => Ok((published, updated)),
+  }
+}
+
+/// Verifies a received page. If it was delivered by a relay, the page id doesn't need to be on
+/// the relay's domain, and author and community may be on unrelated hosts.
+pub(crate) async fn verify_page(
+  page: &Page,
+  expected_domain: &Url,
+  expected_types: &[PageType],
+  relayed: bool,
+  context: &Data<LemmyContext>,
+) -> LemmyResult<()> {
+  let local_site_data = local_site_data_cached(&mut context.pool()).await?;
+  let slur_regex = local_site_opt_to_slur_regex(&local_site_data.local_site);
+  verify_page_content(page, expected_types, &slur_regex, context).await?;
+
+  // We can't verify the domain in case of mod action, because the mod may be on a different
+  // instance from the post author.
+  if !page.is_mod_action(context).await? {
+    if !relayed {
+      page.verify_origin(expected_domain)?;
+    }
+    verify_is_remote_object(page.id.inner(), context.settings())?;
+  };
+
+  let community = page.community(context).await?;
+  if community.local_only {
+    Err(LemmyErrorType::CommunityIsLocalOnly)?
+  }
+  if page.creator()?.inner() == community.actor_id.inner() {
+    Err(LemmyErrorType::PostAuthorIsCommunity)?
+  }
+  let url = page.attachment_url();
+  if let Some(url) = &url {
+    check_url_not_blocked_by_community(url, &community.blocked_url_domains)?;
+  }
+  check_url_host_required(
+    url.as_ref(),
+    community.required_url_host.as_deref(),
+    context
+      .settings()
+      .federation
+      .required_url_host_allows_text_posts,
+  )?;
+  check_apub_id_valid_with_strictness(page.id.inner(), community.local, context).await?;
+  let mut cache = ImportCache::default();
+  let creator = cache.person(&page.creator()?, context).await?;
+  verify_dereferenced_person_in_community(&creator, &community, context).await?;
+  check_post_rate_limit(page, &community, &mut cache, context).await?;
+  check_community_post_cap(page, &community, context).await?;
+  let min_author_age = context.settings().federation.min_post_author_age;
+  if min_author_age > 0 {
+    let published = Some(creator.published).filter(|_| !creator.published_unknown);
+    check_author_age(published, min_author_age)?;
+  }
+
+  let allowed_domains: Vec<String> = local_site_data
+    .allowed_instances
+    .iter()
+    .map(|i| i.domain.clone())
+    .collect();
+  if relayed {
+    info!("Post {} was delivered by relay {expected_domain}", page.id);
+  } else if let Err(e) = verify_post_hosts_related(
+    creator.actor_id.inner(),
+    community.actor_id.inner(),
+    &context.settings().get_hostname_without_port()?,
+    &allowed_domains,
+  ) {
+    if context.settings().federation.reject_unrelated_post_hosts {
+      return Err(e);
+    }
+    info!(
+      "Post {} has author and community on unrelated hosts",
+      page.id
+    );
+  }
+  Ok(())
+}
+
+/// Checks which only need the page itself and the local configuration. These run before the
+/// community or author are read or fetched, so that posts which are rejected anyway don't cause
+/// outgoing requests.
+async fn verify_page_content(
+  page: &Page,
+  expected_types: &[PageType],
+  slur_regex: &Option<Regex>,
+  context: &Data<LemmyContext>,
+) -> LemmyResult<()> {
+  let federation = &context.settings().federation;
+  check_recipient_count(page, federation.max_post_recipients)?;
+  verify_page_type(&page.kind, expected_types)?;
+  verify_image_host_allowed(page, &federation.allowed_image_hosts)?;
+  verify_software_allowed(page, &federation.software_denylist)?;
+  check_slurs_opt(&page.name, slur_regex)?;
+  check_link_count(
+    page,
+    federation.max_links_per_post,
+    &federation.accepted_media_types,
+  )?;
+  ordered_timestamps(
+    page.published,
+    page.updated,
+    federation.swap_invalid_post_timestamps,
+  )?;
+
+  let url = page.attachment_url();
+  check_url_scheme(&url)?;
+  let spam_check = SpamCheckPost {
+    ap_id: page.id.inner(),
+    name: page.name.as_deref(),
+    body: page.content.as_deref(),
+    url: url.as_ref(),
+  };
+  if let Some(reason) = context.spam_classifier().check_post(&spam_check) {
+    Err(LemmyErrorType::PostRejectedAsSpam(reason))?
+  }
+  if let Some(url) = &url {
+    check_url_not_blocked(url, context).await?;
+  }
+
+  if actor_domain(page.creator()?.inner()) != actor_domain(page.id.inner()) {
+    Err(ActivityPubError::UrlVerificationError(
+      "Domains do not match",
+    ))?
+  }
+  // Private addressing doesn't count, the post needs to be public through `to` or `cc`.
+  if !page.bto.is_empty() || !page.bcc.is_empty() {
+    info!("Ignoring private addressing of post {}", page.id);
+  }
+  verify_is_public(&page.to, &page.cc)?;
+  Ok(())
+}
+
+/// Checks if an activity was delivered by one of the configured relays.
+pub(crate) fn is_relay(expected_domain: &Url, relay_domains: &[String]) -> bool {
+  let domain = expected_domain.domain().unwrap_or_default();
+  relay_domains.iter().any(|r| r.eq_ignore_ascii_case(domain))
+}
+
+/// Checks a stored remote post against the current federation and slur policies, for example
+/// after an admin tightened them. Returns the reason if the post would be rejected now, it is
+/// left to the caller to report or remove it. Local posts are not checked.
+pub async fn reverify_stored_post(
+  post_id: PostId,
+  context: &Data<LemmyContext>,
+) -> LemmyResult<Option<LemmyError>> {
+  let post: ApubPost = Post::read(&mut context.pool(), post_id).await?.into();
+  if post.local {
+    return Ok(None);
+  }
+  // Read the site directly, the cached slur filter used by verify may be outdated
+  let local_site = LocalSite::read(&mut context.pool()).await.ok();
+  let slur_filter = local_site.and_then(|s| s.slur_filter_regex);
+  let page = post.into_json(context).await?;
+  Ok(
+    reverify_page(&page, slur_filter.as_deref(), context)
+      .await
+      .err(),
+  )
+}
+
+async fn reverify_page(
+  page: &Page,
+  slur_filter: Option<&str>,
+  context: &Data<LemmyContext>,
+) -> LemmyResult<()> {
+  let slur_regex = &build_slur_regex(slur_filter);
+  check_slurs_opt(&page.name, slur_regex)?;
+  let body = page.body(&context.settings().federation.accepted_media_types);
+  check_slurs_opt(&body, slur_regex)?;
+  verify_page(page, page.id.inner(), &FETCHED_PAGE_TYPES, false, context).await
+}
+
+/// Whether a failed dereference means that the object doesn't exist, as opposed to a failure while
+/// fetching it.
+fn is_not_found(e: &LemmyError) -> bool {
+  matches!(
+    e.inner.downcast_ref::<ActivityPubError>(),
+    Some(ActivityPubError::NotFound | ActivityPubError::ObjectDeleted(_))
+  )
+}
+
+/// Rejects new posts in remote communities which nobody on this instance follows, if enabled.
+async fn check_local_followers(
+  enabled: bool,
+  page: &Page,
+  community: &Community,
+  context: &Data<LemmyContext>,
+) -> LemmyResult<()> {
+  if !enabled || community.local || ApubPost::read_local(&page.id, context).await?.is_some() {
+    return Ok(());
+  }
+  if !CommunityFollower::has_local_followers(&mut context.pool(), community.id).await? {
+    Err(LemmyErrorType::CommunityHasNoLocalFollowers)?
+  }
+  Ok(())
+}
+
+/// Rejects new posts if the author already made too many posts in the community recently. The
+/// community limit takes precedence over the instance setting.
+async fn check_post_rate_limit(
+  page: &Page,
+  community: &Community,
+  cache: &mut ImportCache,
+  context: &Data<LemmyContext>,
+) -> LemmyResult<()> {
+  let federation = &context.settings().federation;
+  let limit = community
+    .post_rate_limit
+    .unwrap_or(federation.post_rate_limit);
+  if limit <= 0 || ApubPost::read_local(&page.id, context).await?.is_some() {
+    return Ok(());
+  }
+  let creator = cache.person(&page.creator()?, context).await?;
+  let since = Utc::now() - Duration::seconds(federation.post_rate_limit_interval);
+  let count =
+    Post::count_recent_by_creator(&mut context.pool(), creator.id, community.id, since).await?;
+  if count >= limit.into() {
+    Err(LemmyErrorType::TooManyPostsInCommunity)?
+  }
+  Ok(())
+}
+
+/// Rejects new posts once the community has reached its maximum number of posts. Edits of existing
+/// posts are still accepted.
+async fn check_community_post_cap(
+  page: &Page,
+  community: &Community,
+  context: &Data<LemmyContext>,
+) -> LemmyResult<()> {
+  let Some(max_posts) = community.max_posts else {
+    return Ok(());
+  };
+  if ApubPost::read_local(&page.id, context).await?.is_some() {
+    return Ok(());
+  }
+  let counts = CommunityAggregates::read(&mut context.pool(), community.id).await?;
+  if counts.posts >= max_posts.into() {
+    Err(LemmyErrorType::CommunityPostLimitReached)?
+  }
+  Ok(())
+}
+
+/// Rejects posts by accounts which were created less than `min_age` seconds ago. The check is
+/// skipped if the creation time of the account is unknown.
+fn check_author_age(published: Option<DateTime<Utc>>, min_age: i64) -> LemmyResult<()> {
+  match published {
+    Some(published) if published > Utc::now() - Duration::seconds(min_age) => {
+      Err(LemmyErrorType::PostAuthorTooNew)?
+    }
+    _ => Ok(()),
+  }
+}
+
+/// Types which are stored as posts when fetched. Peertube videos, Mobilizon events and audio from
+/// podcast platforms are shown as link posts.
+const FETCHED_PAGE_TYPES: [PageType; 6] = [
+  PageType::Page,
+  PageType::Article,
+  PageType::Note,
+  PageType::Video,
+  PageType::Event,
+  PageType::Audio,
+];
+
+/// Types which are accepted as post in create and update activities. Peertube only announces
+/// videos, which are then fetched, so a video in an activity is a different object type routed to
+/// the wrong handler.
+pub(crate) const ACTIVITY_PAGE_TYPES: [PageType; 5] = [
+  PageType::Page,
+  PageType::Article,
+  PageType::Note,
+  PageType::Event,
+  PageType::Audio,
+];
+
+/// Checks that the object type is one which the handler that received it stores as post.
+fn verify_page_type(kind: &PageType, expected: &[PageType]) -> LemmyResult<()> {
+  if !expected.contains(kind) {
+    Err(LemmyErrorType::UnsupportedObjectType(format!("{kind:?}")))?
+  }
+  Ok(())
+}
+
+/// Rejects pages which are addressed to a huge number of recipients. This is checked before
+/// fetching the community from the recipients.
+fn check_recipient_count(page: &Page, max: usize) -> LemmyResult<()> {
+  if page.to.len() + page.cc.len() > max {
+    Err(LemmyErrorType::TooManyRecipients)?
+  }
+  Ok(())
+}
+
+/// Uses the configured fallback thumbnail for link posts without any image.
+fn with_fallback_thumbnail(
+  thumbnail_url: Option<DbUrl>,
+  url: &Option<Url>,
+  fallback: Option<&Url>,
+) -> Option<DbUrl> {
+  thumbnail_url.or_else(|| url.as_ref().and(fallback).cloned().map(Into::into))
+}
+
+/// Rejects pages with a body full of links, which is a common spam pattern.
+fn check_link_count(page: &Page, max: usize, accepted_media_types: &[String]) -> LemmyResult<()> {
+  let body = page.body(accepted_media_types);
+  if body.is_some_and(|b| markdown_link_count(&b) > max) {
+    Err(LemmyErrorType::TooManyLinksInPost)?
+  }
+  Ok(())
+}
+
+/// Rejects links to malicious domains. If enabled, the link is also requested to check where it
+/// redirects to.
+async fn check_url_not_blocked(url: &Url, context: &Data<LemmyContext>) -> LemmyResult<()> {
+  let check = |url: &Url| -> LemmyResult<()> {
+    if context.url_blocklist().is_blocked(url) {
+      let domain = url.domain().unwrap_or_default().to_string();
+      Err(LemmyErrorType::LinkToMaliciousDomain(domain))?
+    }
+    Ok(())
+  };
+  check(url)?;
+  if context.settings().federation.check_link_redirects {
+    // Failed requests are ignored, the site may only be unavailable temporarily
+    if let Ok(res) = context.client().head(url.clone()).send().await {
+      check(res.url())?;
+    }
+  }
+  Ok(())
+}
+
+/// Rejects links to one of the domains which the community blocked, or to their subdomains.
+fn check_url_not_blocked_by_community(url: &Url, blocked_domains: &[String]) -> LemmyResult<()> {
+  let Some(domain) = url.domain().map(str::to_lowercase) else {
+    return Ok(());
+  };
+  let blocked = blocked_domains
+    .iter()
+    .any(|b| is_same_or_subdomain(&domain, b));
+  if blocked {
+    Err(LemmyErrorType::LinkToCommunityBlockedDomain(domain))?
+  }
+  Ok(())
+}
+
+/// Rejects posts whose link is not on the host which the community requires, or one of its
+/// subdomains. Posts without link are only accepted if `allow_text_posts` is set.
+fn check_url_host_required(
+  url: Option<&Url>,
+  required_host: Option<&str>,
+  allow_text_posts: bool,
+) -> LemmyResult<()> {
+  let Some(required_host) = required_host.filter(|h| !h.trim().is_empty()) else {
+    return Ok(());
+  };
+  let host = url.and_then(Url::domain).map(str::to_lowercase);
+  match host {
+    Some(host) if is_same_or_subdomain(&host, required_host) => Ok(()),
+    None if url.is_none() && allow_text_posts => Ok(()),
+    _ => Err(LemmyErrorType::LinkHostNotAllowedInCommunity(
+      required_host.to_string(),
+    ))?,
+  }
+}
+
+/// Checks if the lowercase `domain` is `parent` or one of its subdomains. An empty `parent` never
+/// matches.
+fn is_same_or_subdomain(domain: &str, parent: &str) -> bool {
+  let parent = parent.trim().trim_start_matches('.').to_lowercase();
+  !parent.is_empty()
+    && domain
+      .strip_suffix(&parent)
+      .is_some_and(|prefix| prefix.is_empty() || prefix.ends_with('.'))
+}
+
+/// Rejects pages whose image or icon is hosted neither on the author's instance nor on one of the
+/// allowed image hosts. Nothing is checked if no hosts are configured.
+fn verify_image_host_allowed(page: &Page, allowed_hosts: &[String]) -> LemmyResult<()> {
+  if allowed_hosts.is_empty() {
+    return Ok(());
+  }
+  let author_host = actor_domain(page.creator()?.inner());
+  for image in page.image.iter().chain(page.icon.iter()) {
+    let host = image.url.domain().unwrap_or_default();
+    let allowed = author_host == actor_domain(&image.url)
+      || allowed_hosts.iter().any(|h| h.eq_ignore_ascii_case(host));
+    if !allowed {
+      Err(LemmyErrorType::ImageHostNotAllowed)?
+    }
+  }
+  Ok(())
+}
+
+/// Rejects pages whose generator matches one of the denylisted software strings, ignoring case.
+fn verify_software_allowed(page: &Page, denylist: &[String]) -> LemmyResult<()> {
+  let Some(software) = page.generator.as_ref().and_then(Generator::software) else {
+    return Ok(());
+  };
+  let lowercase = software.to_lowercase();
+  if denylist
+    .iter()
+    .map(|d| d.trim().to_lowercase())
+    .any(|d| !d.is_empty() && lowercase.contains(&d))
+  {
+    Err(LemmyErrorType::SoftwareNotAllowed(software))?
+  }
+  Ok(())
+}
+
+/// Aborts a post import which takes longer than `timeout`, so that slow remote servers can't hold
+/// up the worker. A zero timeout doesn't limit the import.
+async fn with_timeout<T>(
+  import: impl Future<Output = LemmyResult<T>>,
+  timeout: StdDuration,
+) -> LemmyResult<T> {
+  if timeout.is_zero() {
+    return import.await;
+  }
+  tokio::time::timeout(timeout, import)
+    .await
+    .map_err(|_| LemmyErrorType::PostImportTimedOut)?
+}
+
+/// Drops the attachments beyond `max`, or rejects the page if `reject` is enabled.
+fn limit_attachments(page: &mut Page, max: usize, reject: bool) -> LemmyResult<()> {
+  if page.attachment.len() > max {
+    if reject {
+      Err(LemmyErrorType::TooManyAttachments)?
+    }
+    page.attachment.truncate(max);
+  }
+  Ok(())
+}
+
+/// Checks if the title or body of a post contain one of the NSFW keywords configured by the
+/// community, ignoring case.
+fn matches_nsfw_keyword(keywords: &[String], name: &str, body: Option<&str>) -> bool {
+  let name = name.to_lowercase();
+  let body = body.map(str::to_lowercase).unwrap_or_default();
+  keywords
+    .iter()
+    .map(|k| k.trim().to_lowercase())
+    .filter(|k| !k.is_empty())
+    .any(|k| name.contains(&k) || body.contains(&k))
+}
+
+/// Hashes the link of a post, or its body if there is no link. Both are normalized first, so that
+/// crossposts of the same content get the same hash.
+fn content_hash(url: Option<&Url>, body: Option<&str>) -> Option<String> {
+  let normalized = match (url, body) {
+    (Some(url), _) => url.as_str().to_lowercase(),
+    (None, Some(body)) if !body.trim().is_empty() => body.trim().to_string(),
+    _ => return None,
+  };
+  Some(format!("{:x}", Sha256::digest(normalized)))
+}
+
+/// The json of a received page as it arrived, so that it can be stored for debugging, if enabled
+/// in the config. Private addressing in `bto` and `bcc` is left out, as it must never be stored.
+fn raw_apub(page: &Page, enabled: bool) -> Option<Value> {
+  let mut raw = page.raw.clone().filter(|_| enabled)?;
+  if let Some(o) = raw.as_object_mut() {
+    o.remove("bto");
+    o.remove("bcc");
+  }
+  Some(raw)
+}
+
+/// Posts are normally authored by a user whose instance federates with the community's instance.
+/// If neither the author nor the community are local and they are on different hosts, the post
+/// might have been relayed by a third party. This is accepted if both hosts are allowlisted.
+fn verify_post_hosts_related(
+  author_id: &Url,
+  community_id: &Url,
+  local_domain: &str,
+  allowed_domains: &[String],
+) -> Result<(), LemmyError> {
+  let author_domain = author_id.domain().unwrap_or_default();
+  let community_domain = community_id.domain().unwrap_or_default();
+  let is_allowed = |domain: &str| {
+    allowed_domains
+      .iter()
+      .any(|a| a.eq_ignore_ascii_case(domain))
+  };
+  if author_domain == local_domain
+    || community_domain == local_domain
+    || author_domain == community_domain
+    || (is_allowed(author_domain) && is_allowed(community_domain))
+  {
+    Ok(())
+  } else {
+    Err(LemmyErrorType::PostHostsUnrelated)?
+  }
+}
+
+/// Rewrites a post link through the link proxy given by `template`, by replacing the `{url}`
+/// placeholder with the percent-encoded link.
+fn apply_url_proxy(url: &Url, template: &str) -> Result<Url, LemmyError> {
+  let encoded: String = form_urlencoded::byte_serialize(url.as_str().as_bytes()).collect();
+  Ok(Url::parse(&template.replace("{url}", &encoded))?)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{
+    objects::{
+      community::{tests::parse_lemmy_community, ApubCommunity},
+      instance::ApubSite,
+      person::{tests::parse_lemmy_person, ApubPerson},
+      post::ApubPost,
+      tests::{
+        init_context,
+        init_context_with,
+        init_context_with_responses,
+        init_context_with_status_responses,
+      },
+    },
+    protocol::{
+      activities::{create_or_update::page::CreateOrUpdatePage, CreateOrUpdateType},
+      tests::{assert_matches_schema, file_to_json_object},
+    },
+  };
+  use activitypub_federation::traits::ActivityHandler;
+  use lemmy_api_common::{
+    spam::SpamClassifier,
+    thumbnail::ThumbnailGenerator,
+    url_blocklist::DomainBlocklist,
+  };
+  use lemmy_db_schema::{
+    source::{
+      community::{
+        CommunityFollowerForm,
+        CommunityPersonBan,
+        CommunityPersonBanForm,
+        CommunityUpdateForm,
+      },
+      person::PersonUpdateForm,
+      post::PostTranslationForm,
+      site::Site,
+    },
+    traits::{Bannable, Followable},
+    utils::get_conn,
+  };
+  use pretty_assertions::assert_eq;
+  use reqwest::StatusCode;
+  use serde_json::json;
+  use serial_test::serial;
+
+  #[tokio::test]
+  #[serial]
+  async fn test_parse_lemmy_post() -> LemmyResult<()> {
+    let context = init_context().await?;
+    let (person, site) = parse_lemmy_person(&context).await?;
+    let community = parse_lemmy_community(&context).await?;
+
+    let json = file_to_json_object("assets/lemmy/objects/page.json")?;
+    let url = Url::parse("https://enterprise.lemmy.ml/post/55143")?;
+    ApubPost::verify(&json, &url, &context).await?;
+    let post = ApubPost::from_json(json, &context).await?;
+
+    assert_eq!(post.ap_id, url.into());
+    assert_eq!(post.name, "Post title");
+    assert!(post.body.is_some());
+    assert_eq!(post.body.as_ref().map(std::string::String::len), Some(45));
+    assert!(!post.locked);
+    assert!(!post.featured_community);
+    assert_eq!(context.request_count(), 0);
+
+    let page = post.clone().into_json(&context).await?;
+    assert_matches_schema(&page, "assets/schema/page.json")?;
+
+    cleanup(&context, person, site, community, post).await?;
+    Ok(())
+  }
+
+  struct RejectTitle;
+
+  impl SpamClassifier for RejectTitle {
+    fn check_post(&self, post: &SpamCheckPost) -> Option<String> {
+      (post.name == Some("Post title")).then(|| "spam title".to_string())
+    }
+  }
+
+  #[tokio::test]
+  #[serial]
+  async fn test_reject_post_by_spam_classifier() -> LemmyResult<()> {
+    let context = init_context_with(|c| c.with_spam_classifier(Arc::new(RejectTitle))).await?;
+    let (person, site) = parse_lemmy_person(&context).await?;
+    let community = parse_lemmy_community(&context).await?;
+
+    let json = file_to_json_object("assets/lemmy/objects/page.json")?;
+    let url = Url::parse("https://enterprise.lemmy.ml/post/55143")?;
+    let res = ApubPost::verify(&json, &url, &context).await;
+    assert_eq!(
+      Some(LemmyErrorType::PostRejectedAsSpam("spam title".to_string())),
+      res.err().map(|e| e.error_type)
+    );
+
+    Person::delete(&mut context.pool(), person.id).await?;
+    Community::delete(&mut context.pool(), community.id).await?;
+    Site::delete(&mut context.pool(), site.id).await?;
+    Ok(())
+  }
+
+  #[tokio::test]
+  #[serial]
+  async fn test_reject_spam_before_fetching_community() -> LemmyResult<()> {
+    let context = init_context_with(|c| c.with_spam_classifier(Arc::new(RejectTitle))).await?;
+
+    // Neither the author nor the community are known locally, so checking them would require
+    // fetching them.
+    let json = file_to_json_object("assets/lemmy/objects/page.json")?;
+    let url = Url::parse("https://enterprise.lemmy.ml/post/55143")?;
+    let res = ApubPost::verify(&json, &url, &context).await;
+    assert_eq!(
+      Some(LemmyErrorType::PostRejectedAsSpam("spam title".to_string())),
+      res.err().map(|e| e.error_type)
+    );
+    assert_eq!(context.request_count(), 0);
+    Ok(())
+  }
+
+  #[tokio::test]
+  #[serial]
+  async fn test_reject_link_spam() -> LemmyResult<()> {
+    let context = init_context().await?;
+    let url = Url::parse("https://enterprise.lemmy.ml/post/55143")?;
+    let max = context.settings().federation.max_links_per_post;
+
+    let page: Page = file_to_json_object("assets/lemmy/objects/page.json")?;
+    // the normal post passes
+    check_link_count(
+      &page,
+      max,
+      &context.settings().federation.accepted_media_types,
+    )?;
+
+    let mut page = page;
+    let links: Vec<_> = (0..=max)
+      .map(|i| format!("<a href=\"https://spam.example.com/{i}\">buy now</a>"))
+      .collect();
+    page.content = Some(links.join("<br>"));
+    page.source = None;
+    let res = ApubPost::verify(&page, &url, &context).await;
+    assert_eq!(
+      Some(LemmyErrorType::TooManyLinksInPost),
+      res.err().map(|e| e.error_type)
+    );
+    assert_eq!(context.request_count(), 0);
+
+    page.content = links.get(..3).map(|l| l.join("<br>"));
+    check_link_count(
+      &page,
+      max,
+      &context.settings().federation.accepted_media_types,
+    )?;
+    Ok(())
+  }
+
+  #[tokio::test]
+  #[serial]
+  async fn test_reject_post_in_local_only_community() -> LemmyResult<()> {
+    let context = init_context().await?;
+    let (person, site) = parse_lemmy_person(&context).await?;
+    let community = parse_lemmy_community(&context).await?;
+    let form = CommunityUpdateForm {
+      local_only: Some(true),
+      ..Default::default()
+    };
+    Community::update(&mut context.pool(), community.id, &form).await?;
+
+    let json = file_to_json_object("assets/lemmy/objects/page.json")?;
+    let url = Url::parse("https://enterprise.lemmy.ml/post/55143")?;
+    let res = ApubPost::verify(&json, &url, &context).await;
+    assert_eq!(
+      Some(LemmyErrorType::CommunityIsLocalOnly),
+      res.err().map(|e| e.error_type)
+    );
+
+    Person::delete(&mut context.pool(), person.id).await?;
+    Community::delete(&mut context.pool(), community.id).await?;
+    Site::delete(&mut context.pool(), site.id).await?;
+    Ok(())
+  }
+
+  #[tokio::test]
+  #[serial]
+  async fn test_reject_post_authored_by_community() -> LemmyResult<()> {
+    let context = init_context().await?;
+    let (person, site) = parse_lemmy_person(&context).await?;
+    let community = parse_lemmy_community(&context).await?;
+
+    let mut page: Page = file_to_json_object("assets/lemmy/objects/page.json")?;
+    page.attributed_to = AttributedTo::Lemmy(ObjectId::from(community.actor_id.inner().clone()));
+    let url = Url::parse("https://enterprise.lemmy.ml/post/55143")?;
+    let res = ApubPost::verify(&page, &url, &context).await;
+    assert_eq!(
+      Some(LemmyErrorType::PostAuthorIsCommunity),
+      res.err().map(|e| e.error_type)
+    );
+    assert_eq!(context.request_count(), 0);
+
+    Person::delete(&mut context.pool(), person.id).await?;
+    Community::delete(&mut context.pool(), community.id).await?;
+    Site::delete(&mut context.pool(), site.id).await?;
+    Ok(())
+  }
+
+  #[tokio::test]
+  #[serial]
+  async fn test_reject_post_linking_to_community_blocked_domain() -> LemmyResult<()> {
+    let context = init_context().await?;
+    let (person, site) = parse_lemmy_person(&context).await?;
+    let community = parse_lemmy_community(&context).await?;
+    let form = CommunityUpdateForm {
+      blocked_url_domains: Some(vec!["lemmy.ml".to_string()]),
+      ..Default::default()
+    };
+    Community::update(&mut context.pool(), community.id, &form).await?;
+
+    // the post links to an image on enterprise.lemmy.ml
+    let json = file_to_json_object("assets/lemmy/objects/page.json")?;
+    let url = Url::parse("https://enterprise.lemmy.ml/post/55143")?;
+    let res = ApubPost::verify(&json, &url, &context).await;
+    assert_eq!(
+      Some(LemmyErrorType::LinkToCommunityBlockedDomain(
+        "enterprise.lemmy.ml".to_string()
+      )),
+      res.err().map(|e| e.error_type)
+    );
+
+    Person::delete(&mut context.pool(), person.id).await?;
+    Community::delete(&mut context.pool(), community.id).await?;
+    Site::delete(&mut context.pool(), site.id).await?;
+    Ok(())
+  }
+
+  #[tokio::test]
+  #[serial]
+  async fn test_community_required_url_host() -> LemmyResult<()> {
+    let context = init_context().await?;
+    let (person, site) = parse_lemmy_person(&context).await?;
+    let community = parse_lemmy_community(&context).await?;
+    let json: Page = file_to_json_object("assets/lemmy/objects/page.json")?;
+    let url = Url::parse("https://enterprise.lemmy.ml/post/55143")?;
+
+    // the post links to an image on enterprise.lemmy.ml
+    let form = CommunityUpdateForm {
+      required_url_host: Some(Some("lemmy.ml".to_string())),
+      ..Default::default()
+    };
+    Community::update(&mut context.pool(), community.id, &form).await?;
+    ApubPost::verify(&json, &url, &context).await?;
+
+    let form = CommunityUpdateForm {
+      required_url_host: Some(Some("example.com".to_string())),
+      ..Default::default()
+    };
+    Community::update(&mut context.pool(), community.id, &form).await?;
+    let res = ApubPost::verify(&json, &url, &context).await;
+    assert_eq!(
+      Some(LemmyErrorType::LinkHostNotAllowedInCommunity(
+        "example.com".to_string()
+      )),
+      res.err().map(|e| e.error_type)
+    );
+
+    Person::delete(&mut context.pool(), person.id).await?;
+    Community::delete(&mut context.pool(), community.id).await?;
+    Site::delete(&mut context.pool(), site.id).await?;
+    Ok(())
+  }
+
+  #[test]
+  fn test_check_url_host_required() -> LemmyResult<()> {
+    let url = Url::parse("https://www.example.com/page")?;
+    check_url_host_required(Some(&url), None, false)?;
+    check_url_host_required(Some(&url), Some("Example.com"), false)?;
+    assert!(check_url_host_required(Some(&url), Some("example.org"), true).is_err());
+    // text posts depend on the setting
+    check_url_host_required(None, Some("example.com"), true)?;
+    assert!(check_url_host_required(None, Some("example.com"), false).is_err());
+    Ok(())
+  }
+
+  #[test]
+  fn test_check_url_not_blocked_by_community() -> LemmyResult<()> {
+    let blocked = vec!["Example.com".to_string(), " ".to_string()];
+    let check = |url: &str| check_url_not_blocked_by_community(&Url::parse(url)?, &blocked);
+    assert!(check("https://example.com/page").is_err());
+    assert!(check("https://www.example.com/page").is_err());
+    assert!(check("https://notexample.com/page").is_ok());
+    assert!(check("https://example.org/page").is_ok());
+    Ok(())
+  }
+
+  #[tokio::test]
+  #[serial]
+  async fn test_reject_post_linking_to_malicious_domain() -> LemmyResult<()> {
+    let blocklist = Arc::new(DomainBlocklist::new(&["lemmy.ml".to_string()]));
+    let context = init_context_with(|c| c.with_url_blocklist(blocklist)).await?;
+    let (person, site) = parse_lemmy_person(&context).await?;
+    let community = parse_lemmy_community(&context).await?;
+
+    // the post links to enterprise.lemmy.ml, a subdomain of the blocked domain
+    let json = file_to_json_object("assets/lemmy/objects/page.json")?;
+    let url = Url::parse("https://enterprise.lemmy.ml/post/55143")?;
+    let res = ApubPost::verify(&json, &url, &context).await;
+    assert_eq!(
+      Some(LemmyErrorType::LinkToMaliciousDomain(
+        "enterprise.lemmy.ml".to_string()
+      )),
+      res.err().map(|e| e.error_type)
+    );
+
+    Person::delete(&mut context.pool(), person.id).await?;
+    Community::delete(&mut context.pool(), community.id).await?;
+    Site::delete(&mut context.pool(), site.id).await?;
+    Ok(())
+  }
+
+  #[tokio::test]
+  #[serial]
+  async fn test_reject_privately_addressed_post() -> LemmyResult<()> {
+    let context = init_context().await?;
+    let (person, site) = parse_lemmy_person(&context).await?;
+    let community = parse_lemmy_community(&context).await?;
+
+    let mut json: Page = file_to_json_object("assets/lemmy/objects/page.json")?;
+    json.bcc = json.to.clone();
+    json.to = json.cc.clone();
+    json.cc = vec![];
+    let url = Url::parse("https://enterprise.lemmy.ml/post/55143")?;
+    let res = ApubPost::verify(&json, &url, &context).await;
+    assert_eq!(
+      Some(LemmyErrorType::ObjectIsNotPublic),
+      res.err().map(|e| e.error_type)
+    );
+
+    Person::delete(&mut context.pool(), person.id).await?;
+    Community::delete(&mut context.pool(), community.id).await?;
+    Site::delete(&mut context.pool(), site.id).await?;
+    Ok(())
+  }
+
+  #[tokio::test]
+  #[serial]
+  async fn test_reject_post_from_site_banned_author() -> LemmyResult<()> {
+    let context = init_context().await?;
+    let (person, site) = parse_lemmy_person(&context).await?;
+    let community = parse_lemmy_community(&context).await?;
+    let form = PersonUpdateForm {
+      banned: Some(true),
+      ..Default::default()
+    };
+    Person::update(&mut context.pool(), person.id, &form).await?;
+
+    let json = file_to_json_object("assets/lemmy/objects/page.json")?;
+    let url = Url::parse("https://enterprise.lemmy.ml/post/55143")?;
+    let res = ApubPost::verify(&json, &url, &context).await;
+    assert!(matches!(
+      res.map_err(|e| e.error_type),
+      Err(LemmyErrorType::PersonIsBannedFromSite(_))
+    ));
+
+    Person::delete(&mut context.pool(), person.id).await?;
+    Community::delete(&mut context.pool(), community.id).await?;
+    Site::delete(&mut context.pool(), site.id).await?;
+    Ok(())
+  }
+
+  #[tokio::test]
+  #[serial]
+  async fn test_reject_post_from_community_banned_author() -> LemmyResult<()> {
+    let context = init_context().await?;
+    let (person, site) = parse_lemmy_person(&context).await?;
+    let community = parse_lemmy_community(&context).await?;
+    let form = CommunityPersonBanForm {
+      community_id: community.id,
+      person_id: person.id,
+      expires: None,
+    };
+    CommunityPersonBan::ban(&mut context.pool(), &form).await?;
+
+    let json = file_to_json_object("assets/lemmy/objects/page.json")?;
+    let url = Url::parse("https://enterprise.lemmy.ml/post/55143")?;
+    let res = ApubPost::verify(&json, &url, &context).await;
+    assert_eq!(
+      Some(LemmyErrorType::PersonIsBannedFromCommunity),
+      res.err().map(|e| e.error_type)
+    );
+
+    Person::delete(&mut context.pool(), person.id).await?;
+    Community::delete(&mut context.pool(), community.id).await?;
+    Site::delete(&mut context.pool(), site.id).await?;
+    Ok(())
+  }
+
+  #[tokio::test]
+  #[serial]
+  async fn test_update_without_sensitive_keeps_nsfw() -> LemmyResult<()> {
+    let context = init_context().await?;
+    let (person, site) = parse_lemmy_person(&context).await?;
+    let community = parse_lemmy_community(&context).await?;
+    let mut page: Page = file_to_json_object("assets/lemmy/objects/page.json")?;
+    page.sensitive = Some(true);
+    let post = ApubPost::from_json(page.clone(), &context).await?;
+    assert!(post.nsfw);
+
+    page.sensitive = None;
+    let post = ApubPost::from_json(page.clone(), &context).await?;
+    assert!(post.nsfw);
+
+    page.sensitive = Some(false);
+    let post = ApubPost::from_json(page, &context).await?;
+    assert!(!post.nsfw);
+
+    cleanup(&context, person, site, community, post).await?;
+    Ok(())
+  }
+
+  #[tokio::test]
+  #[serial]
+  async fn test_verify_page_type() -> LemmyResult<()> {
+    let context = init_context().await?;
+    let (person, site) = parse_lemmy_person(&context).await?;
+    let community = parse_lemmy_community(&context).await?;
+
+    let mut page: Page = file_to_json_object("assets/lemmy/objects/page.json")?;
+    page.kind = PageType::Video;
+    let url = Url::parse("https://enterprise.lemmy.ml/post/55143")?;
+    // fetched videos are stored as posts
+    ApubPost::verify(&page, &url, &context).await?;
+
+    // a video routed to the page handler for activities is rejected
+    let create = CreateOrUpdatePage::from_page(
+      page.clone(),
+      person.actor_id.inner().clone().into(),
+      &community,
+      CreateOrUpdateType::Create,
+      &context,
+    )?;
+    let err = create.verify(&context).await.err().map(|e| e.error_type);
+    assert_eq!(
+      Some(LemmyErrorType::UnsupportedObjectType("Video".to_string())),
+      err
+    );
+    page.kind = PageType::Page;
+    let create = CreateOrUpdatePage {
+      object: page.clone(),
+      ..create
+    };
+    create.verify(&context).await?;
+
+    let post = ApubPost::from_json(page, &context).await?;
+    cleanup(&context, person, site, community, post).await?;
+    Ok(())
+  }
+
+  #[test]
+  fn test_check_author_age() -> LemmyResult<()> {
+    let day = 24 * 60 * 60;
+    let err = check_author_age(Some(Utc::now() - Duration::hours(1)), day)
+      .err()
+      .map(|e| e.error_type);
+    assert_eq!(Some(LemmyErrorType::PostAuthorTooNew), err);
+    check_author_age(Some(Utc::now() - Duration::days(2)), day)?;
+    // unknown creation time is not checked
+    check_author_age(None, day)?;
+    Ok(())
+  }
+
+  #[test]
+  fn test_check_recipient_count() -> LemmyResult<()> {
+    let mut page: Page = file_to_json_object("assets/lemmy/objects/page.json")?;
+    check_recipient_count(&page, 500)?;
+
+    page.cc = (0..500)
+      .map(|i| Url::parse(&format!("https://example.com/u/{i}")))
+      .collect::<Result<_, _>>()?;
+    let err = check_recipient_count(&page, 500)
+      .err()
+      .map(|e| e.error_type);
+    assert_eq!(Some(LemmyErrorType::TooManyRecipients), err);
+    Ok(())
+  }
+
+  #[test]
+  fn test_verify_image_host_allowed() -> LemmyResult<()> {
+    let mut page: Page = file_to_json_object("assets/lemmy/objects/page.json")?;
+    page.image = Some(ImageObject::new(
+      Url::parse("https://media.example.com/image.png")?.into(),
+    ));
+    verify_image_host_allowed(&page, &[])?;
+    verify_image_host_allowed(&page, &["Media.Example.com".to_string()])?;
+
+    let res = verify_image_host_allowed(&page, &["other.example.com".to_string()]);
+    assert_eq!(
+      Some(LemmyErrorType::ImageHostNotAllowed),
+      res.err().map(|e| e.error_type)
+    );
+
+    // images on the author's instance are always allowed
+    page.image = Some(ImageObject::new(
+      Url::parse("https://enterprise.lemmy.ml/pictrs/image/a.png")?.into(),
+    ));
+    verify_image_host_allowed(&page, &["other.example.com".to_string()])?;
+    Ok(())
+  }
+
+  #[test]
+  fn test_verify_software_allowed() -> LemmyResult<()> {
+    let page: Page = file_to_json_object("assets/friendica/objects/page_3.json")?;
+    verify_software_allowed(&page, &[])?;
+    verify_software_allowed(&page, &["Friendica 2024".to_string()])?;
+
+    let res = verify_software_allowed(&page, &["friendica 'giant rhubarb' 2023.05".to_string()]);
+    assert_eq!(
+      Some(LemmyErrorType::SoftwareNotAllowed(
+        "Friendica 'Giant Rhubarb' 2023.05-1518".to_string()
+      )),
+      res.err().map(|e| e.error_type)
+    );
+
+    // pages without generator are not checked
+    let page: Page = file_to_json_object("assets/lemmy/objects/page.json")?;
+    verify_software_allowed(&page, &["lemmy".to_string()])?;
+    Ok(())
+  }
+
+  #[test]
+  fn test_localize_self_links() {
+    let body = "[post](https://example.com/post/5?sort=New#c) and [other](https://example.com.evil.org/post/5)\n\
+                ![image](https://example.com/pictrs/image/a.png \"title\") [remote](https://lemmy.ml/post/5)";
+    assert_eq!(
+      "[post](/post/5?sort=New#c) and [other](https://example.com.evil.org/post/5)\n\
+       ![image](/pictrs/image/a.png \"title\") [remote](https://lemmy.ml/post/5)",
+      localize_self_links(body, "example.com")
+    );
+    assert_eq!(
+      "[post](https://example.com/post/5)",
+      localize_self_links("[post](https://example.com/post/5)", "example.com:8536")
+    );
+    assert_eq!(
+      "no links](",
+      localize_self_links("no links](", "example.com")
+    );
+  }
+
+  #[test]
+  fn test_absolute_self_links() {
+    let body = "[post](/post/5?sort=New#c) and [other](//evil.org/post/5)\n\
+                ![image](/pictrs/image/a.png \"title\") [remote](https://lemmy.ml/post/5)";
+    assert_eq!(
+      "[post](https://example.com/post/5?sort=New#c) and [other](//evil.org/post/5)\n\
+       ![image](https://example.com/pictrs/image/a.png \"title\") [remote](https://lemmy.ml/post/5)",
+      absolute_self_links(body, "https://example.com")
+    );
+    // links stored by localize_self_links are restored
+    let original = "[post](https://example.com/post/5)";
+    assert_eq!(
+      original,
+      absolute_self_links(
+        &localize_self_links(original, "example.com"),
+        "https://example.com"
+      )
+    );
+  }
+
+  #[test]
+  fn test_localize_self_url() -> LemmyResult<()> {
+    let url = Url::parse("http://example.com/post/5?sort=New#c")?;
+    assert_eq!(
+      "https://example.com/post/5?sort=New#c",
+      localize_self_url(url, "example.com", "https").as_str()
+    );
+    let url = Url::parse("http://example.com.evil.org/post/5")?;
+    assert_eq!(
+      "http://example.com.evil.org/post/5",
+      localize_self_url(url, "example.com", "https").as_str()
+    );
+    Ok(())
+  }
+
+  #[tokio::test]
+  async fn test_import_timeout() -> LemmyResult<()> {
+    let slow_fetch = async {
+      tokio::time::sleep(StdDuration::from_secs(10)).await;
+      Ok::<_, LemmyError>(())
+    };
+    let res = with_timeout(slow_fetch, StdDuration::from_millis(10)).await;
+    assert_eq!(
+      Some(LemmyErrorType::PostImportTimedOut),
+      res.err().map(|e| e.error_type)
+    );
+
+    let fast_fetch = async { Ok::<_, LemmyError>(1) };
+    assert_eq!(
+      1,
+      with_timeout(fast_fetch, StdDuration::from_millis(10)).await?
+    );
+    let no_limit = async { Ok::<_, LemmyError>(2) };
+    assert_eq!(2, with_timeout(no_limit, StdDuration::ZERO).await?);
+    Ok(())
+  }
+
+  #[test]
+  fn test_limit_attachments() -> LemmyResult<()> {
+    let mut page: Page = file_to_json_object("assets/lemmy/objects/page.json")?;
+    page.attachment = (0..5)
+      .map(|i| Url::parse(&format!("https://example.com/{i}")).map(|u| Attachment::new(u.into())))
+      .collect::<Result<_, _>>()?;
+
+    let res = limit_attachments(&mut page.clone(), 2, true);
+    assert_eq!(
+      Some(LemmyErrorType::TooManyAttachments),
+      res.err().map(|e| e.error_type)
+    );
+
+    limit_attachments(&mut page, 5, false)?;
+    assert_eq!(5, page.attachment.len());
+    limit_attachments(&mut page, 2, false)?;
+    let urls: Vec<_> = page
+      .attachment
+      .into_iter()
+      .filter_map(|a| a.url(page.id.inner()))
+      .collect();
+    assert_eq!(
+      vec![
+        Url::parse("https://example.com/0")?,
+        Url::parse("https://example.com/1")?
+      ],
+      urls
+    );
+    Ok(())
+  }
+
+  #[test]
+  fn test_matches_nsfw_keyword() {
+    let keywords = vec!["Spoiler".to_string(), " ".to_string()];
+    assert!(matches_nsfw_keyword(&keywords, "Big SPOILER inside", None));
+    assert!(matches_nsfw_keyword(&keywords, "Title", Some("a spoiler")));
+    assert!(!matches_nsfw_keyword(&keywords, "Title", Some("body")));
+    assert!(!matches_nsfw_keyword(&[], "spoiler", None));
+  }
+
+  #[tokio::test]
+  #[serial]
+  async fn test_nsfw_keyword_marks_post() -> LemmyResult<()> {
+    let context = init_context().await?;
+    let (person, site) = parse_lemmy_person(&context).await?;
+    let community = parse_lemmy_community(&context).await?;
+    let form = CommunityUpdateForm {
+      nsfw_keywords: Some(vec!["title".to_string()]),
+      ..Default::default()
+    };
+    Community::update(&mut context.pool(), community.id, &form).await?;
+
+    let mut page: Page = file_to_json_object("assets/lemmy/objects/page.json")?;
+    page.sensitive = Some(false);
+    let post = ApubPost::from_json(page, &context).await?;
+    assert!(post.nsfw);
+
+    cleanup(&context, person, site, community, post).await?;
+    Ok(())
+  }
+
+  #[tokio::test]
+  #[serial]
+  async fn test_skip_posts_without_local_followers() -> LemmyResult<()> {
+    let context = init_context().await?;
+    let (person, site) = parse_lemmy_person(&context).await?;
+    let community = parse_lemmy_community(&context).await?;
+    let page: Page = file_to_json_object("assets/lemmy/objects/page.json")?;
+
+    check_local_followers(false, &page, &community, &context).await?;
+    let res = check_local_followers(true, &page, &community, &context).await;
+    assert_eq!(
+      Some(LemmyErrorType::CommunityHasNoLocalFollowers),
+      res.err().map(|e| e.error_type)
+    );
+
+    let form = CommunityFollowerForm {
+      community_id: community.id,
+      person_id: person.id,
+      pending: false,
+    };
+    CommunityFollower::follow(&mut context.pool(), &form).await?;
+    check_local_followers(true, &page, &community, &context).await?;
+
+    let post = ApubPost::from_json(page, &context).await?;
+    cleanup(&context, person, site, community, post).await?;
+    Ok(())
+  }
+
+  #[tokio::test]
+  #[serial]
+  async fn test_import_cache_fetches_once() -> LemmyResult<()> {
+    let context = init_context().await?;
+    let mut cache = ImportCache::default();
+    let id = ObjectId::<ApubPerson>::parse("https://unreachable.example/u/repeated")?;
+
+    // fetching fails because network access is blocked in tests, the failure is not retried
+    assert!(cache.person(&id, &context).await.is_err());
+    assert!(cache.person(&id, &context).await.is_err());
+    assert_eq!(1, context.request_count());
+    Ok(())
+  }
+
+  #[tokio::test]
+  #[serial]
+  async fn test_resolve_author_with_webfinger() -> LemmyResult<()> {
+    let webfinger = json!({
+      "subject": "acct:picard@enterprise.lemmy.ml",
+      "links": [{
+        "rel": "self",
+        "type": "application/activity+json",
+        "href": "https://enterprise.lemmy.ml/u/picard"
+      }]
+    });
+    let responses = HashMap::from([(
+      "https://enterprise.lemmy.ml/.well-known/webfinger?resource=acct:picard@enterprise.lemmy.ml"
+        .to_string(),
+      webfinger.to_string(),
+    )]);
+    let context = init_context_with_responses(responses).await?;
+    let (person, site) = parse_lemmy_person(&context).await?;
+    let community = parse_lemmy_community(&context).await?;
+
+    let mut page: Page = file_to_json_object("assets/lemmy/objects/page.json")?;
+    page.attributed_to = AttributedTo::Lemmy(ObjectId::parse("acct:picard@enterprise.lemmy.ml")?);
+    let url = Url::parse("https://enterprise.lemmy.ml/post/55143")?;
+    ApubPost::verify(&page, &url, &context).await?;
+    let post = ApubPost::from_json(page, &context).await?;
+    assert_eq!(person.id, post.creator_id);
+
+    cleanup(&context, person, site, community, post).await?;
+    Ok(())
+  }
+
+  #[tokio::test]
+  #[serial]
+  async fn test_webfinger_author_on_other_domain() -> LemmyResult<()> {
+    // evil.example claims that its user is an account on enterprise.lemmy.ml
+    let webfinger = json!({
+      "subject": "acct:picard@evil.example",
+      "links": [{
+        "rel": "self",
+        "type": "application/activity+json",
+        "href": "https://enterprise.lemmy.ml/u/picard"
+      }]
+    });
+    let responses = HashMap::from([(
+      "https://evil.example/.well-known/webfinger?resource=acct:picard@evil.example".to_string(),
+      webfinger.to_string(),
+    )]);
+    let context = init_context_with_responses(responses).await?;
+    let (person, site) = parse_lemmy_person(&context).await?;
+
+    let id = ObjectId::<ApubPerson>::parse("acct:picard@evil.example")?;
+    assert!(dereference_person(&id, &context).await.is_err());
+
+    Person::delete(&mut context.pool(), person.id).await?;
+    Site::delete(&mut context.pool(), site.id).await?;
+    Ok(())
+  }
+
+  #[tokio::test]
+  #[serial]
+  async fn test_post_rate_limit() -> LemmyResult<()> {
+    let context = init_context().await?;
+    let (person, site) = parse_lemmy_person(&context).await?;
+    let community = parse_lemmy_community(&context).await?;
+    let form = CommunityUpdateForm {
+      post_rate_limit: Some(Some(2)),
+      ..Default::default()
+    };
+    Community::update(&mut context.pool(), community.id, &form).await?;
+
+    let url = Url::parse("https://enterprise.lemmy.ml/post/55143")?;
+    let mut page: Page = file_to_json_object("assets/lemmy/objects/page.json")?;
+    page.published = Some(Utc::now());
+    ApubPost::verify(&page, &url, &context).await?;
+    let post = ApubPost::from_json(page.clone(), &context).await?;
+
+    let mut second = page.clone();
+    second.id = ObjectId::parse("https://enterprise.lemmy.ml/post/55144")?;
+    ApubPost::verify(&second, &url, &context).await?;
+    let second = ApubPost::from_json(second, &context).await?;
+
+    // updating an existing post is still allowed
+    ApubPost::verify(&page, &url, &context).await?;
+    let mut third = page;
+    third.id = ObjectId::parse("https://enterprise.lemmy.ml/post/55145")?;
+    let res = ApubPost::verify(&third, &url, &context).await;
+    assert_eq!(
+      Some(LemmyErrorType::TooManyPostsInCommunity),
+      res.err().map(|e| e.error_type)
+    );
+
+    Post::delete(&mut context.pool(), second.id).await?;
+    cleanup(&context, person, site, community, post).await?;
+    Ok(())
+  }
+
+  #[tokio::test]
+  #[serial]
+  async fn test_post_rate_limit_backdated() -> LemmyResult<()> {
+    let context = init_context().await?;
+    let (person, site) = parse_lemmy_person(&context).await?;
+    let community = parse_lemmy_community(&context).await?;
+    let form = CommunityUpdateForm {
+      post_rate_limit: Some(Some(1)),
+      ..Default::default()
+    };
+    Community::update(&mut context.pool(), community.id, &form).await?;
+
+    // posts which claim to be old still count towards the limit
+    let url = Url::parse("https://enterprise.lemmy.ml/post/55143")?;
+    let mut page: Page = file_to_json_object("assets/lemmy/objects/page.json")?;
+    page.published = Some(Utc::now() - Duration::days(365));
+    ApubPost::verify(&page, &url, &context).await?;
+    let post = ApubPost::from_json(page.clone(), &context).await?;
+
+    let mut second = page;
+    second.id = ObjectId::parse("https://enterprise.lemmy.ml/post/55144")?;
+    let res = ApubPost::verify(&second, &url, &context).await;
+    assert_eq!(
+      Some(LemmyErrorType::TooManyPostsInCommunity),
+      res.err().map(|e| e.error_type)
+    );
+
+    cleanup(&context, person, site, community, post).await?;
+    Ok(())
+  }
+
+  #[tokio::test]
+  #[serial]
+  async fn test_community_post_cap() -> LemmyResult<()> {
+    let context = init_context().await?;
+    let (person, site) = parse_lemmy_person(&context).await?;
+    let community = parse_lemmy_community(&context).await?;
+    let form = CommunityUpdateForm {
+      max_posts: Some(Some(1)),
+      ..Default::default()
+    };
+    Community::update(&mut context.pool(), community.id, &form).await?;
+
+    let url = Url::parse("https://enterprise.lemmy.ml/post/55143")?;
+    let page: Page = file_to_json_object("assets/lemmy/objects/page.json")?;
+    ApubPost::verify(&page, &url, &context).await?;
+    let post = ApubPost::from_json(page.clone(), &context).await?;
+
+    // updating the existing post is still allowed
+    ApubPost::verify(&page, &url, &context).await?;
+    let mut second = page;
+    second.id = ObjectId::parse("https://enterprise.lemmy.ml/post/55144")?;
+    let res = ApubPost::verify(&second, &url, &context).await;
+    assert_eq!(
+      Some(LemmyErrorType::CommunityPostLimitReached),
+      res.err().map(|e| e.error_type)
+    );
+
+    cleanup(&context, person, site, community, post).await?;
+    Ok(())
+  }
+
+  #[tokio::test]
+  #[serial]
+  async fn test_into_json_source_only_for_local_posts() -> LemmyResult<()> {
+    let context = init_context().await?;
+    let (person, site) = parse_lemmy_person(&context).await?;
+    let community = parse_lemmy_community(&context).await?;
+    let json = file_to_json_object("assets/lemmy/objects/page.json")?;
+    let post = ApubPost::from_json(json, &context).await?;
+
+    let page = post.clone().into_json(&context).await?;
+    assert!(page.source.is_none());
+    assert_eq!(
+      Some("<p>This is a post in the /c/tenforward community</p>\n"),
+      page.content.as_deref()
+    );
+
+    let local = ApubPost(Post {
+      local: true,
+      ..post.0.clone()
+    });
+    let page = local.into_json(&context).await?;
+    assert_eq!(post.body, page.source.map(|s| s.content));
+
+    cleanup(&context, person, site, community, post).await?;
+    Ok(())
+  }
+
+  #[tokio::test]
+  #[serial]
+  async fn test_into_json_omits_updated_if_not_edited() -> LemmyResult<()> {
+    let context = init_context().await?;
+    let (person, site) = parse_lemmy_person(&context).await?;
+    let community = parse_lemmy_community(&context).await?;
+
+    let mut page: Page = file_to_json_object("assets/lemmy/objects/page.json")?;
+    page.updated = page.published;
+    let post = ApubPost::from_json(page, &context).await?;
+    assert!(post.updated.is_some());
+    let json = post.clone().into_json(&context).await?;
+    assert_eq!(None, json.updated);
+
+    cleanup(&context, person, site, community, post).await?;
+    Ok(())
+  }
+
+  #[tokio::test]
+  #[serial]
+  async fn test_location_round_trip() -> LemmyResult<()> {
+    let context = init_context().await?;
+    let (person, site) = parse_lemmy_person(&context).await?;
+    let community = parse_lemmy_community(&context).await?;
+
+    let place = Place {
+      kind: Default::default(),
+      name: Some("Berlin".to_string()),
+      latitude: Some(52.52),
+      longitude: Some(13.405),
+    };
+    let mut page: Page = file_to_json_object("assets/lemmy/objects/page.json")?;
+    page.location = Some(place.clone());
+    let post = ApubPost::from_json(page, &context).await?;
+    let json = post.clone().into_json(&context).await?;
+    assert_eq!(Some(place), json.location);
+
+    // location is removed again by an update without it
+    let page: Page = file_to_json_object("assets/lemmy/objects/page.json")?;
+    let post = ApubPost::from_json(page, &context).await?;
+    let json = serde_json::to_value(post.clone().into_json(&context).await?)?;
+    assert_eq!(None, json.get("location"));
+
+    cleanup(&context, person, site, community, post).await?;
+    Ok(())
+  }
+
+  #[tokio::test]
+  #[serial]
+  async fn test_edit_history() -> LemmyResult<()> {
+    let context = init_context().await?;
+    let (person, site) = parse_lemmy_person(&context).await?;
+    let community = parse_lemmy_community(&context).await?;
+    let mut page: Page = file_to_json_object("assets/lemmy/objects/page.json")?;
+    let original = ApubPost::from_json(page.clone(), &context).await?;
+
+    // receiving the same version again is not an edit
+    ApubPost::from_json(page.clone(), &context).await?;
+    assert!(PostEditHistory::list(&mut context.pool(), original.id)
+      .await?
+      .is_empty());
+
+    page.name = Some("Edited title".to_string());
+    let post = ApubPost::from_json(page, &context).await?;
+    assert_eq!("Edited title", post.name);
+    let history = PostEditHistory::list(&mut context.pool(), post.id).await?;
+    assert_eq!(1, history.len());
+    let previous = history.first().ok_or(LemmyErrorType::CouldntFindPost)?;
+    assert_eq!(original.name, previous.name);
+    assert_eq!(original.body, previous.body);
+    assert_eq!(original.url, previous.url);
+
+    cleanup(&context, person, site, community, post).await?;
+    Ok(())
+  }
+
+  #[tokio::test]
+  #[serial]
+  async fn test_store_raw_apub() -> LemmyResult<()> {
+    let mut settings = SETTINGS.clone();
+    settings.federation.store_raw_apub = true;
+    let settings: &'static Settings = Box::leak(Box::new(settings));
+    let context = init_context_with(|c| c.with_settings(settings)).await?;
+    let (person, site) = parse_lemmy_person(&context).await?;
+    let community = parse_lemmy_community(&context).await?;
+
+    let mut json: Value = file_to_json_object("assets/lemmy/objects/page.json")?;
+    if let Some(o) = json.as_object_mut() {
+      o.insert("unknownField".to_string(), json!([1, 2]));
+    }
+    let mut received = json.clone();
+    if let Some(o) = received.as_object_mut() {
+      o.insert("bto".to_string(), json!("https://secret.example/u/alice"));
+      o.insert("bcc".to_string(), json!(["https://secret.example/u/bob"]));
+    }
+    let page: Page = serde_json::from_value(received)?;
+
+    // disabled in the default config
+    let post = ApubPost::from_json(page.clone(), &init_context().await?).await?;
+    assert_eq!(None, PostRawApub::read(&mut context.pool(), post.id).await?);
+
+    // the json is stored as it arrived, including fields which are not parsed, but without
+    // private addressing
+    let post = ApubPost::from_json(page, &context).await?;
+    let stored = PostRawApub::read(&mut context.pool(), post.id)
+      .await?
+      .map(|s| s.data);
+    assert!(stored
+      .as_ref()
+      .is_some_and(|s| s.get("bto").is_none() && s.get("bcc").is_none()));
+    assert_eq!(Some(json), stored);
+
+    cleanup(&context, person, site, community, post).await?;
+    Ok(())
+  }
+
+  #[tokio::test]
+  #[serial]
+  async fn test_parse_post_with_icon() -> LemmyResult<()> {
+    let context = init_context().await?;
+    let (person, site) = parse_lemmy_person(&context).await?;
+    let community = parse_lemmy_community(&context).await?;
+
+    let mut json: Value = file_to_json_object("assets/lemmy/objects/page.json")?;
+    if let Some(o) = json.as_object_mut() {
+      o.remove("image");
+      o.insert(
+        "icon".to_string(),
+        json!({ "type": "Image", "url": "https://example.com/icon.png" }),
+      );
+    }
+    let post = ApubPost::from_json(serde_json::from_value(json)?, &context).await?;
+    assert_eq!(
+      Some("https://example.com/icon.png"),
+      post.thumbnail_url.as_ref().map(|u| u.as_str())
+    );
+
+    // outgoing pages only use image
+    let json = serde_json::to_value(post.clone().into_json(&context).await?)?;
+    assert_eq!(None, json.get("icon"));
+    assert!(json.get("image").is_some());
+
+    cleanup(&context, person, site, community, post).await?;
+    Ok(())
+  }
+
+  #[tokio::test]
+  #[serial]
+  async fn test_preview_seeds_embed() -> LemmyResult<()> {
+    let context = init_context().await?;
+    let (person, site) = parse_lemmy_person(&context).await?;
+    let community = parse_lemmy_community(&context).await?;
+
+    let mut json: Value = file_to_json_object("assets/lemmy/objects/page.json")?;
+    if let Some(o) = json.as_object_mut() {
+      o.remove("image");
+      o.insert(
+        "preview".to_string(),
+        json!({
+          "type": "Image",
+          "url": "https://example.com/card.png",
+          "name": "Card title",
+          "summary": "Card description"
+        }),
+      );
+    }
+    let post = ApubPost::from_json(serde_json::from_value(json)?, &context).await?;
+    assert_eq!(Some("Card title"), post.embed_title.as_deref());
+    assert_eq!(Some("Card description"), post.embed_description.as_deref());
+    assert_eq!(None, post.embed_video_url);
+    assert_eq!(
+      Some("https://example.com/card.png"),
+      post.thumbnail_url.as_ref().map(|u| u.as_str())
+    );
+
+    cleanup(&context, person, site, community, post).await?;
+    Ok(())
+  }
+
+  #[tokio::test]
+  #[serial]
+  async fn test_html_source_converted_to_markdown() -> LemmyResult<()> {
+    let context = init_context().await?;
+    let (person, site) = parse_lemmy_person(&context).await?;
+    let community = parse_lemmy_community(&context).await?;
+
+    let mut page: Page = file_to_json_object("assets/lemmy/objects/page.json")?;
+    page.source = Some(serde_json::from_value(json!({
+      "content": "<p>Some <strong>bold</strong> text</p>",
+      "mediaType": "text/html"
+    }))?);
+    let post = ApubPost::from_json(page, &context).await?;
+    assert_eq!(Some("Some **bold** text"), post.body.as_deref());
+
+    cleanup(&context, person, site, community, post).await?;
+    Ok(())
+  }
+
+  #[tokio::test]
+  #[serial]
+  async fn test_content_map_from_translations() -> LemmyResult<()> {
+    let context = init_context().await?;
+    let (person, site) = parse_lemmy_person(&context).await?;
+    let community = parse_lemmy_community(&context).await?;
+    let page: Page = file_to_json_object("assets/lemmy/objects/page.json")?;
+    let post = ApubPost::from_json(page, &context).await?;
+    let mut settings = SETTINGS.clone();
+    settings.federation.federate_translations = true;
+    let posts = std::slice::from_ref(&post);
+    let parts = PageParts::read(posts, &settings, &mut context.pool()).await?;
+    assert_eq!(None, post.content_map(&parts, &settings));
+
+    let english = Language::read_id_from_code(&mut context.pool(), Some("en"))
+      .await?
+      .ok_or(LemmyErrorType::CouldntFindObject)?;
+    let form = PostTranslationForm {
+      post_id: post.id,
+      language_id: english,
+      body: "Translated *body*".to_string(),
+    };
+    PostTranslation::upsert(&mut context.pool(), &form).await?;
+
+    // the original body is included in the post language
+    let parts = PageParts::read(posts, &settings, &mut context.pool()).await?;
+    let content_map = post.content_map(&parts, &settings).unwrap_or_default();
+    assert_eq!(vec!["en", "fr"], content_map.keys().collect::<Vec<_>>());
+    assert_eq!(
+      Some(&"<p>Translated <em>body</em></p>\n".to_string()),
+      content_map.get("en")
+    );
+    assert_eq!(
+      post.clone().into_json(&context).await?.content,
+      content_map.get("fr").cloned()
+    );
+
+    cleanup(&context, person, site, community, post).await?;
+    Ok(())
+  }
+
+  #[tokio::test]
+  #[serial]
+  async fn test_in_language_fallback() -> LemmyResult<()> {
+    let context = init_context().await?;
+    let (person, site) = parse_lemmy_person(&context).await?;
+    let community = parse_lemmy_community(&context).await?;
+
+    let mut page: Page = file_to_json_object("assets/lemmy/objects/page.json")?;
+    page.language = None;
+    page.in_language = Some("de-AT".to_string());
+    let post = ApubPost::from_json(page.clone(), &context).await?;
+    let german = Language::read_id_from_code(&mut context.pool(), Some("de")).await?;
+    assert_eq!(german, Some(post.language_id));
+
+    // `language` takes precedence, unknown codes are ignored
+    page.language = file_to_json_object::<Page>("assets/lemmy/objects/page.json")?.language;
+    let language = LanguageTag::to_language_id_single(page.language(), &mut context.pool()).await?;
+    assert_ne!(german, language);
+    page.language = None;
+    page.in_language = Some("xx-unknown".to_string());
+    let language = LanguageTag::to_language_id_single(page.language(), &mut context.pool()).await?;
+    assert_eq!(None, language);
+
+    cleanup(&context, person, site, community, post).await?;
+    Ok(())
+  }
+
+  #[tokio::test]
+  #[serial]
+  async fn test_unknown_media_type_as_plain_text() -> LemmyResult<()> {
+    let context = init_context().await?;
+    let (person, site) = parse_lemmy_person(&context).await?;
+    let community = parse_lemmy_community(&context).await?;
+
+    let mut json: Value = file_to_json_object("assets/lemmy/objects/page.json")?;
+    if let Some(o) = json.as_object_mut() {
+      o.insert("mediaType".to_string(), json!("text/x-custom"));
+      o.insert("content".to_string(), json!("# Not a *heading*"));
+      o.remove("source");
+    }
+    let page: Page = serde_json::from_value(json)?;
+    let post = ApubPost::from_json(page.clone(), &context).await?;
+    assert_eq!(Some("\\# Not a \\*heading\\*"), post.body.as_deref());
+
+    // markdown is also downgraded once it is not accepted anymore
+    let mut page = page;
+    page.media_type = Some("text/markdown".to_string());
+    assert_eq!(
+      Some("# Not a *heading*".to_string()),
+      page.body(&["text/markdown".to_string()])
+    );
+    assert_eq!(
+      Some("\\# Not a \\*heading\\*".to_string()),
+      page.body(&["text/html".to_string()])
+    );
+
+    cleanup(&context, person, site, community, post).await?;
+    Ok(())
+  }
+
+  #[test]
+  fn test_fallback_thumbnail() -> LemmyResult<()> {
+    let fallback = Url::parse("https://example.com/fallback.png")?;
+    let link = Some(Url::parse("https://example.com/article")?);
+    let thumbnail: DbUrl = Url::parse("https://example.com/thumbnail.png")?.into();
+
+    assert_eq!(
+      Some(fallback.clone().into()),
+      with_fallback_thumbnail(None, &link, Some(&fallback))
+    );
+    // existing thumbnails are kept, and text posts don't get one
+    assert_eq!(
+      Some(thumbnail.clone()),
+      with_fallback_thumbnail(Some(thumbnail), &link, Some(&fallback))
+    );
+    assert_eq!(None, with_fallback_thumbnail(None, &None, Some(&fallback)));
+    assert_eq!(None, with_fallback_thumbnail(None, &link, None));
+    Ok(())
+  }
+
+  #[tokio::test]
+  #[serial]
+  async fn test_post_from_bot() -> LemmyResult<()> {
+    let context = init_context().await?;
+    let (person, site) = parse_lemmy_person(&context).await?;
+    let community = parse_lemmy_community(&context).await?;
+    let page: Page = file_to_json_object("assets/lemmy/objects/page.json")?;
+
+    let post = ApubPost::from_json(page.clone(), &context).await?;
+    assert!(!post.from_bot);
+
+    let form = PersonUpdateForm {
+      bot_account: Some(true),
+      ..Default::default()
+    };
+    Person::update(&mut context.pool(), person.id, &form).await?;
+    let post = ApubPost::from_json(page, &context).await?;
+    assert!(post.from_bot);
+
+    cleanup(&context, person, site, community, post).await?;
+    Ok(())
+  }
+
+  #[tokio::test]
+  #[serial]
+  async fn test_conversation_id_round_trip() -> LemmyResult<()> {
+    let context = init_context().await?;
+    let (person, site) = parse_lemmy_person(&context).await?;
+    let community = parse_lemmy_community(&context).await?;
+    let conversation = Url::parse("tag:enterprise.lemmy.ml,2024-01-24:objectId=55143")?;
+
+    // `conversation` is only a fallback, it is sent out again as `context`
+    let mut page: Page = file_to_json_object("assets/lemmy/objects/page.json")?;
+    page.conversation = Some(conversation.clone());
+    let post = ApubPost::from_json(page, &context).await?;
+    assert_eq!(Some(conversation.clone().into()), post.conversation_id);
+    let json = post.clone().into_json(&context).await?;
+    assert_eq!(Some(conversation), json.context);
+    assert_eq!(None, json.conversation);
+
+    cleanup(&context, person, site, community, post).await?;
+    Ok(())
+  }
+
+  #[tokio::test]
+  #[serial]
+  async fn test_received_at_independent_of_published() -> LemmyResult<()> {
+    let context = init_context().await?;
+    let (person, site) = parse_lemmy_person(&context).await?;
+    let community = parse_lemmy_community(&context).await?;
+
+    let mut page: Page = file_to_json_object("assets/lemmy/objects/page.json")?;
+    page.published = Some(Utc::now() - Duration::days(365));
+    let before = Utc::now();
+    let post = ApubPost::from_json(page.clone(), &context).await?;
+    let received_at = post.received_at.ok_or(LemmyErrorType::CouldntFindPost)?;
+    assert!(received_at >= before && received_at <= Utc::now());
+    assert!(post.published < before - Duration::days(300));
+
+    // an update keeps the time of first arrival
+    page.updated = Some(Utc::now());
+    let post = ApubPost::from_json(page, &context).await?;
+    assert_eq!(Some(received_at), post.received_at);
+
+    cleanup(&context, person, site, community, post).await?;
+    Ok(())
+  }
+
+  #[tokio::test]
+  #[serial]
+  async fn test_parse_audio_as_link_post() -> LemmyResult<()> {
+    let context = init_context().await?;
+    let (person, site) = parse_lemmy_person(&context).await?;
+    let community = parse_lemmy_community(&context).await?;
+
+    let mut json: serde_json::Value = file_to_json_object("assets/lemmy/objects/page.json")?;
+    if let Some(o) = json.as_object_mut() {
+      o.insert("type".to_string(), json!("Audio"));
+      o.remove("attachment");
+      o.insert(
+        "url".to_string(),
+        json!([
+          {"type": "Link", "mediaType": "text/html", "href": "https://audio.example.com/track/1"},
+          {"type": "Link", "mediaType": "audio/ogg", "href": "https://audio.example.com/1.ogg"}
+        ]),
+      );
+      o.insert("duration".to_string(), json!("PT3M20S"));
+    }
+    let page: Page = serde_json::from_value(json)?;
+    assert_eq!(PageType::Audio, page.kind);
+    ApubPost::verify(&page, page.id.inner(), &context).await?;
+    let post = ApubPost::from_json(page, &context).await?;
+
+    assert_eq!(
+      Some("https://audio.example.com/1.ogg"),
+      post.url.as_ref().map(|u| u.as_str())
+    );
+    assert_eq!(Some("PT3M20S".to_string()), post.duration);
+    let page = post.clone().into_json(&context).await?;
+    assert_eq!(Some("PT3M20S".to_string()), page.duration);
+
+    cleanup(&context, person, site, community, post).await?;
+    Ok(())
+  }
+
+  #[tokio::test]
+  #[serial]
+  async fn test_image_alt_text_round_trip() -> LemmyResult<()> {
+    let context = init_context().await?;
+    let (person, site) = parse_lemmy_person(&context).await?;
+    let community = parse_lemmy_community(&context).await?;
+
+    let mut json: Value = file_to_json_object("assets/lemmy/objects/page.json")?;
+    if let Some(o) = json.as_object_mut() {
+      o.insert(
+        "image".to_string(),
+        json!({ "type": "Image", "url": "https://example.com/cat.png", "name": "A sleeping cat" }),
+      );
+    }
+    let post = ApubPost::from_json(serde_json::from_value(json)?, &context).await?;
+    assert_eq!(Some("A sleeping cat"), post.alt_text.as_deref());
+
+    let page = post.clone().into_json(&context).await?;
+    let image = page.image.as_ref();
+    assert_eq!(
+      Some("https://example.com/cat.png"),
+      image.map(|i| i.url.as_str())
+    );
+    assert_eq!(
+      Some("A sleeping cat"),
+      image.and_then(|i| i.name.as_deref())
+    );
+
+    cleanup(&context, person, site, community, post).await?;
+    Ok(())
+  }
+
+  #[tokio::test]
+  #[serial]
+  async fn test_image_blurhash_round_trip() -> LemmyResult<()> {
+    let context = init_context().await?;
+    let (person, site) = parse_lemmy_person(&context).await?;
+    let community = parse_lemmy_community(&context).await?;
+
+    let mut json: Value = file_to_json_object("assets/lemmy/objects/page.json")?;
+    if let Some(o) = json.as_object_mut() {
+      o.insert(
+        "image".to_string(),
+        json!({
+          "type": "Image",
+          "url": "https://example.com/cat.png",
+          "blurhash": "LEHV6nWB2yk8pyo0adR*.7kCMdnj"
+        }),
+      );
+    }
+    let post = ApubPost::from_json(serde_json::from_value(json)?, &context).await?;
+    assert_eq!(
+      Some("LEHV6nWB2yk8pyo0adR*.7kCMdnj"),
+      post.thumbnail_blurhash.as_deref()
+    );
+
+    let page = post.clone().into_json(&context).await?;
+    let image = serde_json::to_value(page.image)?;
+    assert_eq!(
+      Some("LEHV6nWB2yk8pyo0adR*.7kCMdnj"),
+      image.get("blurhash").and_then(Value::as_str)
+    );
+
+    // blurhash in an unexpected format is ignored
+    let image: ImageObject = serde_json::from_value(json!({
+      "type": "Image",
+      "url": "https://example.com/cat.png",
+      "blurhash": { "value": 1 }
+    }))?;
+    assert_eq!(None, image.blurhash);
+
+    cleanup(&context, person, site, community, post).await?;
+    Ok(())
+  }
+
+  #[tokio::test]
+  #[serial]
+  async fn test_relative_url_resolved_against_id() -> LemmyResult<()> {
+    let context = init_context().await?;
+    let (person, site) = parse_lemmy_person(&context).await?;
+    let community = parse_lemmy_community(&context).await?;
+
+    let mut json: Value = file_to_json_object("assets/lemmy/objects/page.json")?;
+    if let Some(o) = json.as_object_mut() {
+      o.insert(
+        "attachment".to_string(),
+        json!([{ "type": "Link", "href": "/media/cat.png" }]),
+      );
+    }
+    let page: Page = serde_json::from_value(json)?;
+    let url = Url::parse("https://enterprise.lemmy.ml/post/55143")?;
+    ApubPost::verify(&page, &url, &context).await?;
+    let post = ApubPost::from_json(page, &context).await?;
+    assert_eq!(
+      Some("https://enterprise.lemmy.ml/media/cat.png"),
+      post.url.as_ref().map(|u| u.as_str())
+    );
+
+    cleanup(&context, person, site, community, post).await?;
+    Ok(())
+  }
+
+  #[tokio::test]
+  #[serial]
+  async fn test_parse_post_with_invalid_image() -> LemmyResult<()> {
+    let context = init_context().await?;
+    let (person, site) = parse_lemmy_person(&context).await?;
+    let community = parse_lemmy_community(&context).await?;
+
+    let mut json: Value = file_to_json_object("assets/lemmy/objects/page.json")?;
+    if let Some(o) = json.as_object_mut() {
+      o.insert(
+        "image".to_string(),
+        json!({ "type": "Image", "url": "not a url" }),
+      );
+    }
+    let page: Page = serde_json::from_value(json.clone())?;
+    assert!(page.image.is_none());
+    let post = ApubPost::from_json(page, &context).await?;
+    assert_eq!(None, post.thumbnail_url);
+    Post::delete(&mut context.pool(), post.id).await?;
+
+    let long_url = format!(
+      "https://example.com/{}.png",
+      "a".repeat(MAX_IMAGE_URL_LENGTH)
+    );
+    if let Some(o) = json.as_object_mut() {
+      o.insert(
+        "image".to_string(),
+        json!({ "type": "Image", "url": long_url }),
+      );
+    }
+    let post = ApubPost::from_json(serde_json::from_value(json)?, &context).await?;
+    assert_eq!(None, post.thumbnail_url);
+    assert_eq!("Post title", post.name);
+
+    cleanup(&context, person, site, community, post).await?;
+    Ok(())
+  }
+
+  #[test]
+  fn test_page_from_post_parts() -> LemmyResult<()> {
+    let published = "2024-01-01T00:00:00Z";
+    let creator: Person = serde_json::from_value(json!({
+      "id": 1,
+      "name": "alice",
+      "banned": false,
+      "published": published,
+      "actor_id": "https://example.com/u/alice",
+      "local": true,
+      "deleted": false,
+      "bot_account": false,
+      "instance_id": 1
+    }))?;
+    let community: Community = serde_json::from_value(json!({
+      "id": 1,
+      "name": "news",
+      "title": "News",
+      "removed": false,
+      "published": published,
+      "deleted": false,
+      "nsfw": false,
+      "actor_id": "https://example.com/c/news",
+      "local": true,
+      "hidden": false,
+      "posting_restricted_to_mods": false,
+      "instance_id": 1,
+      "nsfw_keywords": [],
+      "local_only": false,
+      "blocked_url_domains": []
+    }))?;
+    let post: Post = serde_json::from_value(json!({
+      "id": 1,
+      "name": "Post title",
+      "body": "Some *markdown*",
+      "creator_id": 1,
+      "community_id": 1,
+      "removed": false,
+      "locked": true,
+      "published": published,
+      "deleted": false,
+      "nsfw": false,
+      "ap_id": "https://example.com/post/1",
+      "local": true,
+      "language_id": 0,
+      "featured_community": false,
+      "featured_local": false,
+      "from_bot": false
+    }))?;
+
+    let page = Page::from_post_parts(&post, &creator, &community, &SETTINGS);
+    assert_eq!(post.ap_id.inner(), page.id.inner());
+    assert_eq!(Some(post.name.clone()), page.name);
+    assert_eq!(
+      Some("<p>Some <em>markdown</em></p>\n".to_string()),
+      page.content
+    );
+    assert_eq!(Some(false), page.comments_enabled);
+    assert_eq!(vec![community.actor_id.inner().clone()], page.cc);
+    assert!(page.language.is_none());
+    Ok(())
+  }
+
+  #[tokio::test]
+  #[serial]
+  async fn test_into_json_addressing() -> LemmyResult<()> {
+    let context = init_context().await?;
+    let (person, site) = parse_lemmy_person(&context).await?;
+    let community = parse_lemmy_community(&context).await?;
+    let json = file_to_json_object("assets/lemmy/objects/page.json")?;
+    let post = ApubPost::from_json(json, &context).await?;
+
+    let page = post.clone().into_json(&context).await?;
+    assert_eq!(vec![public()], page.to);
+    assert_eq!(vec![community.actor_id.inner().clone()], page.cc);
+
+    cleanup(&context, person, site, community, post).await?;
+    Ok(())
+  }
+
+  #[tokio::test]
+  #[serial]
+  async fn test_into_json_addressing_order() -> LemmyResult<()> {
+    let context = init_context().await?;
+    let (person, site) = parse_lemmy_person(&context).await?;
+    let community = parse_lemmy_community(&context).await?;
+    let json = file_to_json_object("assets/lemmy/objects/page.json")?;
+    let post = ApubPost::from_json(json, &context).await?;
+
+    let page = serde_json::to_value(post.clone().into_json(&context).await?)?;
+    assert_eq!(Some(&json!([public()])), page.get("to"));
+    assert_eq!(Some(&json!([community.actor_id])), page.get("cc"));
+
+    cleanup(&context, person, site, community, post).await?;
+    Ok(())
+  }
+
+  #[tokio::test]
+  #[serial]
+  async fn test_verify_origin() -> LemmyResult<()> {
+    let context = init_context().await?;
+    let (person, site) = parse_lemmy_person(&context).await?;
+    let community = parse_lemmy_community(&context).await?;
+    let json = file_to_json_object("assets/lemmy/objects/page.json")?;
+    let post = ApubPost::from_json(json, &context).await?;
+
+    let signer = Url::parse("https://enterprise.lemmy.ml/u/picard")?;
+    assert!(post.verify_origin(&signer).is_ok());
+    let signer = Url::parse("https://ds9.lemmy.ml/u/sisko")?;
+    assert!(post.verify_origin(&signer).is_err());
+
+    cleanup(&context, person, site, community, post).await?;
+    Ok(())
+  }
+
+  #[tokio::test]
+  #[serial]
+  async fn test_into_json_audience() -> LemmyResult<()> {
+    let context = init_context().await?;
+    let (person, site) = parse_lemmy_person(&context).await?;
+    let community = parse_lemmy_community(&context).await?;
+    let json = file_to_json_object("assets/lemmy/objects/page.json")?;
+    let post = ApubPost::from_json(json, &context).await?;
+
+    let page = serde_json::to_value(post.clone().into_json(&context).await?)?;
+    assert_eq!(
+      Some(community.actor_id.as_str()),
+      page.get("audience").and_then(|a| a.as_str())
+    );
+
+    cleanup(&context, person, site, community, post).await?;
+    Ok(())
+  }
+
+  #[tokio::test]
+  #[serial]
+  async fn test_read_ap_id_and_local_url() -> LemmyResult<()> {
+    let context = init_context().await?;
+    let (person, site) = parse_lemmy_person(&context).await?;
+    let community = parse_lemmy_community(&context).await?;
+    let json = file_to_json_object("assets/lemmy/objects/page.json")?;
+    let post = ApubPost::from_json(json, &context).await?;
+
+    let ap_id = ApubPost::read_ap_id(post.id, &context).await?;
+    assert_eq!(post.ap_id.inner(), &ap_id);
+
+    let local_url = post.local_url(context.settings())?;
+    assert_eq!(
+      format!("https://lemmy-alpha/post/{}", post.id),
+      local_url.as_str()
+    );
+
+    cleanup(&context, person, site, community, post).await?;
+    Ok(())
+  }
+
+  struct FixedThumbnail;
+
+  #[async_trait::async_trait]
+  impl ThumbnailGenerator for FixedThumbnail {
+    async fn generate(&self, _image_url: &Url, _context: &LemmyContext) -> Option<Thumbnail> {
+      let url = Url::parse("https://media.example.com/thumb.png").ok()?;
+      Some(Thumbnail {
+        url: url.into(),
+        blurhash: Some("LEHV6nWB2yk8pyo0adR*.7kCMdnj".to_string()),
+      })
+    }
+  }
+
+  #[tokio::test]
+  #[serial]
+  async fn test_thumbnail_generator() -> LemmyResult<()> {
+    let context =
+      init_context_with(|c| c.with_thumbnail_generator(Arc::new(FixedThumbnail))).await?;
+    let (person, site) = parse_lemmy_person(&context).await?;
+    let community = parse_lemmy_community(&context).await?;
+    let json = file_to_json_object("assets/lemmy/objects/page.json")?;
+    let post = ApubPost::from_json(json, &context).await?;
+
+    let refreshed = post.thumbnail_refresh(&context).await?;
+    assert_eq!(
+      Some("https://media.example.com/thumb.png"),
+      refreshed.thumbnail_url.as_ref().map(|u| u.as_str())
+    );
+    assert_eq!(
+      Some("LEHV6nWB2yk8pyo0adR*.7kCMdnj"),
+      refreshed.thumbnail_blurhash.as_deref()
+    );
+
+    cleanup(&context, person, site, community, refreshed).await?;
+    Ok(())
+  }
+
+  #[tokio::test]
+  #[serial]
+  async fn test_thumbnail_refresh() -> LemmyResult<()> {
+    let context = init_context().await?;
+    let (person, site) = parse_lemmy_person(&context).await?;
+    let community = parse_lemmy_community(&context).await?;
+    let json = file_to_json_object("assets/lemmy/objects/page.json")?;
+    let post = ApubPost::from_json(json, &context).await?;
+
+    let metadata: SiteMetadata = serde_json::from_value(json!({
+      "title": "New embed title",
+      "description": "New embed description"
+    }))?;
+    let thumbnail: DbUrl = Url::parse("https://example.com/pictrs/image/new.png")?.into();
+    let refreshed = post
+      .clone()
+      .update_embed(Some(metadata), Some(thumbnail.clone().into()), &context)
+      .await?;
+
+    assert_eq!(Some("New embed title".to_string()), refreshed.embed_title);
+    assert_eq!(
+      Some("New embed description".to_string()),
+      refreshed.embed_description
+    );
+    assert_eq!(Some(thumbnail), refreshed.thumbnail_url);
+    assert_eq!(post.name, refreshed.name);
+    assert_eq!(post.body, refreshed.body);
+    assert_eq!(post.url, refreshed.url);
+
+    cleanup(&context, person, site, community, refreshed).await?;
+    Ok(())
+  }
+
+  #[test]
+  fn test_render_markdown_cached() {
+    let first = render_markdown_cached("Some *markdown*", &SETTINGS);
+    let second = render_markdown_cached("Some *markdown*", &SETTINGS);
+    assert!(Arc::ptr_eq(&first, &second));
+    assert_eq!("<p>Some <em>markdown</em></p>\n", first.as_str());
+
+    let edited = render_markdown_cached("Some **markdown**", &SETTINGS);
+    assert!(!Arc::ptr_eq(&first, &edited));
+  }
+
+  #[test]
+  fn test_render_markdown_with_image_proxy() {
+    let markdown = "![cat](https://example.com/cat.png)";
+    let mut settings = SETTINGS.clone();
+    settings.federation.image_proxy_template = Some("https://images.example/?u={url}".to_string());
+    let proxied = render_markdown_cached(markdown, &settings);
+    assert!(
+      proxied.contains("src=\"https://images.example/?u=https%3A%2F%2Fexample.com%2Fcat.png\"")
+    );
+
+    let direct = render_markdown_cached(markdown, &SETTINGS);
+    assert!(direct.contains("src=\"https://example.com/cat.png\""));
+  }
+
+  #[test]
+  fn test_render_markdown_with_media_embeds() {
+    let markdown = "Cat: https://i.images.example/cat.png";
+    let mut settings = SETTINGS.clone();
+    settings.federation.media_embed_hosts = vec!["images.example".to_string()];
+    let embedded = render_markdown_cached(markdown, &settings);
+    assert!(embedded.contains("<img loading=\"lazy\" src=\"https://i.images.example/cat.png\""));
+
+    let direct = render_markdown_cached(markdown, &SETTINGS);
+    assert!(direct.contains("<a href=\"https://i.images.example/cat.png\">"));
+
+    // hosts which would be identical when concatenated have a different cache key
+    let markdown = "Cat: https://b.example/cat.png";
+    settings.federation.media_embed_hosts = vec!["a.example".to_string(), "b.example".to_string()];
+    let embedded = render_markdown_cached(markdown, &settings);
+    settings.federation.media_embed_hosts = vec!["a.exampleb.example".to_string()];
+    let linked = render_markdown_cached(markdown, &settings);
+    assert!(embedded.contains("<img"));
+    assert!(!linked.contains("<img"));
+  }
+
+  #[test]
+  fn test_render_markdown_with_html_config() {
+    let markdown = "![cat](https://example.com/cat.png)";
+    let direct = render_markdown_cached(markdown, &SETTINGS);
+    assert!(direct.contains("<img"));
+
+    let mut settings = SETTINGS.clone();
+    settings.html.forbidden_markdown_tags = vec!["img".to_string()];
+    let forbidden = render_markdown_cached(markdown, &settings);
+    assert!(!forbidden.contains("<img"));
+  }
+
+  #[test]
+  fn test_ordered_timestamps() -> LemmyResult<()> {
+    let published = Some("2024-01-01T10:00:00Z".parse()?);
+    let updated = Some("2024-01-02T10:00:00Z".parse()?);
+
+    assert_eq!(
+      (published, updated),
+      ordered_timestamps(published, updated, false)?
+    );
+    assert_eq!(
+      (published, None),
+      ordered_timestamps(published, None, false)?
+    );
+    assert_eq!(
+      Some(LemmyErrorType::PostUpdatedBeforePublished),
+      ordered_timestamps(updated, published, false)
+        .err()
+        .map(|e| e.error_type)
+    );
+    assert_eq!(
+      (published, updated),
+      ordered_timestamps(updated, published, true)?
+    );
+    Ok(())
+  }
+
+  #[test]
+  fn test_collapse_blank_lines() {
+    assert_eq!(
+      "first\n\n\nsecond\n\n\nthird",
+      collapse_blank_lines("first\n\n\n\n\n\nsecond\n \n\t\nthird \n\n\n")
+    );
+    // trailing spaces are a line break in markdown
+    assert_eq!("line  \nbreak", collapse_blank_lines("line  \nbreak"));
+  }
+
+  #[test]
+  fn test_collapse_blank_lines_keeps_code_blocks() {
+    let code = "```\nlet a = 1;   \n\n\n\n\nlet b = 2;\n```";
+    assert_eq!(
+      format!("text\n\n\n{code}\n\nmore"),
+      collapse_blank_lines(&format!("text\n\n\n\n{code}\n\nmore"))
+    );
+  }
+
+  #[test]
+  fn test_content_hash() -> LemmyResult<()> {
+    let url = Url::parse("https://news.example/Article")?;
+    let other_url = Url::parse("https://NEWS.example/article")?;
+    assert!(content_hash(Some(&url), None).is_some());
+    assert_eq!(
+      content_hash(Some(&url), Some("first body")),
+      content_hash(Some(&other_url), Some("second body"))
+    );
+    assert_eq!(
+      content_hash(None, Some("body\n")),
+      content_hash(None, Some("  body"))
+    );
+    assert_eq!(None, content_hash(None, Some(" ")));
+    Ok(())
+  }
+
+  #[test]
+  fn test_is_link_to_self() -> LemmyResult<()> {
+    let id = Url::parse("https://lemmy.example/post/1")?;
+    assert!(is_link_to_self(
+      &Url::parse("https://lemmy.example/post/1#post")?,
+      &id
+    ));
+    assert!(is_link_to_self(&id, &id));
+    assert!(!is_link_to_self(
+      &Url::parse("https://lemmy.example/post/2#post")?,
+      &id
+    ));
+    Ok(())
+  }
+
+  #[test]
+  fn test_apply_url_proxy() -> LemmyResult<()> {
+    let url = Url::parse("https://example.com/article?id=1&lang=en")?;
+    let proxied = apply_url_proxy(&url, "https://proxy.example/?u={url}")?;
+    assert_eq!(
+      "https://proxy.example/?u=https%3A%2F%2Fexample.com%2Farticle%3Fid%3D1%26lang%3Den",
+      proxied.as_str()
+    );
+    Ok(())
+  }
+
+  #[test]
+  fn test_verify_post_hosts_related() -> LemmyResult<()> {
+    let local = "lemmy-alpha";
+    let local_community = Url::parse("https://lemmy-alpha/c/main")?;
+    let local_author = Url::parse("https://lemmy-alpha/u/alice")?;
+    let remote_community = Url::parse("https://lemmy-beta/c/main")?;
+    let remote_author = Url::parse("https://lemmy-gamma/u/bob")?;
+
+    verify_post_hosts_related(&remote_author, &local_community, local, &[])?;
+    verify_post_hosts_related(&local_author, &remote_community, local, &[])?;
+    assert!(verify_post_hosts_related(&remote_author, &remote_community, local, &[]).is_err());
+
+    let allowed = vec!["lemmy-beta".to_string(), "lemmy-gamma".to_string()];
+    verify_post_hosts_related(&remote_author, &remote_community, local, &allowed)?;
+    Ok(())
+  }
+
+  #[tokio::test]
+  #[serial]
+  async fn test_amp_link_dedups_against_canonical() -> LemmyResult<()> {
+    let context = init_context().await?;
+    let (person, site) = parse_lemmy_person(&context).await?;
+    let community = parse_lemmy_community(&context).await?;
+
+    let amp_url = Url::parse("https://news.example/amp/article")?;
+    let canonical_url = Url::parse("https://news.example/article")?;
+    let mut page: Page = file_to_json_object("assets/lemmy/objects/page.json")?;
+    page.attachment = vec![Attachment::new(amp_url.clone().into())];
+    let amp_post = ApubPost::from_json(page.clone(), &context).await?;
+    assert_eq!(
+      content_hash(Some(&amp_url), None),
+      amp_post.content_hash.clone()
+    );
+
+    // simulate the canonical link from fetched metadata, it is kept when the post is updated
+    let form = PostUpdateForm {
+      canonical_url: Some(Some(canonical_url.clone().into())),
+      ..Default::default()
+    };
+    Post::update(&mut context.pool(), amp_post.id, &form).await?;
+    let amp_post = ApubPost::from_json(page, &context).await?;
+    assert_eq!(Some(canonical_url.clone().into()), amp_post.canonical_url);
+
+    let mut page: Page = file_to_json_object("assets/lemmy/objects/page.json")?;
+    page.id = ObjectId::parse("https://enterprise.lemmy.ml/post/55144")?;
+    page.attachment = vec![Attachment::new(canonical_url.into())];
+    let canonical_post = ApubPost::from_json(page, &context).await?;
+    assert!(amp_post.content_hash.is_some());
+    assert_eq!(amp_post.content_hash, canonical_post.content_hash);
+
+    Post::delete(&mut context.pool(), canonical_post.id).await?;
+    cleanup(&context, person, site, community, amp_post).await?;
+    Ok(())
+  }
+
+  #[tokio::test]
+  #[serial]
+  async fn test_read_summary_from_apub_id() -> LemmyResult<()> {
+    let context = init_context().await?;
+    let (person, site) = parse_lemmy_person(&context).await?;
+    let community = parse_lemmy_community(&context).await?;
+    let json = file_to_json_object("assets/lemmy/objects/page.json")?;
+    let post = ApubPost::from_json(json, &context).await?;
+    assert!(post.body.is_some());
+
+    let summary = ApubPost::read_summary_from_apub_id(post.ap_id.clone().into(), &context)
+      .await?
+      .ok_or(LemmyErrorType::CouldntFindPost)?;
+    assert_eq!(post.id, summary.id);
+    assert_eq!(post.name, summary.name);
+    assert_eq!(post.thumbnail_url, summary.thumbnail_url);
+    let json = serde_json::to_value(&summary.0)?;
+    assert!(json.get("name").is_some());
+    assert!(json.get("body").is_none());
+
+    let missing = Url::parse("https://enterprise.lemmy.ml/post/0")?;
+    assert!(ApubPost::read_summary_from_apub_id(missing, &context)
+      .await?
+      .is_none());
+
+    cleanup(&context, person, site, community, post).await?;
+    Ok(())
+  }
+
+  #[tokio::test]
+  #[serial]
+  async fn test_resolve_mention_links() -> LemmyResult<()> {
+    let context = init_context().await?;
+    let (person, site) = parse_lemmy_person(&context).await?;
+    let community = parse_lemmy_community(&context).await?;
+
+    let mut page: Page = file_to_json_object("assets/lemmy/objects/page.json")?;
+    page.source = None;
+    page.content = Some(
+      r#"<p>Posted in <a href="https://enterprise.lemmy.ml/c/tenforward">!tenforward@enterprise.lemmy.ml</a>, thanks <a href="https://ds9.lemmy.ml/u/sisko">@sisko@ds9.lemmy.ml</a></p>"#
+        .to_string(),
+    );
+    let post = ApubPost::from_json(page, &context).await?;
+
+    assert_eq!(
+      Some("Posted in !tenforward@enterprise.lemmy.ml, thanks [@sisko@ds9.lemmy.ml](https://ds9.lemmy.ml/u/sisko)"),
+      post.body.as_deref()
+    );
+
+    cleanup(&context, person, site, community, post).await?;
+    Ok(())
+  }
+
+  #[tokio::test]
+  #[serial]
+  async fn test_tombstone_serializes_ap_id() -> LemmyResult<()> {
+    let context = init_context().await?;
+    let (person, site) = parse_lemmy_person(&context).await?;
+    let community = parse_lemmy_community(&context).await?;
+    let json = file_to_json_object("assets/lemmy/objects/page.json")?;
+    let post = ApubPost::from_json(json, &context).await?;
+    assert_ne!(post.url, Some(post.ap_id.clone()));
+
+    let deleted = ApubPost(Post {
+      deleted: true,
+      ..post.0.clone()
+    });
+    let tombstone = serde_json::to_value(deleted.tombstone_if_deleted())?;
+    assert_eq!(json!({"id": post.ap_id, "type": "Tombstone"}), tombstone);
+
+    cleanup(&context, person, site, community, post).await?;
+    Ok(())
+  }
+
+  #[tokio::test]
+  #[serial]
+  async fn test_tombstone_if_deleted() -> LemmyResult<()> {
+    let context = init_context().await?;
+    let (person, site) = parse_lemmy_person(&context).await?;
+    let community = parse_lemmy_community(&context).await?;
+    let json = file_to_json_object("assets/lemmy/objects/page.json")?;
+    let post = ApubPost::from_json(json, &context).await?;
+
+    assert!(post.tombstone_if_deleted().is_none());
+    let deleted = ApubPost(Post {
+      deleted: true,
+      ..post.0.clone()
+    });
+    let tombstone = deleted.tombstone_if_deleted();
+    assert_eq!(Some(post.ap_id.inner()), tombstone.as_ref().map(|t| &t.id));
+    let removed = ApubPost(Post {
+      removed: true,
+      ..post.0.clone()
+    });
+    assert!(removed.tombstone_if_deleted().is_some());
+
+    cleanup(&context, person, site, community, post).await?;
+    Ok(())
+  }
+
+  #[tokio::test]
+  #[serial]
+  async fn test_reverify_stored_post() -> LemmyResult<()> {
+    let context = init_context().await?;
+    let (person, site) = parse_lemmy_person(&context).await?;
+    let community = parse_lemmy_community(&context).await?;
+
+    let json = file_to_json_object("assets/lemmy/objects/page.json")?;
+    let post = ApubPost::from_json(json, &context).await?;
+    assert!(reverify_stored_post(post.id, &context).await?.is_none());
+
+    // the body only violates a slur rule which was added after the post was received
+    let page = post.clone().into_json(&context).await?;
+    reverify_page(&page, None, &context).await?;
+    let res = reverify_page(&page, Some("tenforward"), &context).await;
+    assert_eq!(Some(LemmyErrorType::Slurs), res.err().map(|e| e.error_type));
+
+    cleanup(&context, person, site, community, post).await?;
+    Ok(())
+  }
+
+  #[tokio::test]
+  #[serial]
+  async fn test_replies_seed_comment_count() -> LemmyResult<()> {
+    let context = init_context().await?;
+    let (person, site) = parse_lemmy_person(&context).await?;
+    let community = parse_lemmy_community(&context).await?;
+
+    let mut json: serde_json::Value = file_to_json_object("assets/lemmy/objects/page.json")?;
+    if let Some(obj) = json.as_object_mut() {
+      obj.insert(
+        "replies".to_string(),
+        json!({"type": "Collection", "totalItems": 7}),
+      );
+    }
+    let page: Page = serde_json::from_value(json)?;
+    let post = ApubPost::from_json(page, &context).await?;
+    let aggregates = PostAggregates::read(&mut context.pool(), post.id).await?;
+    assert_eq!(7, aggregates.comments);
+
+    // replies which are only a link are ignored
+    let mut json: serde_json::Value = file_to_json_object("assets/lemmy/objects/page.json")?;
+    if let Some(obj) = json.as_object_mut() {
+      obj.insert(
+        "replies".to_string(),
+        json!("https://enterprise.lemmy.ml/post/55143/replies"),
+      );
+    }
+    let page: Page = serde_json::from_value(json)?;
+    assert!(page.replies.is_none());
+    ApubPost::from_json(page, &context).await?;
+    let aggregates = PostAggregates::read(&mut context.pool(), post.id).await?;
+    assert_eq!(7, aggregates.comments);
+
+    cleanup(&context, person, site, community, post).await?;
+    Ok(())
+  }
+
+  #[tokio::test]
+  #[serial]
+  async fn test_verify_relayed_post() -> LemmyResult<()> {
+    let context = init_context().await?;
+    let (person, site) = parse_lemmy_person(&context).await?;
+    let community = parse_lemmy_community(&context).await?;
+
+    // author on a third instance, different from both the relay and the community
+    let mut json: serde_json::Value = file_to_json_object("assets/lemmy/objects/person.json")?;
+    let author_id = "https://ds9.lemmy.ml/u/sisko";
+    if let Some(obj) = json.as_object_mut() {
+      obj.insert("id".to_string(), json!(author_id));
+      obj.insert("preferredUsername".to_string(), json!("sisko"));
+    }
+    let json: crate::protocol::objects::person::Person = serde_json::from_value(json)?;
+    let author_url = Url::parse(author_id)?;
+    ApubPerson::verify(&json, &author_url, &context).await?;
+    let author = ApubPerson::from_json(json, &context).await?;
+
+    let mut page: Page = file_to_json_object("assets/lemmy/objects/page.json")?;
+    page.id = ObjectId::parse("https://ds9.lemmy.ml/post/1")?;
+    page.attributed_to = AttributedTo::Lemmy(author_url.into());
+    let relay = Url::parse("https://relay.example/inbox")?;
+    assert!(is_relay(&relay, &["Relay.Example".to_string()]));
+    assert!(!is_relay(&relay, &["other.example".to_string()]));
+
+    assert!(
+      verify_page(&page, &relay, &FETCHED_PAGE_TYPES, false, &context)
+        .await
+        .is_err()
+    );
+    verify_page(&page, &relay, &FETCHED_PAGE_TYPES, true, &context).await?;
+
+    // the relay is the actor of the activity which delivers the post
+    let mut json: serde_json::Value = file_to_json_object("assets/lemmy/objects/person.json")?;
+    let relay_id = "https://relay.example/u/relay";
+    if let Some(obj) = json.as_object_mut() {
+      obj.insert("id".to_string(), json!(relay_id));
+      obj.insert("preferredUsername".to_string(), json!("relay"));
+    }
+    let json: crate::protocol::objects::person::Person = serde_json::from_value(json)?;
+    let relay_url = Url::parse(relay_id)?;
+    ApubPerson::verify(&json, &relay_url, &context).await?;
+    let relay_actor = ApubPerson::from_json(json, &context).await?;
+    let create = CreateOrUpdatePage::from_page(
+      page.clone(),
+      relay_url.into(),
+      &community,
+      CreateOrUpdateType::Create,
+      &context,
+    )?;
+    // the relay isn't configured, so the post must be on its domain
+    assert!(create.verify(&context).await.is_err());
+    create.verify_with_relay(true, &context).await?;
+
+    // the author must still be on the same domain as the post
+    let mut spoofed = create.clone();
+    spoofed.object.id = ObjectId::parse("https://relay.example/post/1")?;
+    assert!(spoofed.verify_with_relay(true, &context).await.is_err());
+
+    let post = ApubPost::from_json(page, &context).await?;
+    assert_eq!(post.creator_id, author.id);
+    assert_eq!(post.community_id, community.id);
+
+    Person::delete(&mut context.pool(), author.id).await?;
+    Person::delete(&mut context.pool(), relay_actor.id).await?;
+    cleanup(&context, person, site, community, post).await?;
+    Ok(())
+  }
+
+  #[tokio::test]
+  #[serial]
+  async fn test_convert_mastodon_post_title() -> LemmyResult<()> {
+    let context = init_context().await?;
+    let (person, site) = parse_lemmy_person(&context).await?;
+    let community = parse_lemmy_community(&context).await?;
+
+    let json = file_to_json_object("assets/mastodon/objects/page.json")?;
+    let post = ApubPost::from_json(json, &context).await?;
+
+    assert_eq!(post.name, "Variable never resetting at refresh");
+
+    cleanup(&context, person, site, community, post).await?;
+    Ok(())
+  }
+
+  #[tokio::test]
+  #[serial]
+  async fn test_import_requests() -> LemmyResult<()> {
+    let context = init_context().await?;
+    // fetches are counted even though they fail, as network access is blocked in tests
+    let fetch =
+      |path: &str| ObjectId::<ApubPerson>::parse(&format!("https://unreachable.example/{path}"));
+
+    let mut requests = ImportRequests::new(&context);
+    assert!(fetch("u/author")?.dereference(&context).await.is_err());
+    requests.author = requests.since_last(&context);
+    assert!(fetch("c/first")?.dereference(&context).await.is_err());
+    assert!(fetch("c/second")?.dereference(&context).await.is_err());
+    requests.community = requests.since_last(&context);
+    requests.nested = requests.since_last(&context);
+
+    assert_eq!(1, requests.author);
+    assert_eq!(2, requests.community);
+    assert_eq!(0, requests.nested);
+    assert_eq!(3, requests.total());
+    assert_eq!(context.request_count(), requests.last_count);
+    Ok(())
+  }
+
+  /// Number of table scans needed for [PageParts::read]. With index scans disabled, each query on a
+  /// single table is one sequential scan. The scan counters of the connection also include earlier
+  /// transactions which weren't reported yet, so the difference is taken.
+  async fn count_page_parts_queries(
+    posts: &[ApubPost],
+    settings: &Settings,
+    context: &Data<LemmyContext>,
+  ) -> LemmyResult<i64> {
+    use diesel::{dsl::sql, select, sql_types::BigInt};
+    use diesel_async::{RunQueryDsl, SimpleAsyncConnection};
+    let scans = || {
+      select(sql::<BigInt>(
+        "(SELECT coalesce(sum(seq_scan + idx_scan), 0)::bigint FROM pg_stat_xact_user_tables)",
+      ))
+    };
+    let mut pool = context.pool();
+    let conn = &mut get_conn(&mut pool).await?;
+    conn
+      .batch_execute(
+        "BEGIN; SET LOCAL enable_indexscan = off; SET LOCAL enable_bitmapscan = off; \
+         SET LOCAL enable_indexonlyscan = off",
+      )
+      .await?;
+    let before = scans().get_result::<i64>(conn).await?;
+    PageParts::read(posts, settings, &mut conn.into()).await?;
+    let after = scans().get_result::<i64>(conn).await?;
+    conn.batch_execute("ROLLBACK").await?;
+    Ok(after - before)
+  }
+
+  #[tokio::test]
+  #[serial]
+  async fn test_into_json_batch() -> LemmyResult<()> {
+    let context = init_context().await?;
+    let (person, site) = parse_lemmy_person(&context).await?;
+    let community = parse_lemmy_community(&context).await?;
+
+    let mut posts = vec![];
+    for i in 1..=3 {
+      let mut page: Page = file_to_json_object("assets/lemmy/objects/page.json")?;
+      page.id = ObjectId::parse(&format!("https://enterprise.lemmy.ml/post/{i}"))?;
+      posts.push(ApubPost::from_json(page, &context).await?);
+    }
+
+    // creator and community are shared, so they are only read once for all posts
+    let mut expected = vec![];
+    for post in &posts {
+      expected.push(serde_json::to_value(
+        post.clone().into_json(&context).await?,
+      )?);
+    }
+    let batch = ApubPost::into_json_batch(posts.clone(), &context).await?;
+    let batch = batch
+      .into_iter()
+      .map(serde_json::to_value)
+      .collect::<Result<Vec<_>, _>>()?;
+    assert_eq!(expected, batch);
+    assert!(ApubPost::into_json_batch(vec![], &context)
+      .await?
+      .is_empty());
+
+    // the number of queries doesn't depend on the number of posts
+    let mut settings = SETTINGS.clone();
+    settings.federation.federate_translations = true;
+    let first = posts.first().ok_or(LemmyErrorType::CouldntFindPost)?;
+    let first = std::slice::from_ref(first);
+    assert_eq!(
+      5,
+      count_page_parts_queries(first, &settings, &context).await?
+    );
+    assert_eq!(
+      5,
+      count_page_parts_queries(&posts, &settings, &context).await?
+    );
+    settings.federation.federate_translations = false;
+    assert_eq!(
+      4,
+      count_page_parts_queries(&posts, &settings, &context).await?
+    );
+
+    let post = posts.remove(0);
+    for p in posts {
+      Post::delete(&mut context.pool(), p.id).await?;
+    }
+    cleanup(&context, person, site, community, post).await?;
+    Ok(())
+  }
+
+  #[test]
+  fn test_replace_generic_name() -> LemmyResult<()> {
+    let generic = RegexBuilder::new("^(?:link|untitled)$")
+      .case_insensitive(true)
+      .build()?;
+    let title = Some("A real article title");
+    let replace =
+      |name: &str, title, generic| replace_generic_name(name.to_string(), title, generic, &None);
+    assert_eq!(
+      "A real article title",
+      replace("Link", title, Some(&generic))
+    );
+    assert_eq!(
+      "A real article title",
+      replace(" untitled ", title, Some(&generic))
+    );
+    // only the whole name is matched
+    assert_eq!(
+      "Link to article",
+      replace("Link to article", title, Some(&generic))
+    );
+    assert_eq!("Link", replace("Link", None, Some(&generic)));
+    assert_eq!("Link", replace("Link", Some(" "), Some(&generic)));
+    // disabled without a pattern
+    assert_eq!("Link", replace("Link", title, None));
+    Ok(())
+  }
+
+  #[test]
+  fn test_reading_time() {
+    assert_eq!((0, 0), reading_time(""));
+    assert_eq!(
+      (3, 1),
+      reading_time("Some *short* post.\n\n    indented code")
+    );
+    let long = "word ".repeat(401);
+    assert_eq!((401, 3), reading_time(&long));
+  }
+
+  #[tokio::test]
+  #[serial]
+  async fn test_purge_deleted_remote_posts() -> LemmyResult<()> {
+    let context = init_context().await?;
+    let (person, site) = parse_lemmy_person(&context).await?;
+    let community = parse_lemmy_community(&context).await?;
+
+    let mut page: Page = file_to_json_object("assets/lemmy/objects/page.json")?;
+    let recent = ApubPost::from_json(page.clone(), &context).await?;
+    page.id = ObjectId::parse("https://enterprise.lemmy.ml/post/55144")?;
+    let old = ApubPost::from_json(page, &context).await?;
+    for (post, deleted_at) in [
+      (&recent, Utc::now() - Duration::days(1)),
+      (&old, Utc::now() - Duration::days(31)),
+    ] {
+      let form = PostUpdateForm {
+        deleted: Some(true),
+        updated: Some(Some(deleted_at)),
+        ..Default::default()
+      };
+      Post::update(&mut context.pool(), post.id, &form).await?;
+    }
+
+    let purged = purge_deleted_remote_posts(&mut context.pool(), 30).await?;
+    assert_eq!(1, purged);
+    assert!(Post::read(&mut context.pool(), old.id).await.is_err());
+    assert!(Post::read(&mut context.pool(), recent.id).await.is_ok());
+
+    // nothing left to purge when running again
+    assert_eq!(
+      0,
+      purge_deleted_remote_posts(&mut context.pool(), 30).await?
+    );
+
+    for retention_days in [0, -1, i64::MAX] {
+      let res = purge_deleted_remote_posts(&mut context.pool(), retention_days).await;
+      assert_eq!(
+        Some(LemmyErrorType::InvalidRetentionPeriod),
+        res.err().map(|e| e.error_type)
+      );
+    }
+    assert!(Post::read(&mut context.pool(), recent.id).await.is_ok());
+
+    cleanup(&context, person, site, community, recent).await?;
+    Ok(())
+  }
+
+  #[tokio::test]
+  #[serial]
+  async fn test_resolve_post() -> LemmyResult<()> {
+    let responses = HashMap::from([(
+      "https://gone.example/post/1".to_string(),
+      (StatusCode::GONE, String::new()),
+    )]);
+    let context = init_context_with_status_responses(responses).await?;
+    let (person, site) = parse_lemmy_person(&context).await?;
+    let community = parse_lemmy_community(&context).await?;
+
+    let page: Page = file_to_json_object("assets/lemmy/objects/page.json")?;
+    let id = page.id.clone();
+    assert!(ApubPost::read_local(&id, &context).await?.is_none());
+    let post = ApubPost::from_json(page, &context).await?;
+    let read = ApubPost::read_local(&id, &context).await?;
+    assert_eq!(Some(post.id), read.map(|p| p.id));
+
+    // local posts are never fetched, so a missing one is cleanly not found
+    let missing = ObjectId::parse("https://example.com/post/999999")?;
+    assert!(ApubPost::resolve(&missing, &context).await?.is_none());
+
+    // the remote instance answers that the post was deleted, which is also not found
+    let gone = ObjectId::parse("https://gone.example/post/1")?;
+    assert!(ApubPost::resolve(&gone, &context).await?.is_none());
+
+    // a network error is a real failure, network access to other urls is blocked in tests
+    let remote = ObjectId::parse("https://unreachable.example/post/1")?;
+    let err = ApubPost::resolve(&remote, &context).await.err();
+    assert!(err.is_some_and(|e| !is_not_found(&e)));
 
     cleanup(&context, person, site, community, post).await?;
     Ok(())
@@ -1,8 +1,11 @@
 use crate::{
   check_apub_id_valid_with_strictness,
-  objects::read_from_string_or_source,
+  objects::{markdown_to_html_outgoing, read_from_string_or_source, TombstoneIfDeleted},
   protocol::{
-    objects::chat_message::{ChatMessage, ChatMessageType},
+    objects::{
+      chat_message::{ChatMessage, ChatMessageType},
+      tombstone::Tombstone,
+    },
     Source,
   },
 };
@@ -20,10 +23,7 @@ use lemmy_db_schema::{
   },
   traits::Crud,
 };
-use lemmy_utils::{
-  error::{LemmyError, LemmyErrorType},
-  utils::markdown::markdown_to_html,
-};
+use lemmy_utils::error::{LemmyError, LemmyErrorType};
 use std::ops::Deref;
 use url::Url;
 
@@ -83,7 +83,7 @@ impl Object for ApubPrivateMessage {
       id: self.ap_id.clone().into(),
       attributed_to: creator.actor_id.into(),
       to: [recipient.actor_id.into()],
-      content: markdown_to_html(&self.content),
+      content: markdown_to_html_outgoing(&self.content, context.settings()),
       media_type: Some(MediaTypeHtml::Html),
       source: Some(Source::new(self.content.clone())),
       published: Some(self.published),
@@ -139,6 +139,10 @@ impl Object for ApubPrivateMessage {
   }
 }
 
+impl TombstoneIfDeleted for ApubPrivateMessage {
+  type TombstoneType = Tombstone;
+}
+
 #[cfg(test)]
 mod tests {
   use super::*;
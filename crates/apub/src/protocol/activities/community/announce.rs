@@ -31,3 +31,16 @@ pub struct RawAnnouncableActivities {
   #[serde(flatten)]
   pub(crate) other: Map<String, Value>,
 }
+
+impl RawAnnouncableActivities {
+  /// Whether this creates or updates a post.
+  pub(crate) fn is_create_or_update_page(&self) -> bool {
+    let kind = self.other.get("type").and_then(Value::as_str);
+    let object_kind = self
+      .other
+      .get("object")
+      .and_then(|o| o.get("type"))
+      .and_then(Value::as_str);
+    matches!(kind, Some("Create" | "Update")) && object_kind == Some("Page")
+  }
+}
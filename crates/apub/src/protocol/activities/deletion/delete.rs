@@ -6,7 +6,6 @@ use crate::{
 use activitypub_federation::{
   config::Data,
   fetch::object_id::ObjectId,
-  kinds::activity::DeleteType,
   protocol::helpers::deserialize_one_or_many,
 };
 use anyhow::anyhow;
@@ -18,8 +17,25 @@ use lemmy_db_schema::{
 use lemmy_utils::error::LemmyError;
 use serde::{Deserialize, Serialize};
 use serde_with::skip_serializing_none;
+use std::fmt::{Display, Formatter};
 use url::Url;
 
+/// `Delete` if a user deletes their own content, `Remove` if a mod removes it.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub enum DeletionType {
+  Delete,
+  Remove,
+}
+
+impl Display for DeletionType {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match self {
+      DeletionType::Delete => f.write_str("Delete"),
+      DeletionType::Remove => f.write_str("Remove"),
+    }
+  }
+}
+
 #[skip_serializing_none]
 #[derive(Clone, Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
@@ -29,7 +45,7 @@ pub struct Delete {
   pub(crate) to: Vec<Url>,
   pub(crate) object: IdOrNestedObject<Tombstone>,
   #[serde(rename = "type")]
-  pub(crate) kind: DeleteType,
+  pub(crate) kind: DeletionType,
   pub(crate) id: Url,
   pub(crate) audience: Option<ObjectId<ApubCommunity>>,
 
@@ -37,14 +53,21 @@ pub struct Delete {
   #[serde(default)]
   #[serde(skip_serializing_if = "Vec::is_empty")]
   pub(crate) cc: Vec<Url>,
-  /// If summary is present, this is a mod action (Remove in Lemmy terms). Otherwise, its a user
-  /// deleting their own content.
+  /// Reason for a mod removal. Older Lemmy versions send removals as `Delete` with summary set
+  /// (to an empty string if there is no reason), so this also marks a mod action.
   pub(crate) summary: Option<String>,
   /// Nonstandard field, only valid if object refers to a Person. If present, all content from the
   /// user should be deleted along with the account
   pub(crate) remove_data: Option<bool>,
 }
 
+impl Delete {
+  /// Removal by a mod, as opposed to a user deleting their own content.
+  pub(crate) fn is_mod_action(&self) -> bool {
+    self.kind == DeletionType::Remove || self.summary.is_some()
+  }
+}
+
 #[async_trait::async_trait]
 impl InCommunity for Delete {
   async fn community(&self, context: &Data<LemmyContext>) -> Result<ApubCommunity, LemmyError> {
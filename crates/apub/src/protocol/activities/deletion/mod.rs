@@ -14,6 +14,7 @@ mod tests {
   fn test_parse_lemmy_deletion() -> LemmyResult<()> {
     test_parse_lemmy_item::<Delete>("assets/lemmy/activities/deletion/remove_note.json")?;
     test_parse_lemmy_item::<Delete>("assets/lemmy/activities/deletion/delete_page.json")?;
+    test_parse_lemmy_item::<Delete>("assets/lemmy/activities/deletion/remove_page.json")?;
 
     test_parse_lemmy_item::<UndoDelete>("assets/lemmy/activities/deletion/undo_remove_note.json")?;
     test_parse_lemmy_item::<UndoDelete>("assets/lemmy/activities/deletion/undo_delete_page.json")?;
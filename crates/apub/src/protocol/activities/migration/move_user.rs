@@ -0,0 +1,17 @@
+use crate::objects::person::ApubPerson;
+use activitypub_federation::{fetch::object_id::ObjectId, kinds::activity::MoveType};
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+/// Sent by a user who migrated their account to another instance. Both `actor` and `object` are
+/// the old account, `target` is the new one.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MoveUser {
+  pub(crate) actor: ObjectId<ApubPerson>,
+  pub(crate) object: ObjectId<ApubPerson>,
+  pub(crate) target: ObjectId<ApubPerson>,
+  #[serde(rename = "type")]
+  pub(crate) kind: MoveType,
+  pub(crate) id: Url,
+}
@@ -6,6 +6,7 @@ pub mod community;
 pub mod create_or_update;
 pub mod deletion;
 pub mod following;
+pub mod migration;
 pub mod voting;
 
 #[derive(Clone, Debug, Display, Deserialize, Serialize, PartialEq, Eq)]
@@ -22,6 +23,7 @@ mod tests {
       create_or_update::{note::CreateOrUpdateNote, page::CreateOrUpdatePage},
       deletion::delete::Delete,
       following::{accept::AcceptFollow, follow::Follow, undo_follow::UndoFollow},
+      migration::move_user::MoveUser,
       voting::{undo_vote::UndoVote, vote::Vote},
     },
     tests::test_json,
@@ -51,6 +53,7 @@ mod tests {
     test_json::<Vote>("assets/mastodon/activities/like_page.json")?;
     test_json::<UndoVote>("assets/mastodon/activities/undo_like_page.json")?;
     test_json::<Report>("assets/mastodon/activities/flag.json")?;
+    test_json::<MoveUser>("assets/mastodon/activities/move.json")?;
     Ok(())
   }
 
@@ -0,0 +1,140 @@
+use serde_json::{Map, Value};
+
+/// Namespaces whose terms are used without prefix in the compact form of our context.
+const NAMESPACES: [&str; 4] = [
+  "https://www.w3.org/ns/activitystreams#",
+  "https://join-lemmy.org/ns#",
+  "https://joinpeertube.org/ns#",
+  "http://schema.org/",
+];
+
+/// Properties which are always arrays in the compact form, even with a single value.
+const ARRAY_PROPERTIES: [&str; 2] = ["attachment", "tag"];
+
+/// Converts a document in expanded JSON-LD form, where properties are full IRIs and all values
+/// are arrays, to the compact form which our structs expect. This only covers the namespaces of
+/// our own context and doesn't fetch remote contexts. Documents which are already compact are
+/// returned unchanged.
+pub(crate) fn compact_expanded(value: Value) -> Value {
+  if is_expanded(&value) {
+    compact_value(value, false)
+  } else {
+    value
+  }
+}
+
+fn is_expanded(value: &Value) -> bool {
+  match value {
+    Value::Array(a) => a.len() == 1 && a.iter().all(is_expanded),
+    Value::Object(o) => {
+      o.contains_key("@type")
+        || o
+          .keys()
+          .any(|k| NAMESPACES.iter().any(|n| k.starts_with(n)))
+    }
+    _ => false,
+  }
+}
+
+fn compact_value(value: Value, keep_array: bool) -> Value {
+  match value {
+    Value::Array(a) if a.len() == 1 && !keep_array => a
+      .into_iter()
+      .next()
+      .map(|v| compact_value(v, false))
+      .unwrap_or(Value::Null),
+    Value::Array(a) => Value::Array(a.into_iter().map(|v| compact_value(v, false)).collect()),
+    Value::Object(mut o) => {
+      if let Some(v) = o.remove("@value") {
+        return v;
+      }
+      if let Some(list) = o.remove("@list") {
+        return compact_value(list, true);
+      }
+      if o.len() == 1 {
+        if let Some(id) = o.remove("@id") {
+          return id;
+        }
+      }
+      let mut compact = Map::new();
+      for (key, value) in o {
+        let (key, value) = match key.as_str() {
+          "@id" => ("id".to_string(), value),
+          "@type" => ("type".to_string(), compact_type(value)),
+          _ => {
+            let term = compact_term(&key);
+            let keep_array = ARRAY_PROPERTIES.contains(&term.as_str());
+            (term, compact_value(value, keep_array))
+          }
+        };
+        compact.insert(key, value);
+      }
+      Value::Object(compact)
+    }
+    v => v,
+  }
+}
+
+fn compact_type(value: Value) -> Value {
+  match value {
+    Value::String(s) => Value::String(compact_term(&s)),
+    Value::Array(a) if a.len() == 1 => a
+      .into_iter()
+      .next()
+      .map(compact_type)
+      .unwrap_or(Value::Null),
+    Value::Array(a) => Value::Array(a.into_iter().map(compact_type).collect()),
+    v => v,
+  }
+}
+
+fn compact_term(iri: &str) -> String {
+  NAMESPACES
+    .iter()
+    .find_map(|n| iri.strip_prefix(n))
+    .unwrap_or(iri)
+    .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{
+    fetcher::post_or_comment::PageOrNote,
+    protocol::{objects::page::Page, tests::file_to_json_object},
+  };
+  use lemmy_utils::error::LemmyResult;
+  use pretty_assertions::assert_eq;
+
+  #[test]
+  fn test_parse_expanded_page() -> LemmyResult<()> {
+    let expanded: Value = file_to_json_object("assets/json_ld/objects/page_expanded.json")?;
+    let compact: Value = file_to_json_object("assets/lemmy/objects/page.json")?;
+    // the page deserializer compacts by itself
+    let page: Page = serde_json::from_value(expanded.clone())?;
+    let expected: Page = serde_json::from_value(compact.clone())?;
+
+    assert_eq!(expected.id, page.id);
+    assert_eq!(expected.to, page.to);
+    assert_eq!(expected.cc, page.cc);
+    assert_eq!(expected.name, page.name);
+    assert_eq!(expected.content, page.content);
+    assert_eq!(expected.published, page.published);
+    assert_eq!(Some(true), page.comments_enabled);
+    assert_eq!(Some(false), page.sensitive);
+    assert_eq!(Some("fr".to_string()), page.in_language);
+    assert_eq!(1, page.attachment.len());
+    assert!(page.image.is_some());
+    assert!(page.source.is_some());
+
+    // fetched objects are compacted before selecting the parser by type
+    assert!(matches!(
+      PageOrNote::try_from(expanded)?,
+      PageOrNote::Page(_)
+    ));
+
+    // compact documents are left alone
+    assert_eq!(compact, compact_expanded(compact.clone()));
+    Ok(())
+  }
+}
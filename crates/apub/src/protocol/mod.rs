@@ -3,7 +3,7 @@ use activitypub_federation::{
   config::Data,
   fetch::fetch_object_http,
   kinds::object::ImageType,
-  protocol::values::MediaTypeMarkdown,
+  protocol::{helpers::deserialize_skip_error, values::MediaTypeMarkdownOrHtml},
 };
 use lemmy_api_common::context::LemmyContext;
 use lemmy_db_schema::newtypes::DbUrl;
@@ -14,20 +14,22 @@ use url::Url;
 
 pub mod activities;
 pub(crate) mod collections;
+pub(crate) mod json_ld;
 pub(crate) mod objects;
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Source {
   pub(crate) content: String,
-  pub(crate) media_type: MediaTypeMarkdown,
+  /// Usually markdown, but some platforms send their html source here
+  pub(crate) media_type: MediaTypeMarkdownOrHtml,
 }
 
 impl Source {
   pub(crate) fn new(content: String) -> Self {
     Source {
       content,
-      media_type: MediaTypeMarkdown::Markdown,
+      media_type: MediaTypeMarkdownOrHtml::Markdown,
     }
   }
 }
@@ -38,6 +40,13 @@ pub struct ImageObject {
   #[serde(rename = "type")]
   kind: ImageType,
   pub(crate) url: Url,
+  /// Alt text of the image
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub(crate) name: Option<String>,
+  /// Blurred placeholder of the image, as used by Mastodon
+  #[serde(deserialize_with = "deserialize_skip_error", default)]
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub(crate) blurhash: Option<String>,
 }
 
 impl ImageObject {
@@ -45,8 +54,20 @@ impl ImageObject {
     ImageObject {
       kind: ImageType::Image,
       url: url.into(),
+      name: None,
+      blurhash: None,
     }
   }
+
+  pub(crate) fn with_name(mut self, name: Option<String>) -> Self {
+    self.name = name;
+    self
+  }
+
+  pub(crate) fn with_blurhash(mut self, blurhash: Option<String>) -> Self {
+    self.blurhash = blurhash;
+    self
+  }
 }
 
 #[derive(Clone, Debug, Default, Deserialize, Serialize)]
@@ -93,6 +114,7 @@ pub(crate) mod tests {
   use assert_json_diff::assert_json_include;
   use lemmy_utils::error::LemmyError;
   use serde::{de::DeserializeOwned, Serialize};
+  use serde_json::Value;
   use std::{collections::HashMap, fs::File, io::BufReader};
 
   pub(crate) fn file_to_json_object<T: DeserializeOwned>(path: &str) -> Result<T, LemmyError> {
@@ -119,4 +141,91 @@ pub(crate) mod tests {
     assert_json_include!(actual: &parsed, expected: raw);
     Ok(parsed)
   }
+
+  /// Check that the serialized item matches a JSON schema from `assets/schema`, to catch
+  /// accidental changes in sent data. Only `type`, `enum`, `required`, `properties` and `items`
+  /// are supported.
+  pub(crate) fn assert_matches_schema<T: Serialize>(
+    item: &T,
+    schema_path: &str,
+  ) -> Result<(), LemmyError> {
+    let schema: Value = file_to_json_object(schema_path)?;
+    let mut errors = vec![];
+    schema_errors(&serde_json::to_value(item)?, &schema, "$", &mut errors);
+    assert!(errors.is_empty(), "{schema_path}: {errors:#?}");
+    Ok(())
+  }
+
+  fn schema_errors(value: &Value, schema: &Value, path: &str, errors: &mut Vec<String>) {
+    if let Some(kind) = schema.get("type").and_then(Value::as_str) {
+      let matches = match kind {
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "string" => value.is_string(),
+        "number" => value.is_number(),
+        "boolean" => value.is_boolean(),
+        _ => true,
+      };
+      if !matches {
+        errors.push(format!("{path}: expected {kind}, got {value}"));
+        return;
+      }
+    }
+    if let Some(allowed) = schema.get("enum").and_then(Value::as_array) {
+      if !allowed.contains(value) {
+        errors.push(format!("{path}: {value} is not one of {allowed:?}"));
+      }
+    }
+    for field in schema
+      .get("required")
+      .and_then(Value::as_array)
+      .into_iter()
+      .flatten()
+      .filter_map(Value::as_str)
+    {
+      if value.get(field).is_none() {
+        errors.push(format!("{path}: missing required field {field}"));
+      }
+    }
+    if let (Some(properties), Some(object)) = (
+      schema.get("properties").and_then(Value::as_object),
+      value.as_object(),
+    ) {
+      for (field, field_schema) in properties {
+        if let Some(field_value) = object.get(field) {
+          schema_errors(
+            field_value,
+            field_schema,
+            &format!("{path}.{field}"),
+            errors,
+          );
+        }
+      }
+    }
+    if let (Some(items_schema), Some(items)) = (schema.get("items"), value.as_array()) {
+      for (i, item) in items.iter().enumerate() {
+        schema_errors(item, items_schema, &format!("{path}[{i}]"), errors);
+      }
+    }
+  }
+
+  #[test]
+  fn test_schema_errors() -> Result<(), LemmyError> {
+    let schema: Value = file_to_json_object("assets/schema/page.json")?;
+    let mut page: Value = file_to_json_object("assets/lemmy/objects/page.json")?;
+    if let Some(o) = page.as_object_mut() {
+      o.remove("audience");
+      o.insert("sensitive".to_string(), "no".into());
+    }
+    let mut errors = vec![];
+    schema_errors(&page, &schema, "$", &mut errors);
+    assert_eq!(
+      vec![
+        "$: missing required field audience".to_string(),
+        "$.sensitive: expected boolean, got \"no\"".to_string(),
+      ],
+      errors
+    );
+    Ok(())
+  }
 }
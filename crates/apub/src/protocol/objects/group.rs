@@ -74,6 +74,10 @@ pub struct Group {
   pub(crate) language: Vec<LanguageTag>,
   pub(crate) published: Option<DateTime<Utc>>,
   pub(crate) updated: Option<DateTime<Utc>>,
+  // lemmy extension
+  pub(crate) blocked_url_domains: Option<Vec<String>>,
+  // lemmy extension
+  pub(crate) required_url_host: Option<String>,
 }
 
 impl Group {
@@ -122,6 +126,12 @@ impl Group {
       posting_restricted_to_mods: self.posting_restricted_to_mods,
       instance_id,
       featured_url: self.featured.map(Into::into),
+      nsfw_keywords: None,
+      post_rate_limit: None,
+      local_only: None,
+      blocked_url_domains: self.blocked_url_domains,
+      required_url_host: self.required_url_host,
+      max_posts: None,
     }
   }
 
@@ -152,6 +162,12 @@ impl Group {
       moderators_url: self.attributed_to.map(Into::into),
       posting_restricted_to_mods: self.posting_restricted_to_mods,
       featured_url: self.featured.map(Into::into),
+      nsfw_keywords: None,
+      post_rate_limit: None,
+      local_only: None,
+      blocked_url_domains: self.blocked_url_domains,
+      required_url_host: Some(self.required_url_host),
+      max_posts: None,
     }
   }
 }
@@ -36,15 +36,19 @@ impl LanguageTag {
     pool: &mut DbPool<'_>,
   ) -> Result<Option<LanguageTag>, LemmyError> {
     let lang = Language::read_from_id(pool, lang).await?;
+    Ok(Self::from_language(lang))
+  }
 
+  /// Like [LanguageTag::new_single], for a language which was already read.
+  pub(crate) fn from_language(lang: Language) -> Option<LanguageTag> {
     // undetermined
     if lang.id == UNDETERMINED_ID {
-      Ok(None)
+      None
     } else {
-      Ok(Some(LanguageTag {
+      Some(LanguageTag {
         identifier: lang.code,
         name: lang.name,
-      }))
+      })
     }
   }
 
@@ -160,6 +164,7 @@ mod tests {
     test_json::<Person>("assets/friendica/objects/person_2.json")?;
     test_json::<Page>("assets/friendica/objects/page_1.json")?;
     test_json::<Page>("assets/friendica/objects/page_2.json")?;
+    test_json::<Page>("assets/friendica/objects/page_3.json")?;
     test_json::<Note>("assets/friendica/objects/note_1.json")?;
     test_json::<Note>("assets/friendica/objects/note_2.json")?;
     Ok(())
@@ -61,7 +61,7 @@ impl Note {
     // Fetch parent comment chain in a box, otherwise it can cause a stack overflow.
     let parent = Box::pin(self.in_reply_to.dereference(context).await?);
     match parent.deref() {
-      PostOrComment::Post(p) => Ok((p.clone(), None)),
+      PostOrComment::Post(p) => Ok((*p.clone(), None)),
       PostOrComment::Comment(c) => {
         let post_id = c.post_id;
         let post = Post::read(&mut context.pool(), post_id).await?;
@@ -1,15 +1,20 @@
 use crate::{
   activities::verify_community_matches,
   fetcher::user_or_community::{PersonOrGroupType, UserOrCommunity},
-  objects::{community::ApubCommunity, person::ApubPerson, post::ApubPost},
-  protocol::{objects::LanguageTag, ImageObject, InCommunity, Source},
+  objects::{
+    community::ApubCommunity,
+    person::ApubPerson,
+    post::ApubPost,
+    read_from_string_or_source_opt,
+  },
+  protocol::{json_ld::compact_expanded, objects::LanguageTag, ImageObject, InCommunity, Source},
 };
 use activitypub_federation::{
   config::Data,
   fetch::object_id::ObjectId,
   kinds::{
     link::LinkType,
-    object::{DocumentType, ImageType},
+    object::{DocumentType, ImageType, PlaceType},
   },
   protocol::{
     helpers::{deserialize_one_or_many, deserialize_skip_error},
@@ -17,15 +22,27 @@ use activitypub_federation::{
   },
   traits::{ActivityHandler, Object},
 };
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, TimeZone, Utc};
 use itertools::Itertools;
 use lemmy_api_common::context::LemmyContext;
 use lemmy_db_schema::newtypes::DbUrl;
-use lemmy_utils::error::{LemmyError, LemmyErrorType};
-use serde::{de::Error, Deserialize, Deserializer, Serialize};
+use lemmy_utils::{
+  error::{LemmyError, LemmyErrorType},
+  utils::markdown::escape_markdown,
+};
+use serde::{de::Error, Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::Value;
 use serde_with::skip_serializing_none;
+use std::collections::BTreeMap;
 use url::Url;
 
+const MAX_GENERATOR_LENGTH: usize = 200;
+const MAX_DURATION_LENGTH: usize = 32;
+pub(crate) const MEDIA_TYPE_HTML: &str = "text/html";
+const MEDIA_TYPE_MARKDOWN: &str = "text/markdown";
+/// Epoch timestamps above this are in milliseconds. In seconds it would be the year 5138.
+const MIN_EPOCH_MILLIS: i64 = 100_000_000_000;
+
 #[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
 pub enum PageType {
   Page,
@@ -33,14 +50,16 @@ pub enum PageType {
   Note,
   Video,
   Event,
+  Audio,
 }
 
 #[skip_serializing_none]
 #[derive(Clone, Debug, Deserialize, Serialize)]
-#[serde(rename_all = "camelCase")]
+#[serde(rename_all = "camelCase", remote = "Self")]
 pub struct Page {
   #[serde(rename = "type")]
   pub(crate) kind: PageType,
+  #[serde(deserialize_with = "deserialize_id_or_link")]
   pub(crate) id: ObjectId<ApubPost>,
   pub(crate) attributed_to: AttributedTo,
   #[serde(deserialize_with = "deserialize_one_or_many")]
@@ -52,21 +71,106 @@ pub struct Page {
   pub(crate) name: Option<String>,
   #[serde(deserialize_with = "deserialize_one_or_many", default)]
   pub(crate) cc: Vec<Url>,
+  /// Private addressing, which must never be stored or sent out again. Posts need to be public
+  /// via `to` or `cc`, so these are ignored apart from that.
+  #[serde(
+    deserialize_with = "deserialize_one_or_many",
+    default,
+    skip_serializing
+  )]
+  pub(crate) bto: Vec<Url>,
+  #[serde(
+    deserialize_with = "deserialize_one_or_many",
+    default,
+    skip_serializing
+  )]
+  pub(crate) bcc: Vec<Url>,
   pub(crate) content: Option<String>,
-  pub(crate) media_type: Option<MediaTypeMarkdownOrHtml>,
+  /// Content in multiple languages, keyed by language code. Only sent, received posts use
+  /// `content`.
+  #[serde(deserialize_with = "deserialize_skip_error", default)]
+  pub(crate) content_map: Option<BTreeMap<String, String>>,
+  /// Kept as string, so that unknown media types can be downgraded to plain text instead of
+  /// rejecting the post
+  pub(crate) media_type: Option<String>,
   #[serde(deserialize_with = "deserialize_skip_error", default)]
   pub(crate) source: Option<Source>,
   /// most software uses array type for attachment field, so we do the same. nevertheless, we only
   /// use the first item
   #[serde(default)]
   pub(crate) attachment: Vec<Attachment>,
+  /// Invalid images are ignored, so that the post can still be received
+  #[serde(deserialize_with = "deserialize_skip_error", default)]
   pub(crate) image: Option<ImageObject>,
+  /// Some platforms send the preview image as icon instead, it is only used if image is missing
+  #[serde(deserialize_with = "deserialize_skip_error", default, skip_serializing)]
+  pub(crate) icon: Option<ImageObject>,
+  #[serde(deserialize_with = "deserialize_lenient_bool", default)]
   pub(crate) comments_enabled: Option<bool>,
+  /// If missing in an update, the post keeps its previous nsfw state
+  #[serde(deserialize_with = "deserialize_lenient_bool", default)]
   pub(crate) sensitive: Option<bool>,
+  #[serde(deserialize_with = "deserialize_timestamp", default)]
   pub(crate) published: Option<DateTime<Utc>>,
+  #[serde(deserialize_with = "deserialize_timestamp", default)]
   pub(crate) updated: Option<DateTime<Utc>>,
   pub(crate) language: Option<LanguageTag>,
   pub(crate) audience: Option<ObjectId<ApubCommunity>>,
+  /// Only informational, so it is ignored if invalid
+  #[serde(deserialize_with = "deserialize_skip_error", default)]
+  pub(crate) generator: Option<Generator>,
+  /// Invalid locations are ignored, so that the post can still be received
+  #[serde(deserialize_with = "deserialize_skip_error", default)]
+  pub(crate) location: Option<Place>,
+  /// Only used to seed the comment count, ignored unless it is an embedded collection
+  #[serde(deserialize_with = "deserialize_skip_error", default, skip_serializing)]
+  pub(crate) replies: Option<Replies>,
+  /// Preview of the linked page, used for the link card instead of fetching its metadata
+  #[serde(deserialize_with = "deserialize_skip_error", default, skip_serializing)]
+  pub(crate) preview: Option<Preview>,
+  /// Language as sent by some crossposting tools, in schema.org style. Only used if `language` is
+  /// missing.
+  #[serde(deserialize_with = "deserialize_skip_error", default, skip_serializing)]
+  pub(crate) in_language: Option<String>,
+  /// Media files of `Audio` objects as sent by Funkwhale and podcast platforms. Only used if there
+  /// is no attachment.
+  #[serde(deserialize_with = "deserialize_skip_error", default, skip_serializing)]
+  pub(crate) url: Vec<Link>,
+  /// Duration of audio or video in ISO 8601 format, eg `PT3M20S`
+  pub(crate) duration: Option<String>,
+  /// Identifier of the thread which this object belongs to. Not necessarily dereferenceable.
+  #[serde(deserialize_with = "deserialize_skip_error", default)]
+  pub(crate) context: Option<Url>,
+  /// Older name for `context`, still sent by Mastodon and Pleroma. Only used if `context` is
+  /// missing.
+  #[serde(deserialize_with = "deserialize_skip_error", default, skip_serializing)]
+  pub(crate) conversation: Option<Url>,
+  /// Json of a received page as it arrived, before any parsing. Only set when deserializing.
+  #[serde(skip)]
+  pub(crate) raw: Option<Value>,
+}
+
+/// Keeps the received json in [Page::raw], so that it can be stored for debugging. Pages in
+/// expanded JSON-LD form are compacted before parsing.
+impl<'de> Deserialize<'de> for Page {
+  fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+  where
+    D: Deserializer<'de>,
+  {
+    let raw = Value::deserialize(deserializer)?;
+    let mut page = Page::deserialize(compact_expanded(raw.clone())).map_err(D::Error::custom)?;
+    page.raw = Some(raw);
+    Ok(page)
+  }
+}
+
+impl Serialize for Page {
+  fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+  where
+    S: Serializer,
+  {
+    Page::serialize(self, serializer)
+  }
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -74,6 +178,8 @@ pub struct Page {
 pub(crate) struct Link {
   pub(crate) href: Url,
   pub(crate) r#type: LinkType,
+  #[serde(skip_serializing_if = "Option::is_none", default)]
+  pub(crate) media_type: Option<String>,
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -92,27 +198,123 @@ pub(crate) struct Document {
   pub(crate) url: Url,
 }
 
+/// Attachment with a relative url, as sent by some non-conformant servers.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub(crate) struct RelativeAttachment {
+  #[serde(alias = "url", deserialize_with = "deserialize_relative_url")]
+  pub(crate) href: String,
+}
+
 #[derive(Clone, Debug, Deserialize, Serialize)]
 #[serde(untagged)]
 pub(crate) enum Attachment {
   Link(Link),
   Image(Image),
   Document(Document),
+  Relative(RelativeAttachment),
 }
 
 impl Attachment {
-  pub(crate) fn url(self) -> Url {
+  /// The attachment url, with relative urls resolved against `base`. `None` if the resolved url
+  /// isn't http(s).
+  pub(crate) fn url(self, base: &Url) -> Option<Url> {
     match self {
       // url as sent by Lemmy (new)
-      Attachment::Link(l) => l.href,
+      Attachment::Link(l) => Some(l.href),
       // image sent by lotide
-      Attachment::Image(i) => i.url,
+      Attachment::Image(i) => Some(i.url),
       // sent by mobilizon
-      Attachment::Document(d) => d.url,
+      Attachment::Document(d) => Some(d.url),
+      Attachment::Relative(r) => base
+        .join(&r.href)
+        .ok()
+        .filter(|u| matches!(u.scheme(), "http" | "https")),
     }
   }
 }
 
+/// Software which created the object, as specified in
+/// https://www.w3.org/TR/activitystreams-vocabulary/#dfn-generator
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(untagged)]
+pub(crate) enum Generator {
+  Link(Url),
+  Object(GeneratorObject),
+}
+
+#[skip_serializing_none]
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct GeneratorObject {
+  #[serde(rename = "type")]
+  pub(crate) kind: Option<String>,
+  pub(crate) name: Option<String>,
+  pub(crate) url: Option<Url>,
+}
+
+impl Generator {
+  /// Name of the software, which usually includes the version (eg Friendica sends
+  /// `Friendica 'Giant Rhubarb' 2023.05-1518`). Falls back to the software url.
+  pub(crate) fn software(&self) -> Option<String> {
+    let software = match self {
+      Generator::Link(url) => Some(url.to_string()),
+      Generator::Object(o) => o
+        .name
+        .clone()
+        .or_else(|| o.url.as_ref().map(ToString::to_string)),
+    }?;
+    Some(software.chars().take(MAX_GENERATOR_LENGTH).collect())
+  }
+}
+
+/// Geo location of a post, eg where a PeerTube video was recorded
+#[skip_serializing_none]
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct Place {
+  #[serde(rename = "type")]
+  pub(crate) kind: PlaceType,
+  pub(crate) name: Option<String>,
+  pub(crate) latitude: Option<f64>,
+  pub(crate) longitude: Option<f64>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub(crate) enum PreviewType {
+  Image,
+  Video,
+}
+
+/// Preview of the linked page, as in https://www.w3.org/TR/activitystreams-vocabulary/#dfn-preview
+#[skip_serializing_none]
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct Preview {
+  #[serde(rename = "type")]
+  pub(crate) kind: PreviewType,
+  pub(crate) url: Url,
+  pub(crate) name: Option<String>,
+  pub(crate) summary: Option<String>,
+}
+
+impl Preview {
+  /// Title, description and video url for the link card
+  pub(crate) fn embed(&self) -> (Option<String>, Option<String>, Option<DbUrl>) {
+    let video = (self.kind == PreviewType::Video).then(|| self.url.clone().into());
+    (self.name.clone(), self.summary.clone(), video)
+  }
+
+  pub(crate) fn image(&self) -> Option<DbUrl> {
+    (self.kind == PreviewType::Image).then(|| self.url.clone().into())
+  }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct Replies {
+  pub(crate) total_items: i64,
+}
+
 #[derive(Clone, Debug, Deserialize, Serialize)]
 #[serde(untagged)]
 pub(crate) enum AttributedTo {
@@ -129,6 +331,88 @@ pub(crate) struct AttributedToPeertube {
 }
 
 impl Page {
+  /// The post language, falling back to `inLanguage`. Region subtags like in `en-US` are dropped,
+  /// as languages are stored without them.
+  pub(crate) fn language(&self) -> Option<LanguageTag> {
+    self.language.clone().or_else(|| {
+      let code = self
+        .in_language
+        .as_ref()?
+        .split('-')
+        .next()?
+        .trim()
+        .to_lowercase();
+      Some(LanguageTag {
+        identifier: code,
+        name: String::new(),
+      })
+    })
+  }
+
+  /// The post body as markdown. Content and source are only rendered if their media type is
+  /// accepted, a missing media type counts as html. Otherwise the content is escaped, so that it
+  /// is shown as plain text.
+  pub(crate) fn body(&self, accepted_media_types: &[String]) -> Option<String> {
+    let is_accepted = |m: &str| {
+      accepted_media_types
+        .iter()
+        .any(|a| a.eq_ignore_ascii_case(m))
+    };
+    let source = self.source.clone().filter(|s| {
+      is_accepted(match s.media_type {
+        MediaTypeMarkdownOrHtml::Markdown => MEDIA_TYPE_MARKDOWN,
+        MediaTypeMarkdownOrHtml::Html => MEDIA_TYPE_HTML,
+      })
+    });
+    let media_type = match self.media_type.as_deref().unwrap_or(MEDIA_TYPE_HTML) {
+      m if !is_accepted(m) => None,
+      m if m.eq_ignore_ascii_case(MEDIA_TYPE_MARKDOWN) => Some(MediaTypeMarkdownOrHtml::Markdown),
+      m if m.eq_ignore_ascii_case(MEDIA_TYPE_HTML) => Some(MediaTypeMarkdownOrHtml::Html),
+      _ => None,
+    };
+    if media_type.is_none() && source.is_none() {
+      return self.content.as_deref().map(escape_markdown);
+    }
+    read_from_string_or_source_opt(&self.content, &media_type, &source)
+  }
+
+  /// Url of the first attachment, which is the post link. Relative urls are resolved against the
+  /// post id.
+  pub(crate) fn attachment_url(&self) -> Option<Url> {
+    self
+      .attachment
+      .first()
+      .cloned()
+      .and_then(|a| a.url(self.id.inner()))
+  }
+
+  /// Media file of an `Audio` object, preferring links with an audio media type over others.
+  pub(crate) fn audio_url(&self) -> Option<Url> {
+    self
+      .url
+      .iter()
+      .find(|l| {
+        l.media_type
+          .as_ref()
+          .is_some_and(|m| m.starts_with("audio/"))
+      })
+      .or(self.url.first())
+      .map(|l| l.href.clone())
+  }
+
+  /// The duration if it looks like an ISO 8601 duration, otherwise it is ignored.
+  pub(crate) fn duration(&self) -> Option<String> {
+    self
+      .duration
+      .clone()
+      .filter(|d| d.len() <= MAX_DURATION_LENGTH && d.starts_with('P'))
+  }
+
+  /// Thread identifier from `context`, falling back to `conversation`.
+  pub(crate) fn conversation_id(&self) -> Option<Url> {
+    self.context.clone().or_else(|| self.conversation.clone())
+  }
+
   /// Only mods can change the post's locked status. So if it is changed from the default value,
   /// it is a mod action and needs to be verified as such.
   ///
@@ -137,16 +421,16 @@ impl Page {
     &self,
     context: &Data<LemmyContext>,
   ) -> Result<bool, LemmyError> {
-    let old_post = self.id.clone().dereference_local(context).await;
+    let old_post = ApubPost::read_local(&self.id, context).await?;
     Ok(Page::is_locked_changed(&old_post, &self.comments_enabled))
   }
 
-  pub(crate) fn is_locked_changed<E>(
-    old_post: &Result<ApubPost, E>,
+  pub(crate) fn is_locked_changed(
+    old_post: &Option<ApubPost>,
     new_comments_enabled: &Option<bool>,
   ) -> bool {
     if let Some(new_comments_enabled) = new_comments_enabled {
-      if let Ok(old_post) = old_post {
+      if let Some(old_post) = old_post {
         return new_comments_enabled != &!old_post.locked;
       }
     }
@@ -171,6 +455,7 @@ impl Attachment {
     Attachment::Link(Link {
       href: url.into(),
       r#type: Default::default(),
+      media_type: None,
     })
   }
 }
@@ -198,27 +483,36 @@ impl ActivityHandler for Page {
 #[async_trait::async_trait]
 impl InCommunity for Page {
   async fn community(&self, context: &Data<LemmyContext>) -> Result<ApubCommunity, LemmyError> {
+    let require_known = context.settings().federation.require_known_community;
     let community = match &self.attributed_to {
-      AttributedTo::Lemmy(_) => {
-        let mut iter = self.to.iter().merge(self.cc.iter());
-        loop {
-          if let Some(cid) = iter.next() {
-            let cid = ObjectId::from(cid.clone());
-            if let Ok(c) = cid.dereference(context).await {
-              break c;
+      AttributedTo::Lemmy(_) => match &self.audience {
+        // Prefer the community given in audience, as it is unambiguous
+        Some(audience) => dereference_community(audience, require_known, context).await?,
+        None => {
+          let ids: Vec<Url> = self
+            .to
+            .iter()
+            .merge(self.cc.iter())
+            .unique()
+            .cloned()
+            .collect();
+          let mut communities = vec![];
+          for cid in ids {
+            let cid = ObjectId::<ApubCommunity>::from(cid);
+            if let Ok(c) = dereference_community(&cid, require_known, context).await {
+              communities.push(c);
             }
-          } else {
-            Err(LemmyErrorType::NoCommunityFoundInCc)?
           }
+          select_community(communities)?
         }
-      }
+      },
       AttributedTo::Peertube(p) => {
-        p.iter()
+        let id = p
+          .iter()
           .find(|a| a.kind == PersonOrGroupType::Group)
           .map(|a| ObjectId::<ApubCommunity>::from(a.id.clone().into_inner()))
-          .ok_or(LemmyErrorType::PageDoesNotSpecifyGroup)?
-          .dereference(context)
-          .await?
+          .ok_or(LemmyErrorType::PageDoesNotSpecifyGroup)?;
+        dereference_community(&id, require_known, context).await?
       }
     };
     if let Some(audience) = &self.audience {
@@ -228,6 +522,115 @@ impl InCommunity for Page {
   }
 }
 
+/// Reads the community of a post, fetching it if necessary. With `require_known` only communities
+/// which are already stored locally are accepted.
+async fn dereference_community(
+  id: &ObjectId<ApubCommunity>,
+  require_known: bool,
+  context: &Data<LemmyContext>,
+) -> Result<ApubCommunity, LemmyError> {
+  if require_known {
+    id.dereference_local(context)
+      .await
+      .map_err(|_| LemmyErrorType::UnknownCommunity.into())
+  } else {
+    id.dereference(context).await
+  }
+}
+
+/// Picks the community which a post belongs to, if it is addressed to multiple communities.
+/// A single local community is preferred, otherwise the post is rejected as ambiguous instead of
+/// depending on the order of addressing.
+fn select_community(communities: Vec<ApubCommunity>) -> Result<ApubCommunity, LemmyError> {
+  let (local, remote): (Vec<_>, Vec<_>) = communities.into_iter().partition(|c| c.local);
+  match (local.as_slice(), remote.as_slice()) {
+    ([], []) => Err(LemmyErrorType::NoCommunityFoundInCc)?,
+    ([c], _) | ([], [c]) => Ok(c.clone()),
+    _ => Err(LemmyErrorType::AmbiguousCommunityInCc)?,
+  }
+}
+
+/// Some software wraps the id in a `Link` object, so accept that in addition to a plain url.
+fn deserialize_id_or_link<'de, D>(deserializer: D) -> Result<ObjectId<ApubPost>, D::Error>
+where
+  D: Deserializer<'de>,
+{
+  #[derive(Deserialize)]
+  #[serde(untagged)]
+  enum IdOrLink {
+    Id(Url),
+    Link { href: Url },
+  }
+  Ok(match IdOrLink::deserialize(deserializer)? {
+    IdOrLink::Id(id) | IdOrLink::Link { href: id } => id.into(),
+  })
+}
+
+/// Only accepts relative urls, so that absolute urls are parsed as one of the other attachment
+/// types.
+fn deserialize_relative_url<'de, D>(deserializer: D) -> Result<String, D::Error>
+where
+  D: Deserializer<'de>,
+{
+  let url = String::deserialize(deserializer)?;
+  match Url::parse(&url) {
+    Err(url::ParseError::RelativeUrlWithoutBase) => Ok(url),
+    _ => Err(D::Error::custom("Attachment url is not relative")),
+  }
+}
+
+/// Timestamps are normally RFC3339 strings, but some platforms send a Unix epoch in seconds or
+/// milliseconds instead.
+fn deserialize_timestamp<'de, D>(deserializer: D) -> Result<Option<DateTime<Utc>>, D::Error>
+where
+  D: Deserializer<'de>,
+{
+  #[derive(Deserialize)]
+  #[serde(untagged)]
+  enum Timestamp {
+    Rfc3339(DateTime<Utc>),
+    Epoch(i64),
+  }
+  Ok(match Option::<Timestamp>::deserialize(deserializer)? {
+    None => None,
+    Some(Timestamp::Rfc3339(t)) => Some(t),
+    Some(Timestamp::Epoch(e)) => {
+      let t = if e.abs() >= MIN_EPOCH_MILLIS {
+        Utc.timestamp_millis_opt(e)
+      } else {
+        Utc.timestamp_opt(e, 0)
+      };
+      Some(
+        t.single()
+          .ok_or_else(|| D::Error::custom("Invalid epoch timestamp"))?,
+      )
+    }
+  })
+}
+
+/// Some clients send booleans as strings, so accept `"true"` and `"false"` in addition to plain
+/// booleans.
+fn deserialize_lenient_bool<'de, D>(deserializer: D) -> Result<Option<bool>, D::Error>
+where
+  D: Deserializer<'de>,
+{
+  #[derive(Deserialize)]
+  #[serde(untagged)]
+  enum LenientBool {
+    Bool(bool),
+    String(String),
+  }
+  Ok(match Option::<LenientBool>::deserialize(deserializer)? {
+    None => None,
+    Some(LenientBool::Bool(b)) => Some(b),
+    Some(LenientBool::String(s)) => match s.trim().to_lowercase().as_str() {
+      "true" | "1" | "yes" => Some(true),
+      "false" | "0" | "no" => Some(false),
+      _ => Err(D::Error::custom(format!("Invalid boolean {s}")))?,
+    },
+  })
+}
+
 /// Only allows deserialization if the field is missing or null. If it is present, throws an error.
 pub fn deserialize_not_present<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
 where
@@ -242,10 +645,299 @@ where
 
 #[cfg(test)]
 mod tests {
-  use crate::protocol::{objects::page::Page, tests::test_parse_lemmy_item};
+  use crate::{
+    activity_lists::GroupInboxActivities,
+    objects::{
+      community::{tests::parse_lemmy_community, ApubCommunity},
+      person::tests::parse_lemmy_person,
+      tests::init_context,
+    },
+    protocol::{
+      objects::page::{dereference_community, Attachment, Generator, Page},
+      tests::{file_to_json_object, test_json, test_parse_lemmy_item},
+      InCommunity,
+    },
+  };
+  use activitypub_federation::{
+    fetch::object_id::ObjectId,
+    kinds::public,
+    protocol::context::WithContext,
+    traits::Actor,
+  };
+  use chrono::{TimeZone, Utc};
+  use lemmy_db_schema::{
+    source::{
+      community::{Community, CommunityInsertForm},
+      instance::Instance,
+      person::Person,
+      site::Site,
+    },
+    traits::Crud,
+  };
+  use lemmy_utils::error::{LemmyErrorType, LemmyResult};
+  use serde_json::json;
+  use serial_test::serial;
+  use url::Url;
+
+  #[test]
+  fn test_parse_epoch_timestamps() -> LemmyResult<()> {
+    let mut json: serde_json::Value = file_to_json_object("assets/lemmy/objects/page.json")?;
+    let expected = Utc
+      .timestamp_opt(1_700_000_000, 0)
+      .single()
+      .ok_or(LemmyErrorType::InvalidUnixTime)?;
+    let expected = Some(expected);
+    if let Some(o) = json.as_object_mut() {
+      o.insert("published".to_string(), json!(1_700_000_000));
+      o.insert("updated".to_string(), json!(1_700_000_000_000_i64));
+    }
+    let page: Page = serde_json::from_value(json.clone())?;
+    assert_eq!(expected, page.published);
+    assert_eq!(expected, page.updated);
+
+    if let Some(o) = json.as_object_mut() {
+      o.insert("published".to_string(), json!("2023-11-14T22:13:20Z"));
+      o.remove("updated");
+    }
+    let page: Page = serde_json::from_value(json)?;
+    assert_eq!(expected, page.published);
+    assert_eq!(None, page.updated);
+    Ok(())
+  }
+
+  #[test]
+  fn test_parse_string_booleans() -> LemmyResult<()> {
+    let mut json: serde_json::Value = file_to_json_object("assets/lemmy/objects/page.json")?;
+    if let Some(o) = json.as_object_mut() {
+      o.insert("commentsEnabled".to_string(), json!("true"));
+      o.insert("sensitive".to_string(), json!("False"));
+    }
+    let page: Page = serde_json::from_value(json.clone())?;
+    assert_eq!(Some(true), page.comments_enabled);
+    assert_eq!(Some(false), page.sensitive);
+
+    if let Some(o) = json.as_object_mut() {
+      o.insert("commentsEnabled".to_string(), json!(true));
+      o.remove("sensitive");
+    }
+    let page: Page = serde_json::from_value(json.clone())?;
+    assert_eq!(Some(true), page.comments_enabled);
+    assert_eq!(None, page.sensitive);
+
+    if let Some(o) = json.as_object_mut() {
+      o.insert("sensitive".to_string(), json!("maybe"));
+    }
+    assert!(serde_json::from_value::<Page>(json).is_err());
+    Ok(())
+  }
 
   #[test]
   fn test_not_parsing_note_as_page() {
     assert!(test_parse_lemmy_item::<Page>("assets/lemmy/objects/note.json").is_err());
   }
+
+  #[test]
+  fn test_private_addressing_not_serialized() -> LemmyResult<()> {
+    let mut json: serde_json::Value = file_to_json_object("assets/lemmy/objects/page.json")?;
+    if let Some(o) = json.as_object_mut() {
+      o.insert("bto".to_string(), json!("https://secret.example/u/alice"));
+      o.insert("bcc".to_string(), json!(["https://secret.example/u/bob"]));
+    }
+    let page: Page = serde_json::from_value(json)?;
+    assert_eq!(1, page.bto.len());
+    assert_eq!(1, page.bcc.len());
+
+    let json = serde_json::to_value(page)?;
+    assert!(json.get("bto").is_none());
+    assert!(json.get("bcc").is_none());
+    Ok(())
+  }
+
+  #[test]
+  fn test_parse_link_wrapped_id() -> LemmyResult<()> {
+    let mut json: serde_json::Value = file_to_json_object("assets/lemmy/objects/page.json")?;
+    let id = json.get("id").cloned();
+    if let Some(o) = json.as_object_mut() {
+      o.insert("id".to_string(), json!({ "type": "Link", "href": id }));
+    }
+    let page: Page = serde_json::from_value(json)?;
+    assert_eq!(
+      "https://enterprise.lemmy.ml/post/55143",
+      page.id.inner().as_str()
+    );
+    Ok(())
+  }
+
+  #[test]
+  fn test_parse_relative_attachment_url() -> LemmyResult<()> {
+    let mut json: serde_json::Value = file_to_json_object("assets/lemmy/objects/page.json")?;
+    if let Some(o) = json.as_object_mut() {
+      o.insert(
+        "attachment".to_string(),
+        json!([{ "type": "Link", "href": "../media/cat.png" }]),
+      );
+    }
+    let page: Page = serde_json::from_value(json)?;
+    assert_eq!(
+      Some("https://enterprise.lemmy.ml/media/cat.png"),
+      page.attachment_url().as_ref().map(Url::as_str)
+    );
+
+    // urls which don't resolve to http(s) are dropped
+    let attachment: Attachment = serde_json::from_value(json!({ "url": "cat.png" }))?;
+    assert_eq!(
+      None,
+      attachment.url(&Url::parse("ftp://example.com/post/1")?)
+    );
+    Ok(())
+  }
+
+  #[test]
+  fn test_parse_page_with_unknown_context() -> LemmyResult<()> {
+    let mut json: serde_json::Value = file_to_json_object("assets/lemmy/objects/page.json")?;
+    if let Some(o) = json.as_object_mut() {
+      o.insert(
+        "@context".to_string(),
+        json!([
+          "https://join-lemmy.org/context.json",
+          "https://www.w3.org/ns/activitystreams",
+          "https://unknown.example/ns/extension.jsonld"
+        ]),
+      );
+    }
+    // The context is not interpreted, so unknown entries don't affect parsing of known fields
+    let page: WithContext<Page> = serde_json::from_value(json)?;
+    let page = page.inner();
+    assert_eq!(Some("Post title"), page.name.as_deref());
+    assert!(page.source.is_some());
+    assert!(page.language.is_some());
+    assert!(page.audience.is_some());
+    assert_eq!(Some(false), page.sensitive);
+    assert_eq!(Some(true), page.comments_enabled);
+    Ok(())
+  }
+
+  #[test]
+  fn test_parse_activity_without_context() -> LemmyResult<()> {
+    // this file doesn't include @context, so it is only accepted by inboxes with lenient_context
+    let json: serde_json::Value =
+      file_to_json_object("assets/lemmy/activities/create_or_update/create_page.json")?;
+    assert!(serde_json::from_value::<WithContext<GroupInboxActivities>>(json.clone()).is_err());
+    let activity: GroupInboxActivities = serde_json::from_value(json)?;
+    assert!(matches!(
+      activity,
+      GroupInboxActivities::AnnouncableActivities(_)
+    ));
+    Ok(())
+  }
+
+  #[tokio::test]
+  #[serial]
+  async fn test_community_records_shared_inbox() -> LemmyResult<()> {
+    let context = init_context().await?;
+    let (person, site) = parse_lemmy_person(&context).await?;
+    let community = parse_lemmy_community(&context).await?;
+
+    let page: Page = file_to_json_object("assets/lemmy/objects/page.json")?;
+    let resolved = page.community(&context).await?;
+    let shared_inbox = Url::parse("https://enterprise.lemmy.ml/inbox")?;
+    assert_eq!(Some(shared_inbox.clone()), resolved.shared_inbox());
+    // delivery to the community goes to the shared inbox, not the community inbox
+    assert_eq!(shared_inbox, resolved.shared_inbox_or_inbox());
+
+    Community::delete(&mut context.pool(), community.id).await?;
+    Person::delete(&mut context.pool(), person.id).await?;
+    Site::delete(&mut context.pool(), site.id).await?;
+    Ok(())
+  }
+
+  #[tokio::test]
+  #[serial]
+  async fn test_require_known_community() -> LemmyResult<()> {
+    let context = init_context().await?;
+    let id = ObjectId::<ApubCommunity>::from(Url::parse("https://unknown.example.com/c/new")?);
+
+    let res = dereference_community(&id, true, &context).await;
+    assert_eq!(
+      Some(LemmyErrorType::UnknownCommunity),
+      res.err().map(|e| e.error_type)
+    );
+    // the community is neither fetched nor created
+    assert_eq!(context.request_count(), 0);
+    assert!(id.dereference_local(&context).await.is_err());
+
+    // known communities are still accepted
+    let (person, site) = parse_lemmy_person(&context).await?;
+    let community = parse_lemmy_community(&context).await?;
+    let resolved =
+      dereference_community(&community.actor_id.clone().into(), true, &context).await?;
+    assert_eq!(community.id, resolved.id);
+
+    Community::delete(&mut context.pool(), community.id).await?;
+    Person::delete(&mut context.pool(), person.id).await?;
+    Site::delete(&mut context.pool(), site.id).await?;
+    Ok(())
+  }
+
+  #[tokio::test]
+  #[serial]
+  async fn test_community_prefers_local() -> LemmyResult<()> {
+    let context = init_context().await?;
+    let (person, site) = parse_lemmy_person(&context).await?;
+    let remote_community = parse_lemmy_community(&context).await?;
+    let instance = Instance::read_or_create(&mut context.pool(), "example.com".to_string()).await?;
+    let form = CommunityInsertForm::builder()
+      .name("local".to_string())
+      .title("Local".to_string())
+      .public_key("pubkey".to_string())
+      .actor_id(Some(Url::parse("https://example.com/c/local")?.into()))
+      .local(Some(true))
+      .instance_id(instance.id)
+      .build();
+    let local_community = Community::create(&mut context.pool(), &form).await?;
+
+    let mut page: Page = file_to_json_object("assets/lemmy/objects/page.json")?;
+    page.audience = None;
+    page.to = vec![
+      remote_community.actor_id.clone().into(),
+      local_community.actor_id.clone().into(),
+      public(),
+    ];
+    assert_eq!(local_community.id, page.community(&context).await?.id);
+
+    // the audience field takes precedence
+    page.audience = Some(remote_community.actor_id.clone().into());
+    assert_eq!(remote_community.id, page.community(&context).await?.id);
+
+    Community::delete(&mut context.pool(), local_community.id).await?;
+    Community::delete(&mut context.pool(), remote_community.id).await?;
+    Instance::delete(&mut context.pool(), instance.id).await?;
+    Person::delete(&mut context.pool(), person.id).await?;
+    Site::delete(&mut context.pool(), site.id).await?;
+    Ok(())
+  }
+
+  #[test]
+  fn test_parse_generator() -> LemmyResult<()> {
+    let page = test_json::<Page>("assets/friendica/objects/page_3.json")?;
+    assert_eq!(
+      Some("Friendica 'Giant Rhubarb' 2023.05-1518".to_string()),
+      page
+        .inner()
+        .generator
+        .as_ref()
+        .and_then(Generator::software)
+    );
+
+    // absent or malformed generator is ignored
+    let page = test_json::<Page>("assets/friendica/objects/page_1.json")?;
+    assert!(page.inner().generator.is_none());
+    let mut json = serde_json::to_value(page.inner())?;
+    if let Some(o) = json.as_object_mut() {
+      o.insert("generator".to_string(), json!(["not", "a", "generator"]));
+    }
+    let page: Page = serde_json::from_value(json)?;
+    assert!(page.generator.is_none());
+    Ok(())
+  }
 }
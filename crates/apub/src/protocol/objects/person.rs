@@ -4,7 +4,10 @@ use crate::{
 };
 use activitypub_federation::{
   fetch::object_id::ObjectId,
-  protocol::{helpers::deserialize_skip_error, public_key::PublicKey},
+  protocol::{
+    helpers::{deserialize_one_or_many, deserialize_skip_error},
+    public_key::PublicKey,
+  },
 };
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
@@ -46,4 +49,8 @@ pub struct Person {
   pub(crate) endpoints: Option<Endpoints>,
   pub(crate) published: Option<DateTime<Utc>>,
   pub(crate) updated: Option<DateTime<Utc>>,
+  /// Other accounts of the same user, used to verify account migrations
+  #[serde(deserialize_with = "deserialize_one_or_many", default)]
+  #[serde(skip_serializing_if = "Vec::is_empty")]
+  pub(crate) also_known_as: Vec<Url>,
 }
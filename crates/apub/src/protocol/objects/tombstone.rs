@@ -1,5 +1,9 @@
 use crate::protocol::Id;
-use activitypub_federation::kinds::object::TombstoneType;
+use activitypub_federation::{
+  kinds::object::TombstoneType,
+  protocol::verification::{verify_domains_match, verify_urls_match},
+};
+use lemmy_utils::error::LemmyError;
 use serde::{Deserialize, Serialize};
 use serde_with::skip_serializing_none;
 use url::Url;
@@ -20,6 +24,14 @@ impl Tombstone {
       kind: TombstoneType::Tombstone,
     }
   }
+
+  /// Checks that the tombstone refers to the object which is being deleted, and that it comes
+  /// from the expected domain. Otherwise a remote instance could delete arbitrary objects.
+  pub(crate) fn verify(&self, expected_id: &Url, expected_domain: &Url) -> Result<(), LemmyError> {
+    verify_urls_match(&self.id, expected_id)?;
+    verify_domains_match(&self.id, expected_domain)?;
+    Ok(())
+  }
 }
 
 impl Id for Tombstone {
@@ -27,3 +39,25 @@ impl Id for Tombstone {
     &self.id
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use crate::protocol::objects::tombstone::Tombstone;
+  use lemmy_utils::error::LemmyResult;
+  use url::Url;
+
+  #[test]
+  fn test_verify_tombstone() -> LemmyResult<()> {
+    let post_id = Url::parse("https://enterprise.lemmy.ml/post/55143")?;
+    let actor_id = Url::parse("https://enterprise.lemmy.ml/u/picard")?;
+    let tombstone = Tombstone::new(post_id.clone());
+    tombstone.verify(&post_id, &actor_id)?;
+
+    let other_post_id = Url::parse("https://enterprise.lemmy.ml/post/1")?;
+    assert!(tombstone.verify(&other_post_id, &actor_id).is_err());
+
+    let other_actor_id = Url::parse("https://lemmy.ml/u/nutomic")?;
+    assert!(tombstone.verify(&post_id, &other_actor_id).is_err());
+    Ok(())
+  }
+}
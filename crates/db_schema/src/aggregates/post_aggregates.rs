@@ -1,7 +1,7 @@
 use crate::{
   aggregates::structs::PostAggregates,
   newtypes::PostId,
-  schema::{community_aggregates, post, post_aggregates},
+  schema::{comment, community_aggregates, post, post_aggregates},
   utils::{
     functions::{hot_rank, scaled_rank},
     get_conn,
@@ -49,6 +49,48 @@ impl PostAggregates {
       .get_result::<Self>(conn)
       .await
   }
+
+  /// Raises the comment count to the number of replies reported by a remote instance, so that
+  /// imported posts don't show zero comments until these are fetched.
+  pub async fn seed_comment_count(
+    pool: &mut DbPool<'_>,
+    post_id: PostId,
+    comments: i64,
+  ) -> Result<usize, Error> {
+    let conn = &mut get_conn(pool).await?;
+    diesel::update(
+      post_aggregates::table
+        .find(post_id)
+        .filter(post_aggregates::comments.lt(comments)),
+    )
+    .set(post_aggregates::comments.eq(comments))
+    .execute(conn)
+    .await
+  }
+
+  /// Undoes the increment for a newly imported comment, as long as the seeded count is still
+  /// ahead of the comments which are actually stored.
+  pub async fn correct_seeded_comment_count(
+    pool: &mut DbPool<'_>,
+    post_id: PostId,
+  ) -> Result<usize, Error> {
+    let conn = &mut get_conn(pool).await?;
+    let stored = comment::table
+      .filter(comment::post_id.eq(post_id))
+      .filter(comment::deleted.eq(false))
+      .filter(comment::removed.eq(false))
+      .count()
+      .get_result::<i64>(conn)
+      .await?;
+    diesel::update(
+      post_aggregates::table
+        .find(post_id)
+        .filter(post_aggregates::comments.gt(stored)),
+    )
+    .set(post_aggregates::comments.eq(post_aggregates::comments - 1))
+    .execute(conn)
+    .await
+  }
 }
 
 #[cfg(test)]
@@ -313,4 +355,73 @@ mod tests {
       .unwrap();
     Instance::delete(pool, inserted_instance.id).await.unwrap();
   }
+
+  #[tokio::test]
+  #[serial]
+  async fn test_seed_comment_count() {
+    let pool = &build_db_pool_for_tests().await;
+    let pool = &mut pool.into();
+
+    let inserted_instance = Instance::read_or_create(pool, "my_domain.tld".to_string())
+      .await
+      .unwrap();
+
+    let new_person = PersonInsertForm::builder()
+      .name("thommy_seed_agg".into())
+      .public_key("pubkey".to_string())
+      .instance_id(inserted_instance.id)
+      .build();
+
+    let inserted_person = Person::create(pool, &new_person).await.unwrap();
+
+    let new_community = CommunityInsertForm::builder()
+      .name("TIL_seed_agg".into())
+      .title("nada".to_owned())
+      .public_key("pubkey".to_string())
+      .instance_id(inserted_instance.id)
+      .build();
+
+    let inserted_community = Community::create(pool, &new_community).await.unwrap();
+
+    let new_post = PostInsertForm::builder()
+      .name("A test post".into())
+      .creator_id(inserted_person.id)
+      .community_id(inserted_community.id)
+      .build();
+
+    let inserted_post = Post::create(pool, &new_post).await.unwrap();
+
+    PostAggregates::seed_comment_count(pool, inserted_post.id, 2)
+      .await
+      .unwrap();
+    // a lower count never decreases the stored one
+    PostAggregates::seed_comment_count(pool, inserted_post.id, 1)
+      .await
+      .unwrap();
+    let post_aggs_seeded = PostAggregates::read(pool, inserted_post.id).await.unwrap();
+    assert_eq!(2, post_aggs_seeded.comments);
+
+    let comment_form = CommentInsertForm::builder()
+      .content("A test comment".into())
+      .creator_id(inserted_person.id)
+      .post_id(inserted_post.id)
+      .build();
+
+    // the first two received comments are already included in the seeded count
+    for _ in 0..3 {
+      Comment::create(pool, &comment_form, None).await.unwrap();
+      PostAggregates::correct_seeded_comment_count(pool, inserted_post.id)
+        .await
+        .unwrap();
+    }
+    let counts = PostAggregates::read(pool, inserted_post.id).await.unwrap();
+    assert_eq!(3, counts.comments);
+
+    Post::delete(pool, inserted_post.id).await.unwrap();
+    Person::delete(pool, inserted_person.id).await.unwrap();
+    Community::delete(pool, inserted_community.id)
+      .await
+      .unwrap();
+    Instance::delete(pool, inserted_instance.id).await.unwrap();
+  }
 }
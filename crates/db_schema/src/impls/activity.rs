@@ -58,6 +58,14 @@ impl ReceivedActivity {
       ))
     }
   }
+
+  pub async fn delete(pool: &mut DbPool<'_>, ap_id_: &DbUrl) -> Result<usize, Error> {
+    use crate::schema::received_activity::dsl::{ap_id, received_activity};
+    let conn = &mut get_conn(pool).await?;
+    diesel::delete(received_activity.filter(ap_id.eq(ap_id_)))
+      .execute(conn)
+      .await
+  }
 }
 
 #[cfg(test)]
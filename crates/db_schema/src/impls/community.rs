@@ -112,6 +112,15 @@ pub enum CollectionType {
 }
 
 impl Community {
+  /// Reads all communities with the given ids in a single query, in no particular order.
+  pub async fn read_many(pool: &mut DbPool<'_>, ids: &[CommunityId]) -> Result<Vec<Self>, Error> {
+    let conn = &mut get_conn(pool).await?;
+    community::table
+      .filter(community::id.eq_any(ids))
+      .load::<Self>(conn)
+      .await
+  }
+
   /// Get the community which has a given moderators or featured url, also return the collection type
   pub async fn get_by_collection_url(
     pool: &mut DbPool<'_>,
@@ -421,6 +430,12 @@ mod tests {
       hidden: false,
       posting_restricted_to_mods: false,
       instance_id: inserted_instance.id,
+      nsfw_keywords: vec![],
+      post_rate_limit: None,
+      local_only: false,
+      blocked_url_domains: vec![],
+      required_url_host: None,
+      max_posts: None,
     };
 
     let community_follower_form = CommunityFollowerForm {
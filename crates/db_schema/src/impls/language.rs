@@ -1,7 +1,7 @@
 use crate::{
   diesel::ExpressionMethods,
   newtypes::LanguageId,
-  schema::language::dsl::{code, language},
+  schema::language::dsl::{code, id, language},
   source::language::Language,
   utils::{get_conn, DbPool},
 };
@@ -14,6 +14,11 @@ impl Language {
     language.load::<Self>(conn).await
   }
 
+  pub async fn read_many(pool: &mut DbPool<'_>, ids: &[LanguageId]) -> Result<Vec<Self>, Error> {
+    let conn = &mut get_conn(pool).await?;
+    language.filter(id.eq_any(ids)).load::<Self>(conn).await
+  }
+
   pub async fn read_from_id(pool: &mut DbPool<'_>, id_: LanguageId) -> Result<Language, Error> {
     let conn = &mut get_conn(pool).await?;
     language.find(id_).first::<Self>(conn).await
@@ -49,6 +49,15 @@ impl Crud for Person {
 }
 
 impl Person {
+  /// Reads all persons with the given ids in a single query, in no particular order.
+  pub async fn read_many(pool: &mut DbPool<'_>, ids: &[PersonId]) -> Result<Vec<Self>, Error> {
+    let conn = &mut get_conn(pool).await?;
+    person::table
+      .filter(person::id.eq_any(ids))
+      .load::<Self>(conn)
+      .await
+  }
+
   /// Update or insert the person.
   ///
   /// This is necessary for federation, because Activitypub doesnt distinguish between these actions.
@@ -233,6 +242,7 @@ mod tests {
       shared_inbox_url: None,
       matrix_user_id: None,
       ban_expires: None,
+      published_unknown: false,
       instance_id: inserted_instance.id,
     };
 
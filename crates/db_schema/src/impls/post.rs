@@ -11,6 +11,7 @@ use crate::{
     name,
     post,
     published,
+    received_at,
     removed,
     thumbnail_url,
     updated,
@@ -18,13 +19,22 @@ use crate::{
   },
   source::post::{
     Post,
+    PostEditHistory,
+    PostEditHistoryForm,
     PostInsertForm,
     PostLike,
     PostLikeForm,
+    PostLocation,
+    PostLocationForm,
+    PostRawApub,
+    PostRawApubForm,
     PostRead,
     PostReadForm,
     PostSaved,
     PostSavedForm,
+    PostSummary,
+    PostTranslation,
+    PostTranslationForm,
     PostUpdateForm,
   },
   traits::{Crud, Likeable, Saveable},
@@ -40,8 +50,16 @@ use crate::{
   },
 };
 use ::url::Url;
-use chrono::{Duration, Utc};
-use diesel::{dsl::insert_into, result::Error, ExpressionMethods, QueryDsl, TextExpressionMethods};
+use chrono::{DateTime, Duration, Utc};
+use diesel::{
+  dsl::insert_into,
+  result::Error,
+  ExpressionMethods,
+  OptionalExtension,
+  QueryDsl,
+  SelectableHelper,
+  TextExpressionMethods,
+};
 use diesel_async::RunQueryDsl;
 use std::collections::HashSet;
 
@@ -163,6 +181,59 @@ impl Post {
       .await
   }
 
+  /// Attributes all posts of a user to another account, used for account migrations.
+  pub async fn update_creator(
+    pool: &mut DbPool<'_>,
+    old_creator_id: PersonId,
+    new_creator_id: PersonId,
+  ) -> Result<Vec<Self>, Error> {
+    let conn = &mut get_conn(pool).await?;
+
+    diesel::update(post.filter(creator_id.eq(old_creator_id)))
+      .set(creator_id.eq(new_creator_id))
+      .get_results::<Self>(conn)
+      .await
+  }
+
+  /// Number of posts by a user in a community which were received since the given time. This
+  /// uses the local `received_at` time, because `published` is set by the remote sender.
+  pub async fn count_recent_by_creator(
+    pool: &mut DbPool<'_>,
+    for_creator_id: PersonId,
+    for_community_id: CommunityId,
+    since: DateTime<Utc>,
+  ) -> Result<i64, Error> {
+    let conn = &mut get_conn(pool).await?;
+    post
+      .filter(creator_id.eq(for_creator_id))
+      .filter(community_id.eq(for_community_id))
+      .filter(received_at.gt(since))
+      .count()
+      .get_result::<i64>(conn)
+      .await
+  }
+
+  /// Permanently deletes up to `limit` remote posts which were deleted before the given time,
+  /// and returns how many were deleted.
+  pub async fn purge_deleted_remote(
+    pool: &mut DbPool<'_>,
+    before: DateTime<Utc>,
+    limit: i64,
+  ) -> Result<usize, Error> {
+    let conn = &mut get_conn(pool).await?;
+    let ids: Vec<PostId> = post
+      .select(crate::schema::post::id)
+      .filter(deleted.eq(true))
+      .filter(local.eq(false))
+      .filter(coalesce(updated, published).lt(before))
+      .limit(limit)
+      .load(conn)
+      .await?;
+    diesel::delete(post.filter(crate::schema::post::id.eq_any(ids)))
+      .execute(conn)
+      .await
+  }
+
   pub fn is_post_creator(person_id: PersonId, post_creator_id: PersonId) -> bool {
     person_id == post_creator_id
   }
@@ -178,11 +249,32 @@ impl Post {
         .filter(ap_id.eq(object_id))
         .first::<Post>(conn)
         .await
-        .ok()
+        .optional()?
         .map(Into::into),
     )
   }
 
+  /// Reads a post without its body, for lists where the full content isn't needed.
+  pub async fn read_summary_from_apub_id(
+    pool: &mut DbPool<'_>,
+    object_id: Url,
+  ) -> Result<Option<PostSummary>, Error> {
+    let conn = &mut get_conn(pool).await?;
+    let object_id: DbUrl = object_id.into();
+    post
+      .filter(ap_id.eq(object_id))
+      .select(PostSummary::as_select())
+      .first::<PostSummary>(conn)
+      .await
+      .optional()
+  }
+
+  /// Reads only the activitypub id of a post, without loading the rest of it.
+  pub async fn read_ap_id(pool: &mut DbPool<'_>, post_id: PostId) -> Result<DbUrl, Error> {
+    let conn = &mut get_conn(pool).await?;
+    post.find(post_id).select(ap_id).first::<DbUrl>(conn).await
+  }
+
   pub async fn fetch_pictrs_posts_for_creator(
     pool: &mut DbPool<'_>,
     for_creator_id: PersonId,
@@ -304,6 +396,155 @@ impl Saveable for PostSaved {
   }
 }
 
+impl PostRawApub {
+  /// Stores the json of a received post, replacing any previously stored version.
+  pub async fn upsert(pool: &mut DbPool<'_>, form: &PostRawApubForm) -> Result<Self, Error> {
+    use crate::schema::post_raw_apub::dsl::{post_id, post_raw_apub, published};
+    let conn = &mut get_conn(pool).await?;
+    insert_into(post_raw_apub)
+      .values(form)
+      .on_conflict(post_id)
+      .do_update()
+      .set((form, published.eq(Utc::now())))
+      .get_result::<Self>(conn)
+      .await
+  }
+
+  pub async fn read(pool: &mut DbPool<'_>, for_post_id: PostId) -> Result<Option<Self>, Error> {
+    use crate::schema::post_raw_apub::dsl::post_raw_apub;
+    let conn = &mut get_conn(pool).await?;
+    post_raw_apub
+      .find(for_post_id)
+      .first::<Self>(conn)
+      .await
+      .optional()
+  }
+}
+
+impl PostEditHistory {
+  /// Stores a previous version of a post, and removes the oldest versions so that at most `keep`
+  /// are left.
+  pub async fn create(
+    pool: &mut DbPool<'_>,
+    form: &PostEditHistoryForm,
+    keep: i64,
+  ) -> Result<Self, Error> {
+    use crate::schema::post_edit_history::dsl::{id, post_edit_history, post_id, published};
+    let conn = &mut get_conn(pool).await?;
+    let inserted = insert_into(post_edit_history)
+      .values(form)
+      .get_result::<Self>(conn)
+      .await?;
+    let outdated: Vec<i32> = post_edit_history
+      .filter(post_id.eq(form.post_id))
+      .order_by((published.desc(), id.desc()))
+      .offset(keep)
+      .select(id)
+      .load(conn)
+      .await?;
+    diesel::delete(post_edit_history.filter(id.eq_any(outdated)))
+      .execute(conn)
+      .await?;
+    Ok(inserted)
+  }
+
+  /// All stored versions of a post, newest first.
+  pub async fn list(pool: &mut DbPool<'_>, for_post_id: PostId) -> Result<Vec<Self>, Error> {
+    use crate::schema::post_edit_history::dsl::{id, post_edit_history, post_id, published};
+    let conn = &mut get_conn(pool).await?;
+    post_edit_history
+      .filter(post_id.eq(for_post_id))
+      .order_by((published.desc(), id.desc()))
+      .load::<Self>(conn)
+      .await
+  }
+}
+
+impl PostLocation {
+  /// Stores the location of a post, replacing any previous one.
+  pub async fn upsert(pool: &mut DbPool<'_>, form: &PostLocationForm) -> Result<Self, Error> {
+    use crate::schema::post_location::dsl::{post_id, post_location};
+    let conn = &mut get_conn(pool).await?;
+    insert_into(post_location)
+      .values(form)
+      .on_conflict(post_id)
+      .do_update()
+      .set(form)
+      .get_result::<Self>(conn)
+      .await
+  }
+
+  pub async fn read(pool: &mut DbPool<'_>, for_post_id: PostId) -> Result<Option<Self>, Error> {
+    use crate::schema::post_location::dsl::post_location;
+    let conn = &mut get_conn(pool).await?;
+    post_location
+      .find(for_post_id)
+      .first::<Self>(conn)
+      .await
+      .optional()
+  }
+
+  pub async fn read_many(
+    pool: &mut DbPool<'_>,
+    for_post_ids: &[PostId],
+  ) -> Result<Vec<Self>, Error> {
+    use crate::schema::post_location::dsl::{post_id, post_location};
+    let conn = &mut get_conn(pool).await?;
+    post_location
+      .filter(post_id.eq_any(for_post_ids))
+      .load::<Self>(conn)
+      .await
+  }
+
+  pub async fn delete(pool: &mut DbPool<'_>, for_post_id: PostId) -> Result<usize, Error> {
+    use crate::schema::post_location::dsl::post_location;
+    let conn = &mut get_conn(pool).await?;
+    diesel::delete(post_location.find(for_post_id))
+      .execute(conn)
+      .await
+  }
+}
+
+impl PostTranslation {
+  /// Stores the translation of a post body, replacing any previous one in the same language.
+  pub async fn upsert(pool: &mut DbPool<'_>, form: &PostTranslationForm) -> Result<Self, Error> {
+    use crate::schema::post_translation::dsl::{language_id, post_id, post_translation};
+    let conn = &mut get_conn(pool).await?;
+    insert_into(post_translation)
+      .values(form)
+      .on_conflict((post_id, language_id))
+      .do_update()
+      .set(form)
+      .get_result::<Self>(conn)
+      .await
+  }
+
+  /// All translations of a post, ordered by language.
+  pub async fn list(pool: &mut DbPool<'_>, for_post_id: PostId) -> Result<Vec<Self>, Error> {
+    use crate::schema::post_translation::dsl::{language_id, post_id, post_translation};
+    let conn = &mut get_conn(pool).await?;
+    post_translation
+      .filter(post_id.eq(for_post_id))
+      .order_by(language_id)
+      .load::<Self>(conn)
+      .await
+  }
+
+  /// All translations of the given posts, ordered by post and language.
+  pub async fn list_many(
+    pool: &mut DbPool<'_>,
+    for_post_ids: &[PostId],
+  ) -> Result<Vec<Self>, Error> {
+    use crate::schema::post_translation::dsl::{language_id, post_id, post_translation};
+    let conn = &mut get_conn(pool).await?;
+    post_translation
+      .filter(post_id.eq_any(for_post_ids))
+      .order_by((post_id, language_id))
+      .load::<Self>(conn)
+      .await
+  }
+}
+
 impl PostRead {
   pub async fn mark_as_read(
     pool: &mut DbPool<'_>,
@@ -434,6 +675,18 @@ mod tests {
       language_id: Default::default(),
       featured_community: false,
       featured_local: false,
+      original_url: None,
+      generator: None,
+      content_hash: None,
+      canonical_url: None,
+      alt_text: None,
+      word_count: None,
+      reading_time: None,
+      duration: None,
+      received_at: None,
+      from_bot: false,
+      conversation_id: None,
+      thumbnail_blurhash: None,
     };
 
     // Post Like
@@ -183,6 +183,12 @@ diesel::table! {
         moderators_url -> Nullable<Varchar>,
         #[max_length = 255]
         featured_url -> Nullable<Varchar>,
+        nsfw_keywords -> Array<Text>,
+        post_rate_limit -> Nullable<Int4>,
+        local_only -> Bool,
+        blocked_url_domains -> Array<Text>,
+        required_url_host -> Nullable<Text>,
+        max_posts -> Nullable<Int4>,
     }
 }
 
@@ -609,6 +615,7 @@ diesel::table! {
         bot_account -> Bool,
         ban_expires -> Nullable<Timestamptz>,
         instance_id -> Int4,
+        published_unknown -> Bool,
     }
 }
 
@@ -691,6 +698,19 @@ diesel::table! {
         language_id -> Int4,
         featured_community -> Bool,
         featured_local -> Bool,
+        #[max_length = 512]
+        original_url -> Nullable<Varchar>,
+        generator -> Nullable<Text>,
+        content_hash -> Nullable<Text>,
+        canonical_url -> Nullable<Text>,
+        alt_text -> Nullable<Text>,
+        word_count -> Nullable<Int4>,
+        reading_time -> Nullable<Int4>,
+        duration -> Nullable<Text>,
+        received_at -> Nullable<Timestamptz>,
+        from_bot -> Bool,
+        conversation_id -> Nullable<Text>,
+        thumbnail_blurhash -> Nullable<Text>,
     }
 }
 
@@ -750,6 +770,35 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    post_edit_history (id) {
+        id -> Int4,
+        post_id -> Int4,
+        #[max_length = 200]
+        name -> Varchar,
+        body -> Nullable<Text>,
+        url -> Nullable<Text>,
+        published -> Timestamptz,
+    }
+}
+
+diesel::table! {
+    post_location (post_id) {
+        post_id -> Int4,
+        name -> Nullable<Text>,
+        latitude -> Nullable<Float8>,
+        longitude -> Nullable<Float8>,
+    }
+}
+
+diesel::table! {
+    post_raw_apub (post_id) {
+        post_id -> Int4,
+        data -> Json,
+        published -> Timestamptz,
+    }
+}
+
 diesel::table! {
     post_saved (person_id, post_id) {
         post_id -> Int4,
@@ -758,6 +807,15 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    post_translation (post_id, language_id) {
+        post_id -> Int4,
+        language_id -> Int4,
+        body -> Text,
+        published -> Timestamptz,
+    }
+}
+
 diesel::table! {
     private_message (id) {
         id -> Int4,
@@ -962,9 +1020,14 @@ diesel::joinable!(post_aggregates -> person (creator_id));
 diesel::joinable!(post_aggregates -> post (post_id));
 diesel::joinable!(post_like -> person (person_id));
 diesel::joinable!(post_like -> post (post_id));
+diesel::joinable!(post_edit_history -> post (post_id));
+diesel::joinable!(post_location -> post (post_id));
 diesel::joinable!(post_read -> person (person_id));
 diesel::joinable!(post_read -> post (post_id));
 diesel::joinable!(post_report -> post (post_id));
+diesel::joinable!(post_raw_apub -> post (post_id));
+diesel::joinable!(post_translation -> language (language_id));
+diesel::joinable!(post_translation -> post (post_id));
 diesel::joinable!(post_saved -> person (person_id));
 diesel::joinable!(post_saved -> post (post_id));
 diesel::joinable!(private_message_report -> private_message (private_message_id));
@@ -1032,9 +1095,13 @@ diesel::allow_tables_to_appear_in_same_query!(
     post,
     post_aggregates,
     post_like,
+    post_edit_history,
+    post_location,
     post_read,
     post_report,
+    post_raw_apub,
     post_saved,
+    post_translation,
     private_message,
     private_message_report,
     received_activity,
@@ -66,6 +66,19 @@ pub struct Community {
   /// Url where featured posts collection is served over Activitypub
   #[serde(skip)]
   pub featured_url: Option<DbUrl>,
+  /// Posts whose title or body contain one of these words are marked as NSFW.
+  pub nsfw_keywords: Vec<String>,
+  /// Maximum number of posts per remote user and rate limit interval, overriding the instance
+  /// setting.
+  pub post_rate_limit: Option<i32>,
+  /// Only users of this instance can post in the community, so it doesn't accept federated posts.
+  pub local_only: bool,
+  /// Posts linking to these domains, or their subdomains, are rejected.
+  pub blocked_url_domains: Vec<String>,
+  /// Only link posts to this host, or its subdomains, are accepted.
+  pub required_url_host: Option<String>,
+  /// New posts are rejected once the community has this many posts.
+  pub max_posts: Option<i32>,
 }
 
 #[derive(Debug, Clone, TypedBuilder)]
@@ -99,6 +112,12 @@ pub struct CommunityInsertForm {
   pub posting_restricted_to_mods: Option<bool>,
   #[builder(!default)]
   pub instance_id: InstanceId,
+  pub nsfw_keywords: Option<Vec<String>>,
+  pub post_rate_limit: Option<i32>,
+  pub local_only: Option<bool>,
+  pub blocked_url_domains: Option<Vec<String>>,
+  pub required_url_host: Option<String>,
+  pub max_posts: Option<i32>,
 }
 
 #[derive(Debug, Clone, Default)]
@@ -126,6 +145,12 @@ pub struct CommunityUpdateForm {
   pub featured_url: Option<DbUrl>,
   pub hidden: Option<bool>,
   pub posting_restricted_to_mods: Option<bool>,
+  pub nsfw_keywords: Option<Vec<String>>,
+  pub post_rate_limit: Option<Option<i32>>,
+  pub local_only: Option<bool>,
+  pub blocked_url_domains: Option<Vec<String>>,
+  pub required_url_host: Option<Option<String>>,
+  pub max_posts: Option<Option<i32>>,
 }
 
 #[derive(PartialEq, Eq, Debug)]
@@ -57,6 +57,9 @@ pub struct Person {
   /// When their ban, if it exists, expires, if at all.
   pub ban_expires: Option<DateTime<Utc>>,
   pub instance_id: InstanceId,
+  /// The remote actor doesn't have a creation time, so `published` is when it was first fetched.
+  #[serde(skip)]
+  pub published_unknown: bool,
 }
 
 #[derive(Clone, TypedBuilder)]
@@ -87,6 +90,7 @@ pub struct PersonInsertForm {
   pub matrix_user_id: Option<String>,
   pub bot_account: Option<bool>,
   pub ban_expires: Option<DateTime<Utc>>,
+  pub published_unknown: Option<bool>,
 }
 
 #[derive(Clone, Default)]
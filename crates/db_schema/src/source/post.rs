@@ -1,8 +1,19 @@
 use crate::newtypes::{CommunityId, DbUrl, LanguageId, PersonId, PostId};
 #[cfg(feature = "full")]
-use crate::schema::{post, post_like, post_read, post_saved};
+use crate::schema::{
+  post,
+  post_edit_history,
+  post_like,
+  post_location,
+  post_raw_apub,
+  post_read,
+  post_saved,
+  post_translation,
+};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+#[cfg(feature = "full")]
+use serde_json::Value;
 use serde_with::skip_serializing_none;
 #[cfg(feature = "full")]
 use ts_rs::TS;
@@ -55,6 +66,58 @@ pub struct Post {
   pub featured_community: bool,
   /// Whether the post is featured to its site.
   pub featured_local: bool,
+  #[cfg_attr(feature = "full", ts(type = "string"))]
+  /// The original link of a federated post, if `url` was rewritten through a link proxy.
+  pub original_url: Option<DbUrl>,
+  /// Name and version of the software which created a federated post.
+  pub generator: Option<String>,
+  /// Hash of the normalized link or body, used to find crossposts of the same content.
+  pub content_hash: Option<String>,
+  #[cfg_attr(feature = "full", ts(type = "string"))]
+  /// The canonical link as declared by the linked page, if it differs. Used to find crossposts of
+  /// the same article under amp or shortened links.
+  pub canonical_url: Option<DbUrl>,
+  /// Alt text describing the thumbnail, for screen readers.
+  pub alt_text: Option<String>,
+  /// Number of words in the body, if computed on import.
+  pub word_count: Option<i32>,
+  /// Estimated time to read the body in minutes, if computed on import.
+  pub reading_time: Option<i32>,
+  /// Duration of audio or video posts, in ISO 8601 format (eg `PT3M20S`).
+  pub duration: Option<String>,
+  /// When a remote post was first received, independent of its `published` timestamp.
+  pub received_at: Option<DateTime<Utc>>,
+  /// Whether the post was created by a bot account, so that clients can filter it.
+  pub from_bot: bool,
+  #[cfg_attr(feature = "full", ts(type = "string"))]
+  /// Identifier of the thread which a federated post belongs to, as sent in `context` or
+  /// `conversation`. Used to group related objects from other platforms.
+  pub conversation_id: Option<DbUrl>,
+  /// Blurhash of the thumbnail, which clients can show as placeholder while loading the image or
+  /// instead of an nsfw image.
+  pub thumbnail_blurhash: Option<String>,
+}
+
+#[skip_serializing_none]
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "full", derive(Queryable, Selectable, Identifiable))]
+#[cfg_attr(feature = "full", diesel(table_name = post))]
+#[cfg_attr(feature = "full", diesel(check_for_backend(diesel::pg::Pg)))]
+/// The fields of a post which are needed for lists and feeds. Leaves out the body, which can be
+/// large.
+pub struct PostSummary {
+  pub id: PostId,
+  pub name: String,
+  pub url: Option<DbUrl>,
+  pub creator_id: PersonId,
+  pub community_id: CommunityId,
+  pub removed: bool,
+  pub published: DateTime<Utc>,
+  pub deleted: bool,
+  pub nsfw: bool,
+  pub thumbnail_url: Option<DbUrl>,
+  pub ap_id: DbUrl,
+  pub local: bool,
 }
 
 #[derive(Debug, Clone, TypedBuilder)]
@@ -85,6 +148,18 @@ pub struct PostInsertForm {
   pub language_id: Option<LanguageId>,
   pub featured_community: Option<bool>,
   pub featured_local: Option<bool>,
+  pub original_url: Option<DbUrl>,
+  pub generator: Option<String>,
+  pub content_hash: Option<String>,
+  pub canonical_url: Option<DbUrl>,
+  pub alt_text: Option<String>,
+  pub word_count: Option<i32>,
+  pub reading_time: Option<i32>,
+  pub duration: Option<String>,
+  pub received_at: Option<DateTime<Utc>>,
+  pub from_bot: Option<bool>,
+  pub conversation_id: Option<DbUrl>,
+  pub thumbnail_blurhash: Option<String>,
 }
 
 #[derive(Debug, Clone, Default)]
@@ -109,6 +184,18 @@ pub struct PostUpdateForm {
   pub language_id: Option<LanguageId>,
   pub featured_community: Option<bool>,
   pub featured_local: Option<bool>,
+  pub original_url: Option<Option<DbUrl>>,
+  pub generator: Option<Option<String>>,
+  pub content_hash: Option<Option<String>>,
+  pub canonical_url: Option<Option<DbUrl>>,
+  pub alt_text: Option<Option<String>>,
+  pub word_count: Option<Option<i32>>,
+  pub reading_time: Option<Option<i32>>,
+  pub duration: Option<Option<String>>,
+  pub received_at: Option<Option<DateTime<Utc>>>,
+  pub from_bot: Option<bool>,
+  pub conversation_id: Option<Option<DbUrl>>,
+  pub thumbnail_blurhash: Option<Option<String>>,
 }
 
 #[derive(PartialEq, Eq, Debug)]
@@ -158,6 +245,111 @@ pub struct PostSavedForm {
   pub person_id: PersonId,
 }
 
+#[cfg(feature = "full")]
+#[derive(PartialEq, Eq, Debug)]
+#[cfg_attr(
+  feature = "full",
+  derive(Identifiable, Queryable, Selectable, Associations)
+)]
+#[cfg_attr(feature = "full", diesel(belongs_to(crate::source::post::Post)))]
+#[cfg_attr(feature = "full", diesel(table_name = post_raw_apub))]
+#[cfg_attr(feature = "full", diesel(primary_key(post_id)))]
+#[cfg_attr(feature = "full", diesel(check_for_backend(diesel::pg::Pg)))]
+/// The json of a received post, stored for debugging.
+pub struct PostRawApub {
+  pub post_id: PostId,
+  pub data: Value,
+  pub published: DateTime<Utc>,
+}
+
+#[cfg(feature = "full")]
+#[cfg_attr(feature = "full", derive(Insertable, AsChangeset))]
+#[cfg_attr(feature = "full", diesel(table_name = post_raw_apub))]
+pub struct PostRawApubForm {
+  pub post_id: PostId,
+  pub data: Value,
+}
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+#[cfg_attr(
+  feature = "full",
+  derive(Identifiable, Queryable, Selectable, Associations)
+)]
+#[cfg_attr(feature = "full", diesel(belongs_to(crate::source::post::Post)))]
+#[cfg_attr(feature = "full", diesel(table_name = post_edit_history))]
+#[cfg_attr(feature = "full", diesel(check_for_backend(diesel::pg::Pg)))]
+/// A previous version of an edited post.
+pub struct PostEditHistory {
+  pub id: i32,
+  pub post_id: PostId,
+  pub name: String,
+  pub body: Option<String>,
+  pub url: Option<DbUrl>,
+  /// When this version was published, or last edited before the snapshot was taken.
+  pub published: DateTime<Utc>,
+}
+
+#[cfg_attr(feature = "full", derive(Insertable, AsChangeset))]
+#[cfg_attr(feature = "full", diesel(table_name = post_edit_history))]
+pub struct PostEditHistoryForm {
+  pub post_id: PostId,
+  pub name: String,
+  pub body: Option<String>,
+  pub url: Option<DbUrl>,
+  pub published: DateTime<Utc>,
+}
+
+#[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(
+  feature = "full",
+  derive(Identifiable, Queryable, Selectable, Associations)
+)]
+#[cfg_attr(feature = "full", diesel(belongs_to(crate::source::post::Post)))]
+#[cfg_attr(feature = "full", diesel(table_name = post_location))]
+#[cfg_attr(feature = "full", diesel(primary_key(post_id)))]
+#[cfg_attr(feature = "full", diesel(check_for_backend(diesel::pg::Pg)))]
+/// A geo location attached to a post.
+pub struct PostLocation {
+  pub post_id: PostId,
+  pub name: Option<String>,
+  pub latitude: Option<f64>,
+  pub longitude: Option<f64>,
+}
+
+#[cfg_attr(feature = "full", derive(Insertable, AsChangeset))]
+#[cfg_attr(feature = "full", diesel(table_name = post_location))]
+pub struct PostLocationForm {
+  pub post_id: PostId,
+  pub name: Option<String>,
+  pub latitude: Option<f64>,
+  pub longitude: Option<f64>,
+}
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+#[cfg_attr(
+  feature = "full",
+  derive(Identifiable, Queryable, Selectable, Associations)
+)]
+#[cfg_attr(feature = "full", diesel(belongs_to(crate::source::post::Post)))]
+#[cfg_attr(feature = "full", diesel(table_name = post_translation))]
+#[cfg_attr(feature = "full", diesel(primary_key(post_id, language_id)))]
+#[cfg_attr(feature = "full", diesel(check_for_backend(diesel::pg::Pg)))]
+/// The body of a post translated into another language.
+pub struct PostTranslation {
+  pub post_id: PostId,
+  pub language_id: LanguageId,
+  pub body: String,
+  pub published: DateTime<Utc>,
+}
+
+#[cfg_attr(feature = "full", derive(Insertable, AsChangeset))]
+#[cfg_attr(feature = "full", diesel(table_name = post_translation))]
+pub struct PostTranslationForm {
+  pub post_id: PostId,
+  pub language_id: LanguageId,
+  pub body: String,
+}
+
 #[derive(PartialEq, Eq, Debug)]
 #[cfg_attr(
   feature = "full",
@@ -379,6 +379,12 @@ mod tests {
         moderators_url: inserted_community.moderators_url,
         featured_url: inserted_community.featured_url,
         instance_id: inserted_instance.id,
+        nsfw_keywords: vec![],
+        post_rate_limit: None,
+        local_only: false,
+        blocked_url_domains: vec![],
+        required_url_host: None,
+        max_posts: None,
       },
       creator: Person {
         id: inserted_jessica.id,
@@ -398,6 +404,7 @@ mod tests {
         shared_inbox_url: None,
         matrix_user_id: None,
         ban_expires: None,
+        published_unknown: false,
         instance_id: inserted_instance.id,
         private_key: inserted_jessica.private_key,
         public_key: inserted_jessica.public_key,
@@ -421,6 +428,7 @@ mod tests {
         shared_inbox_url: None,
         matrix_user_id: None,
         ban_expires: None,
+        published_unknown: false,
         instance_id: inserted_instance.id,
         private_key: inserted_timmy.private_key.clone(),
         public_key: inserted_timmy.public_key.clone(),
@@ -463,6 +471,7 @@ mod tests {
       shared_inbox_url: None,
       matrix_user_id: None,
       ban_expires: None,
+      published_unknown: false,
       instance_id: inserted_instance.id,
       private_key: inserted_sara.private_key,
       public_key: inserted_sara.public_key,
@@ -531,6 +540,7 @@ mod tests {
       shared_inbox_url: None,
       matrix_user_id: None,
       ban_expires: None,
+      published_unknown: false,
       instance_id: inserted_instance.id,
     });
 
@@ -988,6 +988,7 @@ mod tests {
         shared_inbox_url: None,
         matrix_user_id: None,
         ban_expires: None,
+        published_unknown: false,
         instance_id: data.inserted_instance.id,
         private_key: data.timmy_local_user_view.person.private_key.clone(),
         public_key: data.timmy_local_user_view.person.public_key.clone(),
@@ -1015,6 +1016,18 @@ mod tests {
         language_id: Default::default(),
         featured_community: false,
         featured_local: false,
+        original_url: None,
+        generator: None,
+        content_hash: None,
+        canonical_url: None,
+        alt_text: None,
+        word_count: None,
+        reading_time: None,
+        duration: None,
+        received_at: None,
+        from_bot: false,
+        conversation_id: None,
+        thumbnail_blurhash: None,
       },
       community: Community {
         id: data.inserted_community.id,
@@ -1041,6 +1054,12 @@ mod tests {
         shared_inbox_url: data.inserted_community.shared_inbox_url.clone(),
         moderators_url: data.inserted_community.moderators_url.clone(),
         featured_url: data.inserted_community.featured_url.clone(),
+        nsfw_keywords: vec![],
+        post_rate_limit: None,
+        local_only: false,
+        blocked_url_domains: vec![],
+        required_url_host: None,
+        max_posts: None,
       },
       counts: CommentAggregates {
         comment_id: data.inserted_comment_0.id,
@@ -1482,6 +1482,18 @@ mod tests {
         language_id: LanguageId(47),
         featured_community: false,
         featured_local: false,
+        original_url: None,
+        generator: None,
+        content_hash: None,
+        canonical_url: None,
+        alt_text: None,
+        word_count: None,
+        reading_time: None,
+        duration: None,
+        received_at: None,
+        from_bot: false,
+        conversation_id: None,
+        thumbnail_blurhash: None,
       },
       my_vote: None,
       unread_comments: 0,
@@ -1503,6 +1515,7 @@ mod tests {
         shared_inbox_url: None,
         matrix_user_id: None,
         ban_expires: None,
+        published_unknown: false,
         instance_id: data.inserted_instance.id,
         private_key: inserted_person.private_key.clone(),
         public_key: inserted_person.public_key.clone(),
@@ -1536,6 +1549,12 @@ mod tests {
         shared_inbox_url: inserted_community.shared_inbox_url.clone(),
         moderators_url: inserted_community.moderators_url.clone(),
         featured_url: inserted_community.featured_url.clone(),
+        nsfw_keywords: vec![],
+        post_rate_limit: None,
+        local_only: false,
+        blocked_url_domains: vec![],
+        required_url_host: None,
+        max_posts: None,
       },
       counts: PostAggregates {
         post_id: inserted_post.id,
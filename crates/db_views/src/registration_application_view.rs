@@ -283,6 +283,7 @@ mod tests {
         local: true,
         banned: false,
         ban_expires: None,
+        published_unknown: false,
         deleted: false,
         bot_account: false,
         bio: None,
@@ -361,6 +362,7 @@ mod tests {
       local: true,
       banned: false,
       ban_expires: None,
+      published_unknown: false,
       deleted: false,
       bot_account: false,
       bio: None,
@@ -13,7 +13,7 @@ use activitypub_federation::{
 use anyhow::{Context, Result};
 use chrono::{DateTime, Days, TimeZone, Utc};
 use lemmy_api_common::{context::LemmyContext, federate_retry_sleep_duration};
-use lemmy_apub::{activity_lists::SharedInboxActivities, FEDERATION_CONTEXT};
+use lemmy_apub::activity_lists::SharedInboxActivities;
 use lemmy_db_schema::{
   newtypes::{ActivityId, CommunityId, InstanceId},
   source::{
@@ -29,7 +29,7 @@ use once_cell::sync::Lazy;
 use reqwest::Url;
 use std::{
   collections::{HashMap, HashSet},
-  ops::{Add, Deref},
+  ops::Add,
   time::Duration,
 };
 use tokio::{sync::mpsc::UnboundedSender, time::sleep};
@@ -231,7 +231,8 @@ impl InstanceWorker {
       .await
       .context("failed getting actor instance (was it marked deleted / removed?)")?;
 
-    let object = WithContext::new(object.clone(), FEDERATION_CONTEXT.deref().clone());
+    let send_context = object.send_context(self.context.settings());
+    let object = WithContext::new(object.clone(), send_context);
     let inbox_urls = inbox_urls.into_iter().collect();
     let requests =
       SendActivityTask::prepare(&object, actor.as_ref(), inbox_urls, &self.context).await?;
@@ -22,6 +22,7 @@ use lemmy_db_views_actor::{
 use lemmy_utils::{
   cache_header::cache_1hour,
   error::LemmyError,
+  settings::structs::HtmlConfig,
   utils::markdown::{markdown_to_html, sanitize_html},
 };
 use once_cell::sync::Lazy;
@@ -138,7 +139,11 @@ async fn get_feed_data(
   .list(&mut context.pool())
   .await?;
 
-  let items = create_post_items(posts, &context.settings().get_protocol_and_hostname())?;
+  let items = create_post_items(
+    posts,
+    &context.settings().get_protocol_and_hostname(),
+    &context.settings().html,
+  )?;
 
   let mut channel = Channel {
     namespaces: RSS_NAMESPACE.clone(),
@@ -245,7 +250,11 @@ async fn get_feed_user(
   .list(&mut context.pool())
   .await?;
 
-  let items = create_post_items(posts, &context.settings().get_protocol_and_hostname())?;
+  let items = create_post_items(
+    posts,
+    &context.settings().get_protocol_and_hostname(),
+    &context.settings().html,
+  )?;
 
   let channel = Channel {
     namespaces: RSS_NAMESPACE.clone(),
@@ -281,7 +290,11 @@ async fn get_feed_community(
   .list(&mut context.pool())
   .await?;
 
-  let items = create_post_items(posts, &context.settings().get_protocol_and_hostname())?;
+  let items = create_post_items(
+    posts,
+    &context.settings().get_protocol_and_hostname(),
+    &context.settings().html,
+  )?;
 
   let mut channel = Channel {
     namespaces: RSS_NAMESPACE.clone(),
@@ -292,7 +305,7 @@ async fn get_feed_community(
   };
 
   if let Some(community_desc) = community.description {
-    channel.set_description(markdown_to_html(&community_desc));
+    channel.set_description(markdown_to_html(&community_desc, &context.settings().html));
   }
 
   Ok(channel)
@@ -323,7 +336,7 @@ async fn get_feed_front(
   .await?;
 
   let protocol_and_hostname = context.settings().get_protocol_and_hostname();
-  let items = create_post_items(posts, &protocol_and_hostname)?;
+  let items = create_post_items(posts, &protocol_and_hostname, &context.settings().html)?;
 
   let mut channel = Channel {
     namespaces: RSS_NAMESPACE.clone(),
@@ -334,7 +347,7 @@ async fn get_feed_front(
   };
 
   if let Some(site_desc) = site_view.site.description {
-    channel.set_description(markdown_to_html(&site_desc));
+    channel.set_description(markdown_to_html(&site_desc, &context.settings().html));
   }
 
   Ok(channel)
@@ -374,7 +387,12 @@ async fn get_feed_inbox(context: &LemmyContext, jwt: &str) -> Result<Channel, Le
   .await?;
 
   let protocol_and_hostname = context.settings().get_protocol_and_hostname();
-  let items = create_reply_and_mention_items(replies, mentions, &protocol_and_hostname)?;
+  let items = create_reply_and_mention_items(
+    replies,
+    mentions,
+    &protocol_and_hostname,
+    &context.settings().html,
+  )?;
 
   let mut channel = Channel {
     namespaces: RSS_NAMESPACE.clone(),
@@ -396,6 +414,7 @@ fn create_reply_and_mention_items(
   replies: Vec<CommentReplyView>,
   mentions: Vec<PersonMentionView>,
   protocol_and_hostname: &str,
+  html: &HtmlConfig,
 ) -> Result<Vec<Item>, LemmyError> {
   let mut reply_items: Vec<Item> = replies
     .iter()
@@ -407,6 +426,7 @@ fn create_reply_and_mention_items(
         &reply_url,
         &r.comment.content,
         protocol_and_hostname,
+        html,
       )
     })
     .collect::<Result<Vec<Item>, LemmyError>>()?;
@@ -421,6 +441,7 @@ fn create_reply_and_mention_items(
         &mention_url,
         &m.comment.content,
         protocol_and_hostname,
+        html,
       )
     })
     .collect::<Result<Vec<Item>, LemmyError>>()?;
@@ -436,6 +457,7 @@ fn build_item(
   url: &str,
   content: &str,
   protocol_and_hostname: &str,
+  html: &HtmlConfig,
 ) -> Result<Item, LemmyError> {
   // TODO add images
   let author_url = format!("{protocol_and_hostname}/u/{creator_name}");
@@ -443,7 +465,7 @@ fn build_item(
     permalink: true,
     value: url.to_owned(),
   });
-  let description = Some(markdown_to_html(content));
+  let description = Some(markdown_to_html(content, html));
 
   Ok(Item {
     title: Some(format!("Reply from {creator_name}")),
@@ -463,6 +485,7 @@ fn build_item(
 fn create_post_items(
   posts: Vec<PostView>,
   protocol_and_hostname: &str,
+  html: &HtmlConfig,
 ) -> Result<Vec<Item>, LemmyError> {
   let mut items: Vec<Item> = Vec::new();
 
@@ -501,8 +524,8 @@ fn create_post_items(
     });
 
     if let Some(body) = p.post.body {
-      let html = markdown_to_html(&body);
-      description.push_str(&html);
+      let body = markdown_to_html(&body, html);
+      description.push_str(&body);
     }
 
     let i = Item {
@@ -228,6 +228,30 @@ pub enum LemmyErrorType {
   InvalidUnixTime,
   InvalidBotAction,
   CantBlockLocalInstance,
+  PostHostsUnrelated,
+  AmbiguousCommunityInCc,
+  InvalidMoveTarget,
+  PostRejectedAsSpam(String),
+  PostUpdatedBeforePublished,
+  UnsupportedObjectType(String),
+  TooManyRecipients,
+  TooManyPostsInCommunity,
+  CommunityHasNoLocalFollowers,
+  ImageHostNotAllowed,
+  LinkToMaliciousDomain(String),
+  CommunityIsLocalOnly,
+  DuplicateActivity,
+  SoftwareNotAllowed(String),
+  TooManyAttachments,
+  LinkToCommunityBlockedDomain(String),
+  PostAuthorIsCommunity,
+  TooManyLinksInPost,
+  UnknownCommunity,
+  PostAuthorTooNew,
+  LinkHostNotAllowedInCommunity(String),
+  PostImportTimedOut,
+  CommunityPostLimitReached,
+  InvalidRetentionPeriod,
   Unknown(String),
 }
 
@@ -13,6 +13,11 @@ pub struct Settings {
   #[default(Default::default())]
   pub database: DatabaseConfig,
   /// Settings related to activitypub federation
+  #[default(Default::default())]
+  pub federation: FederationConfig,
+  /// Html which may appear in markdown rendered by Lemmy, eg for federated posts and emails
+  #[default(Default::default())]
+  pub html: HtmlConfig,
   /// Pictrs image server configuration.
   #[default(Some(Default::default()))]
   pub(crate) pictrs: Option<PictrsConfig>,
@@ -95,6 +100,196 @@ pub struct PictrsConfig {
   pub upload_timeout: u64,
 }
 
+#[derive(Debug, Deserialize, Serialize, Clone, SmartDefault, Document)]
+#[serde(default, deny_unknown_fields)]
+pub struct FederationConfig {
+  /// Rewrite the link of incoming posts through a redirect or proxy service before storing it.
+  /// The placeholder `{url}` is replaced with the percent-encoded original link, which is kept
+  /// separately so that posts can still be deduplicated. Disabled by default.
+  #[default(None)]
+  #[doku(example = "https://proxy.example/?u={url}")]
+  pub url_proxy_template: Option<String>,
+  /// Reject incoming posts where neither the author nor the community are local, and both are on
+  /// different instances (unless both instances are in the allowlist). Such posts were likely
+  /// relayed by a third party and can indicate spam. If disabled, they are only logged.
+  #[default(false)]
+  pub reject_unrelated_post_hosts: bool,
+  /// Accept activities in user and community inboxes which are missing the `@context` field.
+  /// Some non-conformant software omits it. If disabled, such activities are rejected.
+  #[default(false)]
+  pub lenient_context: bool,
+  /// Store the json of received posts in the database, to help debugging federation issues.
+  /// Disabled by default because of the storage requirements.
+  #[default(false)]
+  pub store_raw_apub: bool,
+  /// Number of days after which stored post json is deleted
+  #[default(7)]
+  pub raw_apub_retention_days: i32,
+  /// Maximum number of rendered post bodies to keep in memory. Posts are often serialized
+  /// repeatedly, so this avoids rendering the same markdown again.
+  #[default(1000)]
+  pub markdown_cache_size: u64,
+  /// Incoming posts whose `updated` timestamp is before `published` are rejected by default. If
+  /// enabled, the two timestamps are swapped instead.
+  #[default(false)]
+  pub swap_invalid_post_timestamps: bool,
+  /// Incoming posts with more `to` and `cc` entries than this are rejected before any of them
+  /// are fetched.
+  #[default(500)]
+  pub max_post_recipients: usize,
+  /// Maximum size in bytes of activities received in the inbox. Larger requests are rejected
+  /// based on their content-length, or as soon as the limit is reached while reading the body.
+  /// The default is the same as the limit for fetching objects.
+  #[default(204_800)]
+  pub max_inbox_body_size: usize,
+  /// Maximum number of posts which a single remote user can make in a community within
+  /// `post_rate_limit_interval` seconds. Further posts are rejected. Communities can override
+  /// this with their own limit. Set to 0 to disable.
+  #[default(0)]
+  pub post_rate_limit: i32,
+  /// Length in seconds of the time window in which `post_rate_limit` applies.
+  #[default(3600)]
+  pub post_rate_limit_interval: i64,
+  /// Don't store new posts in remote communities which have no local followers. This also
+  /// prevents fetching such posts via search.
+  #[default(false)]
+  pub skip_posts_without_local_followers: bool,
+  /// Domains of relays which forward posts from other instances. Posts delivered by them can have
+  /// their id, author and community on different hosts from the relay.
+  #[default(Vec::new())]
+  #[doku(example = "relay.example.com")]
+  pub relay_domains: Vec<String>,
+  /// Collapse runs of three or more blank lines in the body of received posts to two, and remove
+  /// trailing whitespace. Code blocks are left unchanged.
+  #[default(false)]
+  pub collapse_blank_lines: bool,
+  /// Hosts from which received posts may use images as thumbnail. Images on the author's
+  /// instance are always allowed. If empty, images from all hosts are allowed.
+  #[default(Vec::new())]
+  #[doku(example = "media.example.com")]
+  pub allowed_image_hosts: Vec<String>,
+  /// Reject received posts which link to one of these domains or their subdomains.
+  #[default(Vec::new())]
+  #[doku(example = "phishing.example.com")]
+  pub malicious_domains: Vec<String>,
+  /// Also request links of received posts with HEAD, to check that they don't redirect to a
+  /// malicious domain.
+  #[default(false)]
+  pub check_link_redirects: bool,
+  /// Contact for admins of other instances, which is included in the User-Agent of outgoing
+  /// requests.
+  #[default(None)]
+  #[doku(example = "mailto:admin@example.com")]
+  pub contact_url: Option<String>,
+  /// Maximum number of received activity ids to keep in memory. Delivery retries carry the same
+  /// id, so they can be skipped without querying the database.
+  #[default(10000)]
+  pub received_activity_cache_size: u64,
+  /// Load images in the html content of outgoing objects through an image proxy, so that remote
+  /// viewers don't connect to the image host directly. The placeholder `{url}` is replaced with the
+  /// percent-encoded image url. Disabled by default.
+  #[default(None)]
+  #[doku(example = "https://images.example/?u={url}")]
+  pub image_proxy_template: Option<String>,
+  /// Reject received posts whose `generator` contains one of these strings, ignoring case, eg to
+  /// block a software version with known problems. Posts without generator are not checked.
+  #[default(Vec::new())]
+  #[doku(example = "Friendica 'Giant Rhubarb' 2023.05")]
+  pub software_denylist: Vec<String>,
+  /// Serve and send posts with a minimal `@context` which Mastodon is known to handle, instead of
+  /// the full Lemmy context. Only the `sensitive` and `stickied` terms are declared in addition to
+  /// ActivityStreams.
+  #[default(false)]
+  pub mastodon_compat: bool,
+  /// Maximum number of attachments of received posts. Only the first attachment is used as the
+  /// post link, so further ones are dropped.
+  #[default(20)]
+  pub max_attachments_per_post: usize,
+  /// Reject received posts with more than `max_attachments_per_post` attachments, instead of
+  /// dropping the excess ones.
+  #[default(false)]
+  pub reject_excess_attachments: bool,
+  /// Remote posts which were deleted more than this many days ago are removed from the database
+  /// permanently, including their comments. If unset, deleted remote posts are kept. Must be at
+  /// least 1.
+  #[default(None)]
+  #[doku(example = "30")]
+  pub deleted_remote_post_retention_days: Option<i64>,
+  /// Compute word count and estimated reading time of received posts, and store them with the
+  /// post.
+  #[default(false)]
+  pub compute_reading_time: bool,
+  /// Received posts whose entire name matches this regex, ignoring case, use the title of the
+  /// linked page as name instead, if it is available. Useful for bots which name all posts "Link".
+  #[default(None)]
+  #[doku(example = "link|article|untitled")]
+  pub generic_post_name_pattern: Option<String>,
+  /// Bare links to these hosts, or their subdomains, are embedded as image or video in the html of
+  /// outgoing objects.
+  #[default(Vec::new())]
+  #[doku(example = "i.imgur.com")]
+  pub media_embed_hosts: Vec<String>,
+  /// Incoming posts with more links than this in their body are rejected as link spam. Both
+  /// markdown links and bare urls are counted.
+  #[default(100)]
+  pub max_links_per_post: usize,
+  /// Only accept posts in communities which are already known locally, for example because a
+  /// local user follows them. Otherwise unknown communities are fetched from incoming posts.
+  #[default(false)]
+  pub require_known_community: bool,
+  /// Thumbnail for received link posts which don't include an image, and where none could be
+  /// fetched from the linked page
+  #[default(None)]
+  #[doku(example = "https://example.com/fallback-thumbnail.png")]
+  pub fallback_thumbnail_url: Option<Url>,
+  /// Media types of received post content which are rendered. Content with any other media type
+  /// is stored as plain text.
+  #[default(vec!["text/html".to_string(), "text/markdown".to_string()])]
+  #[doku(example = "text/html")]
+  #[doku(example = "text/markdown")]
+  pub accepted_media_types: Vec<String>,
+  /// Number of previous versions to keep when a received post is edited. Set to 0 to disable edit
+  /// history.
+  #[default(10)]
+  pub post_edit_history_size: i64,
+  /// Reject received posts whose author account was created less than this many seconds ago.
+  /// Authors with unknown creation time are not checked. Set to 0 to disable.
+  #[default(0)]
+  pub min_post_author_age: i64,
+  /// Include stored translations of post bodies in `contentMap` of outgoing posts, keyed by
+  /// language.
+  #[default(false)]
+  pub federate_translations: bool,
+  /// Accept text posts without link in communities which only allow links to a specific host.
+  #[default(true)]
+  pub required_url_host_allows_text_posts: bool,
+  /// Maximum time in seconds for importing a single received post, including fetching its
+  /// author, community and link metadata. Slower imports are aborted. Set to 0 to disable.
+  #[default(120)]
+  pub post_import_timeout: u64,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, SmartDefault, Document)]
+#[serde(default, deny_unknown_fields)]
+pub struct HtmlConfig {
+  /// Raw html tags which may be used in markdown, in addition to the ones generated from markdown
+  /// syntax. Other raw html is shown as text. By default no raw html is allowed.
+  #[default(Vec::new())]
+  #[doku(example = "sub")]
+  #[doku(example = "sup")]
+  pub allowed_tags: Vec<String>,
+  /// Attributes which may be used on the allowed raw html tags. Links which could run scripts are
+  /// always removed.
+  #[default(Vec::new())]
+  #[doku(example = "title")]
+  pub allowed_attributes: Vec<String>,
+  /// Tags generated from markdown syntax which are removed from the rendered html, eg `img` to not
+  /// show any images. The text inside them is kept. By default all of them are kept.
+  #[default(Vec::new())]
+  #[doku(example = "img")]
+  pub forbidden_markdown_tags: Vec<String>,
+}
+
 #[derive(Debug, Deserialize, Serialize, Clone, SmartDefault, Document)]
 #[serde(default)]
 pub struct DatabaseConfig {
@@ -1,6 +1,21 @@
-use markdown_it::MarkdownIt;
+use crate::settings::structs::HtmlConfig;
+use markdown_it::{
+  parser::inline::Text,
+  plugins::{
+    cmark::inline::{autolink::Autolink, image::Image, link::Link},
+    extra::linkify::Linkified,
+    html::html_inline::HtmlInline,
+  },
+  MarkdownIt,
+  Node,
+  NodeValue,
+  Renderer,
+};
 use once_cell::sync::Lazy;
+use std::collections::{HashMap, HashSet};
+use url::{form_urlencoded, Url};
 
+mod lazy_image_rule;
 mod spoiler_rule;
 
 static MARKDOWN_PARSER: Lazy<MarkdownIt> = Lazy::new(|| {
@@ -8,10 +23,64 @@ static MARKDOWN_PARSER: Lazy<MarkdownIt> = Lazy::new(|| {
   markdown_it::plugins::cmark::add(&mut parser);
   markdown_it::plugins::extra::add(&mut parser);
   spoiler_rule::add(&mut parser);
+  lazy_image_rule::add(&mut parser);
 
   parser
 });
 
+/// Parser for rendering, which also reads inline html tags so that ones from the allowlist can be
+/// kept.
+static RENDER_PARSER: Lazy<MarkdownIt> = Lazy::new(|| {
+  let mut parser = MarkdownIt::new();
+  markdown_it::plugins::cmark::add(&mut parser);
+  markdown_it::plugins::extra::add(&mut parser);
+  markdown_it::plugins::html::html_inline::add(&mut parser);
+  spoiler_rule::add(&mut parser);
+  lazy_image_rule::add(&mut parser);
+
+  parser
+});
+
+/// Tags which are generated from markdown syntax. They are kept when the rendered html is sanitized,
+/// unless they are forbidden in the config.
+const MARKDOWN_TAGS: [&str; 30] = [
+  "a",
+  "blockquote",
+  "br",
+  "code",
+  "del",
+  "details",
+  "em",
+  "h1",
+  "h2",
+  "h3",
+  "h4",
+  "h5",
+  "h6",
+  "hr",
+  "img",
+  "li",
+  "ol",
+  "p",
+  "pre",
+  "s",
+  "strong",
+  "summary",
+  "table",
+  "tbody",
+  "td",
+  "th",
+  "thead",
+  "tr",
+  "ul",
+  "video",
+];
+
+/// Attributes which are generated from markdown syntax.
+const MARKDOWN_ATTRIBUTES: [&str; 9] = [
+  "alt", "controls", "height", "href", "loading", "src", "start", "title", "width",
+];
+
 /// Replace special HTML characters in API parameters to prevent XSS attacks.
 ///
 /// Taken from https://github.com/OWASP/CheatSheetSeries/blob/master/cheatsheets/Cross_Site_Scripting_Prevention_Cheat_Sheet.md#output-encoding-for-html-contexts
@@ -25,9 +94,219 @@ pub fn sanitize_html(text: &str) -> String {
     .replace('\'', "&#x27;")
 }
 
-/// Converts text from markdown to HTML, while escaping special characters.
-pub fn markdown_to_html(text: &str) -> String {
-  MARKDOWN_PARSER.parse(text).xrender()
+/// Escapes markdown syntax, so that plain text is shown as it is.
+pub fn escape_markdown(text: &str) -> String {
+  let mut escaped = String::with_capacity(text.len());
+  for c in text.chars() {
+    if "\\`*_{}[]<>#+-!|~".contains(c) {
+      escaped.push('\\');
+    }
+    escaped.push(c);
+  }
+  escaped
+}
+
+/// Converts text from markdown to HTML, while escaping special characters. Only the html allowed
+/// by the given config is kept.
+pub fn markdown_to_html(text: &str, html: &HtmlConfig) -> String {
+  markdown_to_html_with_options(
+    text,
+    &MarkdownOptions {
+      html: Some(html),
+      ..Default::default()
+    },
+  )
+}
+
+/// Converts text from markdown to HTML with the default html config, and loads images through the
+/// proxy given by `template`. The placeholder `{url}` is replaced with the percent-encoded image
+/// url.
+pub fn markdown_to_html_with_image_proxy(text: &str, template: Option<&str>) -> String {
+  markdown_to_html_with_options(
+    text,
+    &MarkdownOptions {
+      image_proxy_template: template,
+      ..Default::default()
+    },
+  )
+}
+
+/// Optional changes to the html which [markdown_to_html_with_options] renders.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct MarkdownOptions<'a> {
+  /// Load images through this proxy. The placeholder `{url}` is replaced with the percent-encoded
+  /// image url.
+  pub image_proxy_template: Option<&'a str>,
+  /// Bare links to these hosts or their subdomains are embedded as video if the url has a video
+  /// file extension, or as image otherwise.
+  pub media_embed_hosts: &'a [String],
+  /// Allowed and forbidden html. If unset, the default config is used, which only allows html
+  /// generated from markdown syntax.
+  pub html: Option<&'a HtmlConfig>,
+}
+
+/// Converts text from markdown to HTML like [markdown_to_html], with the given options applied.
+pub fn markdown_to_html_with_options(text: &str, options: &MarkdownOptions) -> String {
+  let default_html = HtmlConfig::default();
+  let html = options.html.unwrap_or(&default_html);
+  let mut ast = RENDER_PARSER.parse(text);
+  // Inline html which isn't allowed is shown as text, like any html without the allowlist
+  ast.walk_mut(|node, _| {
+    let Some(tag) = node.cast::<HtmlInline>().map(|h| h.content.clone()) else {
+      return;
+    };
+    let name = html_tag_name(&tag);
+    let allowed = html
+      .allowed_tags
+      .iter()
+      .any(|a| !name.is_empty() && a.eq_ignore_ascii_case(name));
+    if !allowed {
+      node.replace(Text { content: tag });
+    }
+  });
+  if !options.media_embed_hosts.is_empty() {
+    ast.walk_mut(|node, _| embed_media_link(node, options.media_embed_hosts));
+  }
+  if let Some(template) = options.image_proxy_template {
+    ast.walk_mut(|node, _| {
+      if let Some(image) = node.cast_mut::<Image>() {
+        let encoded: String = form_urlencoded::byte_serialize(image.url.as_bytes()).collect();
+        image.url = template.replace("{url}", &encoded);
+      }
+    });
+  }
+  let rendered = ast.xrender();
+  if html.allowed_tags.is_empty() && html.forbidden_markdown_tags.is_empty() {
+    // All raw html was escaped above, so the output only contains tags generated by the parser
+    rendered
+  } else {
+    clean_html(&rendered, html)
+  }
+}
+
+/// The name of a raw html tag like `<sub>` or `</a>`, empty for comments and other markup.
+fn html_tag_name(tag: &str) -> &str {
+  tag
+    .trim_start_matches('<')
+    .trim_start_matches('/')
+    .split(|c: char| !c.is_ascii_alphanumeric() && c != '-')
+    .next()
+    .unwrap_or_default()
+}
+
+/// Sanitizes rendered html which contains raw html tags from the allowlist, so that only those
+/// and the tags generated from markdown are kept, without any scripts or unsafe links. Forbidden
+/// tags are removed, but their content is kept.
+fn clean_html(rendered: &str, html: &HtmlConfig) -> String {
+  let tags = MARKDOWN_TAGS
+    .into_iter()
+    .chain(html.allowed_tags.iter().map(String::as_str))
+    .filter(|t| {
+      !html
+        .forbidden_markdown_tags
+        .iter()
+        .any(|f| f.eq_ignore_ascii_case(t))
+    })
+    .collect::<HashSet<_>>();
+  let attributes = MARKDOWN_ATTRIBUTES
+    .into_iter()
+    .chain(html.allowed_attributes.iter().map(String::as_str))
+    .collect::<HashSet<_>>();
+  ammonia::Builder::default()
+    .tags(tags)
+    .generic_attributes(attributes)
+    .tag_attributes(HashMap::from([("code", HashSet::from(["class"]))]))
+    // Only keep the language class of code blocks
+    .attribute_filter(|element, attribute, value| {
+      if element == "code" && attribute == "class" && !value.starts_with("language-") {
+        None
+      } else {
+        Some(value.into())
+      }
+    })
+    .link_rel(None)
+    .clean(rendered)
+    .to_string()
+}
+
+const VIDEO_EXTENSIONS: [&str; 4] = [".mp4", ".webm", ".mov", ".ogv"];
+
+/// Embedded video, which shows the url as text if the video can't be played.
+#[derive(Debug)]
+struct Video {
+  url: String,
+}
+
+impl NodeValue for Video {
+  fn render(&self, node: &Node, fmt: &mut dyn Renderer) {
+    let mut attrs = node.attrs.clone();
+    attrs.push(("src", self.url.clone()));
+    attrs.push(("controls", String::new()));
+    fmt.open("video", &attrs);
+    fmt.text(&self.url);
+    fmt.close("video");
+  }
+}
+
+/// Replaces a bare link to one of the media hosts with an embedded image or video.
+fn embed_media_link(node: &mut Node, hosts: &[String]) {
+  let Some(link) = node.cast::<Linkified>().map(|l| l.url.clone()) else {
+    return;
+  };
+  let Ok(url) = Url::parse(&link) else {
+    return;
+  };
+  let Some(domain) = url.domain().map(str::to_lowercase) else {
+    return;
+  };
+  let matches_host = hosts.iter().any(|h| {
+    let h = h.trim().to_lowercase();
+    !h.is_empty()
+      && domain
+        .strip_suffix(&h)
+        .is_some_and(|prefix| prefix.is_empty() || prefix.ends_with('.'))
+  });
+  if !matches_host {
+    return;
+  }
+  let path = url.path().to_lowercase();
+  node.children.clear();
+  if VIDEO_EXTENSIONS.iter().any(|e| path.ends_with(e)) {
+    node.replace(Video { url: link });
+  } else {
+    node.replace(Image {
+      url: link,
+      title: None,
+    });
+    node.attrs.push(("loading", "lazy".to_string()));
+  }
+}
+
+/// Counts the words in the text content of markdown, so that syntax, link urls, code blocks and
+/// lone punctuation are not included.
+pub fn markdown_word_count(text: &str) -> usize {
+  let mut count = 0;
+  MARKDOWN_PARSER.parse(text).walk(|node, _| {
+    if let Some(text) = node.cast::<Text>() {
+      count += text
+        .content
+        .split_whitespace()
+        .filter(|w| w.chars().any(char::is_alphanumeric))
+        .count();
+    }
+  });
+  count
+}
+
+/// Counts the links in markdown, including autolinks and bare urls. Images are not included.
+pub fn markdown_link_count(text: &str) -> usize {
+  let mut count = 0;
+  MARKDOWN_PARSER.parse(text).walk(|node, _| {
+    if node.is::<Link>() || node.is::<Autolink>() || node.is::<Linkified>() {
+      count += 1;
+    }
+  });
+  count
 }
 
 #[cfg(test)]
@@ -78,7 +357,7 @@ mod tests {
       (
         "images",
         "![My linked image](https://image.com \"image alt text\")",
-        "<p><img src=\"https://image.com\" alt=\"My linked image\" title=\"image alt text\" /></p>\n"
+        "<p><img loading=\"lazy\" src=\"https://image.com\" alt=\"My linked image\" title=\"image alt text\" /></p>\n"
       ),
       // Ensure any custom plugins are added to 'MARKDOWN_PARSER' implementation.
       (
@@ -94,7 +373,7 @@ mod tests {
     ];
 
     tests.iter().for_each(|&(msg, input, expected)| {
-      let result = markdown_to_html(input);
+      let result = markdown_to_html(input, &HtmlConfig::default());
 
       assert_eq!(
         result, expected,
@@ -104,6 +383,155 @@ mod tests {
     });
   }
 
+  #[test]
+  fn test_image_proxy() {
+    let text = "![cat](https://example.com/cat.png?size=2) [link](https://example.com/)";
+    assert_eq!(
+      markdown_to_html(text, &HtmlConfig::default()),
+      markdown_to_html_with_image_proxy(text, None)
+    );
+    assert_eq!(
+      "<p><img loading=\"lazy\" src=\"https://proxy.example/?u=https%3A%2F%2Fexample.com%2Fcat.png%3Fsize%3D2\" alt=\"cat\" /> <a href=\"https://example.com/\">link</a></p>\n",
+      markdown_to_html_with_image_proxy(text, Some("https://proxy.example/?u={url}"))
+    );
+  }
+
+  #[test]
+  fn test_embed_media_links() {
+    let hosts = vec!["images.example".to_string()];
+    let options = MarkdownOptions {
+      media_embed_hosts: &hosts,
+      ..Default::default()
+    };
+    assert_eq!(
+      "<p>Look <img loading=\"lazy\" src=\"https://i.images.example/cat.png\" alt=\"\" /></p>\n",
+      markdown_to_html_with_options("Look https://i.images.example/cat.png", &options)
+    );
+    assert_eq!(
+      "<p><video src=\"https://images.example/cat.MP4\" controls=\"\">https://images.example/cat.MP4</video></p>\n",
+      markdown_to_html_with_options("https://images.example/cat.MP4", &options)
+    );
+    // links with text and other hosts are kept
+    let text = "[cat](https://images.example/cat.png) https://notimages.example/cat.png";
+    assert_eq!(
+      markdown_to_html(text, &HtmlConfig::default()),
+      markdown_to_html_with_options(text, &options)
+    );
+
+    // embedded images use the image proxy
+    let options = MarkdownOptions {
+      image_proxy_template: Some("https://proxy.example/?u={url}"),
+      media_embed_hosts: &hosts,
+      ..Default::default()
+    };
+    assert!(
+      markdown_to_html_with_options("https://images.example/cat.png", &options)
+        .contains("src=\"https://proxy.example/?u=https%3A%2F%2Fimages.example%2Fcat.png\"")
+    );
+  }
+
+  #[test]
+  fn test_html_allowlist() {
+    // without an allowlist all raw html is escaped
+    assert_eq!(
+      "<p>H&lt;sub&gt;2&lt;/sub&gt;O &lt;img src=x onerror=alert(1)&gt;</p>\n",
+      markdown_to_html(
+        "H<sub>2</sub>O <img src=x onerror=alert(1)>",
+        &HtmlConfig::default()
+      )
+    );
+
+    let html = HtmlConfig {
+      allowed_tags: vec!["a".into(), "sub".into()],
+      ..Default::default()
+    };
+    let options = MarkdownOptions {
+      html: Some(&html),
+      ..Default::default()
+    };
+    // allowed raw tags are kept, others are shown as text
+    assert_eq!(
+      "<p>H<sub>2</sub>O &lt;b&gt;bold&lt;/b&gt;</p>\n",
+      markdown_to_html_with_options("H<sub>2</sub>O <b>bold</b>", &options)
+    );
+    // unclosed tags are closed
+    assert_eq!(
+      "<p>H<sub>2O</sub></p>\n",
+      markdown_to_html_with_options("H<sub>2O", &options)
+    );
+    // links in raw html can't run scripts
+    assert_eq!(
+      "<p><a>x</a> <a href=\"https://example.com/\">y</a></p>\n",
+      markdown_to_html_with_options(
+        "<a href=\"javascript:alert(1)\">x</a> <a href='https://example.com/' onclick=\"alert(1)\">y</a>",
+        &options
+      )
+    );
+    // html generated from markdown is kept
+    assert_eq!(
+      "<p><strong>bold</strong> <a href=\"https://example.com/\" title=\"title\">link</a></p>\n",
+      markdown_to_html_with_options("**bold** [link](https://example.com/ \"title\")", &options)
+    );
+  }
+
+  #[test]
+  fn test_html_forbidden_tags() {
+    let html = HtmlConfig {
+      forbidden_markdown_tags: vec!["IMG".into()],
+      ..Default::default()
+    };
+    // images from markdown are removed, other markdown is kept
+    assert_eq!(
+      "<p>look <strong>here</strong> </p>\n",
+      markdown_to_html("look **here** ![cat](https://example.com/cat.png)", &html)
+    );
+
+    // forbidden tags are also removed if they are allowed as raw html
+    let html = HtmlConfig {
+      allowed_tags: vec!["img".into(), "sub".into()],
+      forbidden_markdown_tags: vec!["img".into()],
+      ..Default::default()
+    };
+    assert_eq!(
+      "<p>H<sub>2</sub>O </p>\n",
+      markdown_to_html(
+        "H<sub>2</sub>O <img src=\"https://example.com/cat.png\">",
+        &html
+      )
+    );
+  }
+
+  #[test]
+  fn test_markdown_word_count() {
+    let text = "# A heading\n\nSome **bold** text with a [link](https://example.com/a/b).\n\n\
+      ```\nlet code = not_counted();\n```\n\n- one\n- two";
+    assert_eq!(10, markdown_word_count(text));
+    assert_eq!(0, markdown_word_count(""));
+  }
+
+  #[test]
+  fn test_markdown_link_count() {
+    let text = "[a](https://example.com/a) and <https://example.com/b>, also \
+      https://example.com/c\n\n![image](https://example.com/d.png)\n\n\
+      [https://example.com/e](https://example.com/e)";
+    assert_eq!(4, markdown_link_count(text));
+    assert_eq!(0, markdown_link_count("no links here"));
+  }
+
+  #[test]
+  fn test_escape_markdown() {
+    let text = "# not a heading, *not bold* and <b>no html</b>";
+    let escaped = escape_markdown(text);
+    assert_eq!(
+      "\\# not a heading, \\*not bold\\* and \\<b\\>no html\\</b\\>",
+      escaped
+    );
+    assert_eq!(
+      "<p># not a heading, *not bold* and &lt;b&gt;no html&lt;/b&gt;</p>\n",
+      markdown_to_html(&escaped, &HtmlConfig::default())
+    );
+  }
+
   #[test]
   fn test_sanitize_html() {
     let sanitized = sanitize_html("<script>alert('xss');</script> hello &\"'");
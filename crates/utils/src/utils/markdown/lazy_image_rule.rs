@@ -0,0 +1,93 @@
+// Custom Markdown plugin which adds `loading="lazy"` to images, so that clients only load them
+// once they are scrolled into view. If the title gives the dimensions of the image as
+// `=WIDTHxHEIGHT`, they are added as attributes instead of the title, so that clients can reserve
+// the space before the image is loaded.
+//
+// FORMAT:
+// Input Markdown: ![alt](https://example.com/image.png)
+// Output HTML: <img loading="lazy" src="https://example.com/image.png" alt="alt" />
+//
+// Input Markdown: ![alt](https://example.com/image.png "=640x480")
+// Output HTML: <img loading="lazy" width="640" height="480" src="https://example.com/image.png" alt="alt" />
+
+use markdown_it::{
+  parser::{core::CoreRule, inline::builtin::InlineParserRule},
+  plugins::cmark::inline::image::Image,
+  MarkdownIt,
+  Node,
+};
+
+struct LazyImageRule;
+
+impl CoreRule for LazyImageRule {
+  fn run(root: &mut Node, _: &MarkdownIt) {
+    root.walk_mut(|node, _| {
+      let Some(image) = node.cast_mut::<Image>() else {
+        return;
+      };
+      let dimensions = image.title.as_deref().and_then(parse_dimensions);
+      if dimensions.is_some() {
+        image.title = None;
+      }
+      node.attrs.push(("loading", "lazy".to_string()));
+      if let Some((width, height)) = dimensions {
+        node.attrs.push(("width", width.to_string()));
+        node.attrs.push(("height", height.to_string()));
+      }
+    });
+  }
+}
+
+/// Parses image dimensions given as `=WIDTHxHEIGHT`.
+fn parse_dimensions(title: &str) -> Option<(u32, u32)> {
+  let (width, height) = title.strip_prefix('=')?.split_once('x')?;
+  Some((width.parse().ok()?, height.parse().ok()?))
+}
+
+pub fn add(markdown_parser: &mut MarkdownIt) {
+  markdown_parser
+    .add_rule::<LazyImageRule>()
+    .after::<InlineParserRule>();
+}
+
+#[cfg(test)]
+mod tests {
+  #![allow(clippy::unwrap_used)]
+  #![allow(clippy::indexing_slicing)]
+
+  use crate::utils::markdown::lazy_image_rule::add;
+  use markdown_it::MarkdownIt;
+
+  #[test]
+  fn test_lazy_image() {
+    let md = &mut MarkdownIt::new();
+    markdown_it::plugins::cmark::add(md);
+    add(md);
+
+    assert_eq!(
+      "<p>text <img loading=\"lazy\" src=\"https://example.com/a.png\" alt=\"alt\" /></p>\n",
+      md.parse("text ![alt](https://example.com/a.png)").xrender()
+    );
+    assert_eq!(
+      "<p><a href=\"https://example.com/\">link</a></p>\n",
+      md.parse("[link](https://example.com/)").xrender()
+    );
+  }
+
+  #[test]
+  fn test_image_dimensions() {
+    let md = &mut MarkdownIt::new();
+    markdown_it::plugins::cmark::add(md);
+    add(md);
+
+    assert_eq!(
+      "<p><img loading=\"lazy\" width=\"640\" height=\"480\" src=\"https://example.com/a.png\" alt=\"alt\" /></p>\n",
+      md.parse("![alt](https://example.com/a.png \"=640x480\")").xrender()
+    );
+    // other titles are kept
+    assert_eq!(
+      "<p><img loading=\"lazy\" src=\"https://example.com/a.png\" alt=\"alt\" title=\"=640x\" /></p>\n",
+      md.parse("![alt](https://example.com/a.png \"=640x\")").xrender()
+    );
+  }
+}
@@ -5,6 +5,10 @@ use regex::Regex;
 static MENTIONS_REGEX: Lazy<Regex> = Lazy::new(|| {
   Regex::new(r"@(?P<name>[\w.]+)@(?P<domain>[a-zA-Z0-9._:-]+)").expect("compile regex")
 });
+static MENTION_LINKS_REGEX: Lazy<Regex> = Lazy::new(|| {
+  Regex::new(r"\[(?P<mention>[@!][\w.]+(@[a-zA-Z0-9._:-]+)?)\]\((?P<url>https?://[^)\s]+)\)")
+    .expect("compile regex")
+});
 // TODO nothing is done with community / group webfingers yet, so just ignore those for now
 #[derive(Clone, PartialEq, Eq, Hash)]
 pub struct MentionData {
@@ -33,12 +37,44 @@ pub fn scrape_text_for_mentions(text: &str) -> Vec<MentionData> {
   out.into_iter().unique().collect()
 }
 
+/// A markdown link which has a mention as text, like `[!community@instance](url)`. Such links
+/// are created when converting html content of posts from other platforms.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct MentionLink {
+  /// The whole markdown link
+  pub link: String,
+  pub mention: String,
+  pub url: String,
+}
+
+impl MentionLink {
+  pub fn is_community(&self) -> bool {
+    self.mention.starts_with('!')
+  }
+}
+
+pub fn scrape_text_for_mention_links(text: &str) -> Vec<MentionLink> {
+  let mut out: Vec<MentionLink> = Vec::new();
+  for caps in MENTION_LINKS_REGEX.captures_iter(text) {
+    if let (Some(link), Some(mention), Some(url)) =
+      (caps.get(0), caps.name("mention"), caps.name("url"))
+    {
+      out.push(MentionLink {
+        link: link.as_str().to_string(),
+        mention: mention.as_str().to_string(),
+        url: url.as_str().to_string(),
+      });
+    }
+  }
+  out.into_iter().unique().collect()
+}
+
 #[cfg(test)]
 mod test {
   #![allow(clippy::unwrap_used)]
   #![allow(clippy::indexing_slicing)]
 
-  use crate::utils::mention::scrape_text_for_mentions;
+  use crate::utils::mention::{scrape_text_for_mention_links, scrape_text_for_mentions};
   use pretty_assertions::assert_eq;
 
   #[test]
@@ -50,4 +86,20 @@ mod test {
     assert_eq!(mentions[0].domain, "honk.teduangst.com".to_string());
     assert_eq!(mentions[1].domain, "lemmy-alpha:8540".to_string());
   }
+
+  #[test]
+  fn test_mention_links_regex() {
+    let text = "Posted in [!tenforward@enterprise.lemmy.ml](https://enterprise.lemmy.ml/c/tenforward) by [@picard](https://enterprise.lemmy.ml/u/picard), see [the docs](https://join-lemmy.org/)";
+    let links = scrape_text_for_mention_links(text);
+
+    assert_eq!(2, links.len());
+    assert!(links[0].is_community());
+    assert_eq!(links[0].mention, "!tenforward@enterprise.lemmy.ml");
+    assert_eq!(links[0].url, "https://enterprise.lemmy.ml/c/tenforward");
+    assert!(!links[1].is_community());
+    assert_eq!(
+      links[1].link,
+      "[@picard](https://enterprise.lemmy.ml/u/picard)"
+    );
+  }
 }
@@ -1,9 +1,26 @@
 use crate::error::{LemmyError, LemmyErrorExt, LemmyErrorType};
+use once_cell::sync::Lazy;
 use regex::{Regex, RegexBuilder};
 
+/// Code blocks, inline code and links, which are left untouched by [remove_slurs].
+static PROTECTED_SPAN_REGEX: Lazy<Regex> = Lazy::new(|| {
+  Regex::new(r"(?s)```.*?```|`[^`\n]*`|\bhttps?://[^\s)\]>]+").expect("compile regex")
+});
+
 pub fn remove_slurs(test: &str, slur_regex: &Option<Regex>) -> String {
   if let Some(slur_regex) = slur_regex {
-    slur_regex.replace_all(test, "*removed*").to_string()
+    let mut out = String::with_capacity(test.len());
+    let mut last = 0;
+    for span in PROTECTED_SPAN_REGEX.find_iter(test) {
+      out.push_str(&slur_regex.replace_all(
+        test.get(last..span.start()).unwrap_or_default(),
+        "*removed*",
+      ));
+      out.push_str(span.as_str());
+      last = span.end();
+    }
+    out.push_str(&slur_regex.replace_all(test.get(last..).unwrap_or_default(), "*removed*"));
+    out
   } else {
     test.to_string()
   }
@@ -101,6 +118,16 @@ mod test {
     }
   }
 
+  #[test]
+  fn test_remove_slurs_keeps_code_and_links() {
+    let slur_regex = Some(RegexBuilder::new("kikes?").build().unwrap());
+    let test = "kike\n```\nlet kike = 1;\n```\nuse `kike()` at https://example.com/kike?a=1 kike";
+    assert_eq!(
+      remove_slurs(test, &slur_regex),
+      "*removed*\n```\nlet kike = 1;\n```\nuse `kike()` at https://example.com/kike?a=1 *removed*"
+    );
+  }
+
   // These helped with testing
   // #[test]
   // fn test_send_email() {
@@ -0,0 +1,3 @@
+ALTER TABLE post
+    ADD COLUMN original_url varchar(512);
+
@@ -0,0 +1,3 @@
+ALTER TABLE post
+    ADD COLUMN generator text;
+
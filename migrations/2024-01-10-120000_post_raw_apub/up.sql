@@ -0,0 +1,9 @@
+-- Raw json of received posts, stored for debugging if enabled in the config
+CREATE TABLE post_raw_apub (
+    post_id int PRIMARY KEY REFERENCES post ON UPDATE CASCADE ON DELETE CASCADE,
+    data json NOT NULL,
+    published timestamptz NOT NULL DEFAULT now()
+);
+
+CREATE INDEX idx_post_raw_apub_published ON post_raw_apub (published);
+
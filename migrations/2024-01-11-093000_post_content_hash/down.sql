@@ -0,0 +1,3 @@
+ALTER TABLE post
+    DROP COLUMN content_hash;
+
@@ -0,0 +1,5 @@
+ALTER TABLE post
+    ADD COLUMN content_hash text;
+
+CREATE INDEX idx_post_content_hash ON post (content_hash);
+
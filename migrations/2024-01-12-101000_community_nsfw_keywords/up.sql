@@ -0,0 +1,3 @@
+ALTER TABLE community
+    ADD COLUMN nsfw_keywords text[] NOT NULL DEFAULT '{}';
+
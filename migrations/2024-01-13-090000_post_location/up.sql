@@ -0,0 +1,8 @@
+-- Geo location attached to a post, eg by PeerTube
+CREATE TABLE post_location (
+    post_id int PRIMARY KEY REFERENCES post ON UPDATE CASCADE ON DELETE CASCADE,
+    name text,
+    latitude float8,
+    longitude float8
+);
+
@@ -0,0 +1,3 @@
+ALTER TABLE community
+    DROP COLUMN post_rate_limit;
+
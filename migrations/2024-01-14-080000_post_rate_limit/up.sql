@@ -0,0 +1,3 @@
+ALTER TABLE community
+    ADD COLUMN post_rate_limit int;
+
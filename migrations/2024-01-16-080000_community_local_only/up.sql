@@ -0,0 +1,3 @@
+ALTER TABLE community
+    ADD COLUMN local_only boolean NOT NULL DEFAULT FALSE;
+
@@ -0,0 +1,3 @@
+ALTER TABLE community
+    DROP COLUMN blocked_url_domains;
+
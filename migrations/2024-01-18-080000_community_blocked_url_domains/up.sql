@@ -0,0 +1,3 @@
+ALTER TABLE community
+    ADD COLUMN blocked_url_domains text[] NOT NULL DEFAULT '{}';
+
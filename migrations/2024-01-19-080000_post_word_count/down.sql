@@ -0,0 +1,4 @@
+ALTER TABLE post
+    DROP COLUMN word_count,
+    DROP COLUMN reading_time;
+
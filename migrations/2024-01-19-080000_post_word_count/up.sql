@@ -0,0 +1,4 @@
+ALTER TABLE post
+    ADD COLUMN word_count int,
+    ADD COLUMN reading_time int;
+
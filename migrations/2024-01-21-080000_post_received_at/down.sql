@@ -0,0 +1,5 @@
+DROP INDEX idx_post_creator_community_received_at;
+
+ALTER TABLE post
+    DROP COLUMN received_at;
+
@@ -0,0 +1,5 @@
+ALTER TABLE post
+    ADD COLUMN received_at timestamptz;
+
+CREATE INDEX idx_post_creator_community_received_at ON post (creator_id, community_id, received_at);
+
@@ -0,0 +1,3 @@
+ALTER TABLE post
+    ADD COLUMN from_bot boolean DEFAULT FALSE NOT NULL;
+
@@ -0,0 +1,12 @@
+-- Previous versions of received posts, stored when they are edited
+CREATE TABLE post_edit_history (
+    id serial PRIMARY KEY,
+    post_id int NOT NULL REFERENCES post ON UPDATE CASCADE ON DELETE CASCADE,
+    name varchar(200) NOT NULL,
+    body text,
+    url text,
+    published timestamptz NOT NULL
+);
+
+CREATE INDEX idx_post_edit_history_post ON post_edit_history (post_id, published);
+
@@ -0,0 +1,10 @@
+-- Translations of post bodies, eg generated by machine translation
+CREATE TABLE post_translation (
+    post_id int REFERENCES post ON UPDATE CASCADE ON DELETE CASCADE NOT NULL,
+    language_id int REFERENCES
+    LANGUAGE ON UPDATE CASCADE ON DELETE CASCADE NOT NULL,
+    body text NOT NULL,
+    published timestamptz NOT NULL DEFAULT now(),
+    PRIMARY KEY (post_id, language_id)
+);
+
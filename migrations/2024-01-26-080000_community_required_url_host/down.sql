@@ -0,0 +1,3 @@
+ALTER TABLE community
+    DROP COLUMN required_url_host;
+
@@ -0,0 +1,3 @@
+ALTER TABLE community
+    ADD COLUMN required_url_host text;
+
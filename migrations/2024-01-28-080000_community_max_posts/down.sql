@@ -0,0 +1,3 @@
+ALTER TABLE community
+    DROP COLUMN max_posts;
+
@@ -0,0 +1,3 @@
+ALTER TABLE community
+    ADD COLUMN max_posts int;
+
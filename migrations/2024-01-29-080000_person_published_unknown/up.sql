@@ -0,0 +1,3 @@
+ALTER TABLE person
+    ADD COLUMN published_unknown boolean NOT NULL DEFAULT FALSE;
+
@@ -11,6 +11,7 @@ use diesel::{
 };
 use diesel_async::{AsyncPgConnection, RunQueryDsl};
 use lemmy_api_common::context::LemmyContext;
+use lemmy_apub::objects::post::purge_deleted_remote_posts;
 use lemmy_db_schema::{
   schema::{
     captcha_answer,
@@ -19,6 +20,7 @@ use lemmy_db_schema::{
     instance,
     person,
     post,
+    post_raw_apub,
     received_activity,
     sent_activity,
   },
@@ -78,6 +80,38 @@ pub async fn setup(context: LemmyContext) -> Result<(), LemmyError> {
     }
   });
 
+  let context_1 = context.clone();
+  // Clear stored json of received posts every day
+  scheduler.every(CTimeUnits::days(1)).run(move || {
+    let context = context_1.clone();
+
+    async move {
+      let retention_days = context.settings().federation.raw_apub_retention_days;
+      clear_old_raw_apub(&mut context.pool(), retention_days).await;
+    }
+  });
+
+  let context_1 = context.clone();
+  // Purge remote posts which were deleted long ago every day
+  scheduler.every(CTimeUnits::days(1)).run(move || {
+    let context = context_1.clone();
+
+    async move {
+      if let Some(retention_days) = context
+        .settings()
+        .federation
+        .deleted_remote_post_retention_days
+      {
+        info!("Purging old deleted remote posts...");
+        purge_deleted_remote_posts(&mut context.pool(), retention_days)
+          .await
+          .map(|count| info!("Done, purged {count} posts."))
+          .map_err(|e| error!("Failed to purge deleted remote posts: {e}"))
+          .ok();
+      }
+    }
+  });
+
   let context_1 = context.clone();
   // Overwrite deleted & removed posts and comments every day
   scheduler.every(CTimeUnits::days(1)).run(move || {
@@ -320,6 +354,29 @@ async fn clear_old_activities(pool: &mut DbPool<'_>) {
   }
 }
 
+/// Clear json of received posts which was stored for debugging
+async fn clear_old_raw_apub(pool: &mut DbPool<'_>, retention_days: i32) {
+  info!("Clearing old raw post json...");
+  let conn = get_conn(pool).await;
+
+  match conn {
+    Ok(mut conn) => {
+      diesel::delete(
+        post_raw_apub::table
+          .filter(post_raw_apub::published.lt(now() - IntervalDsl::days(retention_days))),
+      )
+      .execute(&mut conn)
+      .await
+      .map(|_| info!("Done."))
+      .map_err(|e| error!("Failed to clear old raw post json: {e}"))
+      .ok();
+    }
+    Err(e) => {
+      error!("Failed to get connection from pool: {e}");
+    }
+  }
+}
+
 /// overwrite posts and comments 30d after deletion
 async fn overwrite_deleted_posts_and_comments(pool: &mut DbPool<'_>) {
   info!("Overwriting deleted posts...");